@@ -0,0 +1,471 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Checking how a [MidiData] will play on different synths, see
+//! [check_compatibility].
+//!
+//! Only what SoundPalette knows about is checked: there are no tone lists, so
+//! e.g. a GS variation tone that one Sound Canvas has and another lacks isn't
+//! noticed.
+
+use crate::midi::{AbsoluteTime, ChannelMessage, ChannelMessageKind, MidiData};
+use crate::state::{dt1_writes, gs_model, gs_part_address, parse_reset, Reset, SynthState};
+use crate::sysex::roland::{
+    look_up_parameter, DeviceId, ModelId, ParsedRolandSysExBody, ParsedRolandSysExCommand,
+    DV_ID_BROADCAST,
+};
+use crate::sysex::{parse_sysex, MaybeParsed, ParsedSysExBody};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// How much of Roland GS a [Device] receives.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GsSupport {
+    None,
+    /// Only the GS reset.
+    ResetOnly,
+    Full,
+}
+
+/// A synth that [check_compatibility] can check for.
+#[derive(Debug)]
+pub struct Device {
+    /// Short name for the command line.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub gs: GsSupport,
+    /// Roland model IDs, other than GS, that it receives DT1s for.
+    pub model_ids: &'static [ModelId<'static>],
+    /// The models named in parameter names like "Rx. BANK SELECT
+    /// [SC-55mkII+]" that it counts as.
+    pub tags: &'static [&'static str],
+    /// Maximum number of voices.
+    pub polyphony: u8,
+    /// Whether it receives bank select for melodic parts.
+    pub bank_select: bool,
+    /// Program numbers of its drum sets, if known.
+    pub drum_sets: Option<&'static [u8]>,
+}
+
+/// The GS drum sets on the SC-55 and SC-55mkII: STANDARD, ROOM, POWER,
+/// ELECTRONIC, TR-808, JAZZ, BRUSH, ORCHESTRA, SFX and CM-64/32L.
+const SC_55_DRUM_SETS: &[u8] = &[0, 8, 16, 24, 25, 32, 40, 48, 56, 127];
+
+/// The devices [check_compatibility] knows about.
+pub const DEVICES: &[Device] = &[
+    Device {
+        id: "gm",
+        name: "General MIDI only",
+        gs: GsSupport::None,
+        model_ids: &[],
+        tags: &[],
+        // The minimum General MIDI System Level 1 requires.
+        polyphony: 24,
+        bank_select: false,
+        drum_sets: Some(&[0]),
+    },
+    Device {
+        id: "sc-55",
+        name: "Roland SC-55",
+        gs: GsSupport::Full,
+        model_ids: &[&[0x45]],
+        tags: &[],
+        polyphony: 24,
+        bank_select: true,
+        drum_sets: Some(SC_55_DRUM_SETS),
+    },
+    Device {
+        id: "sc-55mkii",
+        name: "Roland SC-55mkII",
+        gs: GsSupport::Full,
+        model_ids: &[&[0x45]],
+        tags: &["SC-55mkII"],
+        polyphony: 28,
+        bank_select: true,
+        drum_sets: Some(SC_55_DRUM_SETS),
+    },
+    Device {
+        id: "sc-7",
+        name: "Roland SC-7",
+        gs: GsSupport::ResetOnly,
+        model_ids: &[&[0x56]],
+        tags: &[],
+        polyphony: 28,
+        bank_select: true,
+        drum_sets: None,
+    },
+];
+
+/// Something in a [MidiData] that won't work on a [Device].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Incompatibility {
+    /// The SysEx at this index in [MidiData::other_events] is ignored by the
+    /// device, because it doesn't understand it.
+    IgnoredSysEx { index: usize },
+    /// The SysEx at this index is for a device ID other than the default one.
+    OtherDeviceId { index: usize, device_id: DeviceId },
+    /// The DT1 at this index sets a GS parameter that's only on later models.
+    UnsupportedParameter { index: usize, address: Vec<u8> },
+    /// The DT1 at this index reserves more voices than the device has.
+    TooManyVoices { index: usize, voices: u32 },
+    /// A program change on a melodic part has a bank select the device won't
+    /// receive, so it plays the capital tone.
+    BankSelectIgnored {
+        channel: u8,
+        bank_select: u8,
+        program: u8,
+    },
+    /// A program change on a rhythm part is for a drum set the device doesn't
+    /// have.
+    NoSuchDrumSet { channel: u8, program: u8 },
+}
+impl Incompatibility {
+    /// The index of the SysEx in [MidiData::other_events] this is about, if
+    /// any.
+    pub fn sysex_index(&self) -> Option<usize> {
+        match *self {
+            Incompatibility::IgnoredSysEx { index }
+            | Incompatibility::OtherDeviceId { index, .. }
+            | Incompatibility::UnsupportedParameter { index, .. }
+            | Incompatibility::TooManyVoices { index, .. } => Some(index),
+            Incompatibility::BankSelectIgnored { .. } | Incompatibility::NoSuchDrumSet { .. } => {
+                None
+            }
+        }
+    }
+}
+impl Display for Incompatibility {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Incompatibility::IgnoredSysEx { .. } => write!(f, "SysEx is ignored"),
+            Incompatibility::OtherDeviceId { device_id, .. } => write!(
+                f,
+                "SysEx is ignored: it's for device ID {:02X}h, not the default",
+                device_id
+            ),
+            Incompatibility::UnsupportedParameter { ref address, .. } => {
+                match look_up_parameter(gs_model(), address) {
+                    (Some((block_name, _)), Some(param)) => {
+                        write!(f, "{} § {} is not supported", block_name, param.name)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Incompatibility::TooManyVoices { voices, .. } => {
+                write!(f, "VOICE RESERVE adds up to {} voices, too many", voices)
+            }
+            Incompatibility::BankSelectIgnored {
+                channel,
+                bank_select,
+                program,
+            } => write!(
+                f,
+                "Channel {}: bank select {} is ignored, so Program Change #{} plays the capital tone",
+                channel + 1,
+                bank_select,
+                program as u16 + 1
+            ),
+            Incompatibility::NoSuchDrumSet { channel, program } => write!(
+                f,
+                "Channel {}: there is no drum set for Program Change #{}",
+                channel + 1,
+                program as u16 + 1
+            ),
+        }
+    }
+}
+
+/// An [Incompatibility] at a time, for a device, see [check_compatibility].
+#[derive(Debug)]
+pub struct Finding {
+    pub time: AbsoluteTime,
+    pub device: &'static Device,
+    pub incompatibility: Incompatibility,
+}
+
+/// The model named in a parameter name like "Rx. BANK SELECT [SC-55mkII+]".
+fn required_model(param_name: &str) -> Option<&str> {
+    param_name.rsplit_once('[')?.1.strip_suffix("+]")
+}
+
+/// Check the SysEx at `index`: whether `device` receives it, and if so, what
+/// it doesn't support in it.
+fn check_sysex(
+    device: &'static Device,
+    index: usize,
+    sysex: &[u8],
+    state: &mut SynthState,
+) -> Vec<Incompatibility> {
+    let Ok(parsed) = parse_sysex(sysex) else {
+        return Vec::new();
+    };
+    if let Some((reset, _)) = parse_reset(sysex) {
+        if reset == Reset::GsReset && device.gs == GsSupport::None {
+            return vec![Incompatibility::IgnoredSysEx { index }];
+        }
+        state.apply_sysex(sysex);
+        return Vec::new();
+    }
+    let (device_id, model_id, address, data) = match parsed.content {
+        MaybeParsed::Parsed(ParsedSysExBody::Universal(_)) => {
+            state.apply_sysex(sysex);
+            return Vec::new();
+        }
+        MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+            device_id,
+            model_id,
+            command:
+                MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                    address,
+                    data,
+                    valid_checksum: true,
+                    ..
+                }),
+            ..
+        })) => (device_id, model_id, address, data),
+        _ => return vec![Incompatibility::IgnoredSysEx { index }],
+    };
+
+    let gs = gs_model();
+    let receives = if model_id == gs.model_id {
+        device.gs == GsSupport::Full
+    } else {
+        device.model_ids.contains(&model_id)
+    };
+    if !receives {
+        return vec![Incompatibility::IgnoredSysEx { index }];
+    }
+    if device_id != state.device_id && device_id != DV_ID_BROADCAST {
+        return vec![Incompatibility::OtherDeviceId { index, device_id }];
+    }
+    state.apply_sysex(sysex);
+    if model_id != gs.model_id {
+        return Vec::new();
+    }
+
+    let mut incompatibilities = Vec::new();
+    for (address, _) in dt1_writes(gs, address, data) {
+        let (_, Some(param)) = look_up_parameter(gs, &address) else {
+            continue;
+        };
+        let Some(model) = required_model(param.name) else {
+            continue;
+        };
+        if !device.tags.contains(&model) {
+            incompatibilities.push(Incompatibility::UnsupportedParameter { index, address });
+        }
+    }
+    if let Some(voice_reserve) = state.get(gs, &[0x40, 0x01, 0x10], 0x10) {
+        let voices = voice_reserve.iter().map(|&voices| voices as u32).sum();
+        let touched = dt1_writes(gs, address, data)
+            .iter()
+            .any(|(address, _)| matches!(address[..], [0x40, 0x01, 0x10..=0x1F]));
+        if touched && voices > device.polyphony.into() {
+            incompatibilities.push(Incompatibility::TooManyVoices { index, voices });
+        }
+    }
+    incompatibilities
+}
+
+/// Check a program change: whether `device` has the tone or drum set, after
+/// the message has been applied to `state`. `bank_select` is the channel's
+/// last bank select.
+fn check_program_change(
+    device: &'static Device,
+    message: &ChannelMessage,
+    bank_select: u8,
+    state: &SynthState,
+) -> Vec<Incompatibility> {
+    let ChannelMessageKind::ProgramChange(program) = message.kind else {
+        return Vec::new();
+    };
+    let channel = message.channel;
+    let gs = gs_model();
+    let mut incompatibilities = Vec::new();
+    for part_idx in 0..16 {
+        let get = |suffix| state.get(gs, &gs_part_address(part_idx, false, suffix), 1);
+        if get(0x02) != Some(vec![channel]) {
+            continue;
+        }
+        let incompatibility = if get(0x15) != Some(vec![0x00]) {
+            match device.drum_sets {
+                Some(drum_sets) if !drum_sets.contains(&program) => {
+                    Incompatibility::NoSuchDrumSet { channel, program }
+                }
+                _ => continue,
+            }
+        } else if bank_select != 0x00
+            && (!device.bank_select
+                || state.get(gs, &gs_part_address(part_idx, false, 0x00), 1)
+                    != Some(vec![bank_select]))
+        {
+            Incompatibility::BankSelectIgnored {
+                channel,
+                bank_select,
+                program,
+            }
+        } else {
+            continue;
+        };
+        if !incompatibilities.contains(&incompatibility) {
+            incompatibilities.push(incompatibility);
+        }
+    }
+    incompatibilities
+}
+
+/// An event to replay, see [check_compatibility].
+enum Event<'a> {
+    /// The SysEx at this index in [MidiData::other_events].
+    SysEx(usize),
+    Channel(&'a ChannelMessage),
+}
+
+/// Check how `data` will play on each of `devices`, assuming each one has its
+/// default device ID and starts from its defaults. A problem with a program
+/// change is only reported the first time for each device.
+///
+/// SysExes and channel messages are replayed in order, as by
+/// [SynthState::replay], with only the SysExes each device receives.
+pub fn check_compatibility(data: &MidiData, devices: &[&'static Device]) -> Vec<Finding> {
+    let mut events: Vec<(AbsoluteTime, Event)> = data
+        .other_events
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, bytes))| bytes.first() == Some(&0xF0))
+        .map(|(index, &(time, _))| (time, Event::SysEx(index)))
+        .chain(
+            data.channel_messages
+                .iter()
+                .map(|(time, message)| (*time, Event::Channel(message))),
+        )
+        .collect();
+    // SysExes come before channel messages at the same time.
+    events.sort_by_key(|(time, event)| (*time, matches!(event, Event::Channel(_))));
+
+    let mut findings = Vec::new();
+    for &device in devices {
+        let mut state = SynthState::new(0x10);
+        let mut bank_selects = [0u8; 16];
+        let mut reported: Vec<Incompatibility> = Vec::new();
+        for (time, event) in &events {
+            let incompatibilities = match *event {
+                Event::SysEx(index) => {
+                    check_sysex(device, index, &data.other_events[index].1, &mut state)
+                }
+                Event::Channel(message) => {
+                    let channel = message.channel as usize;
+                    if let ChannelMessageKind::ControlChange { control: 0, value } = message.kind {
+                        bank_selects[channel] = value;
+                    }
+                    state.apply_channel_message(message);
+                    check_program_change(device, message, bank_selects[channel], &state)
+                }
+            };
+            for incompatibility in incompatibilities {
+                if incompatibility.sysex_index().is_none() {
+                    if reported.contains(&incompatibility) {
+                        continue;
+                    }
+                    reported.push(incompatibility.clone());
+                }
+                findings.push(Finding {
+                    time: *time,
+                    device,
+                    incompatibility,
+                });
+            }
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+#[test]
+fn test_check_compatibility() {
+    use crate::midi::Division;
+    use crate::state::GS_RESET;
+    use crate::sysex::roland::{dt1, MODELS};
+    use crate::sysex::yamaha::XG_SYSTEM_ON;
+
+    let gs = gs_model();
+    let sc_7 = MODELS
+        .iter()
+        .find(|model| model.model_id == [0x56])
+        .unwrap();
+    let message = |channel, kind| ChannelMessage { channel, kind };
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            (
+                30,
+                message(
+                    0,
+                    ChannelMessageKind::ControlChange {
+                        control: 0,
+                        value: 8,
+                    },
+                ),
+            ),
+            (30, message(0, ChannelMessageKind::ProgramChange(5))),
+            // TR-808 + 1 isn't a drum set.
+            (40, message(9, ChannelMessageKind::ProgramChange(26))),
+            (50, message(0, ChannelMessageKind::ProgramChange(5))),
+        ],
+        other_events: vec![
+            (0, GS_RESET.to_vec()),
+            // Part 1 Rx. BANK SELECT = ON
+            (10, dt1(gs, 0x10, &[0x40, 0x11, 0x23], &[0x01])),
+            // REVERB LEVEL = 64
+            (20, dt1(sc_7, 0x10, &[0x00, 0x00, 0x01], &[0x40])),
+            (25, XG_SYSTEM_ON.to_vec()),
+        ],
+    };
+
+    let devices: Vec<_> = DEVICES.iter().collect();
+    let findings: Vec<_> = check_compatibility(&data, &devices)
+        .into_iter()
+        .map(|finding| {
+            (
+                finding.device.id,
+                finding.time,
+                finding.incompatibility.to_string(),
+            )
+        })
+        .collect();
+    let ignored = "SysEx is ignored".to_string();
+    let no_drum_set = "Channel 10: there is no drum set for Program Change #27".to_string();
+    assert_eq!(
+        findings,
+        &[
+            ("gm", 0, ignored.clone()),
+            ("gm", 10, ignored.clone()),
+            ("gm", 20, ignored.clone()),
+            ("gm", 25, ignored.clone()),
+            (
+                "gm",
+                30,
+                "Channel 1: bank select 8 is ignored, so Program Change #6 plays the capital tone"
+                    .to_string()
+            ),
+            ("gm", 40, no_drum_set.clone()),
+            (
+                "sc-55",
+                10,
+                "Patch parameters, Part 1 § Rx. BANK SELECT [SC-55mkII+] is not supported"
+                    .to_string()
+            ),
+            ("sc-55", 20, ignored.clone()),
+            ("sc-55", 25, ignored.clone()),
+            ("sc-55", 40, no_drum_set.clone()),
+            ("sc-55mkii", 20, ignored.clone()),
+            ("sc-55mkii", 25, ignored.clone()),
+            ("sc-55mkii", 40, no_drum_set.clone()),
+            ("sc-7", 10, ignored.clone()),
+            ("sc-7", 25, ignored.clone()),
+        ]
+    );
+}
@@ -0,0 +1,421 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Guessing which synth a [MidiData] was written for, see [guess_target].
+//!
+//! This is a matter of weighing up clues, since nothing in a MIDI file says
+//! what it's for. A reset SysEx is the strongest clue, but many files send
+//! more than one kind, and many send none at all.
+
+use crate::midi::{AbsoluteTime, ChannelMessageKind, MidiData};
+use crate::nrpn::{decode_data_entries, ParameterNumber};
+use crate::sysex::roland::{ParsedRolandSysExBody, ParsedRolandSysExCommand};
+use crate::sysex::universal::{
+    ParsedUniversalSysExBody, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_2_SYSTEM_ON,
+    SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON,
+};
+use crate::sysex::yamaha::ParsedYamahaSysExBody;
+use crate::sysex::{parse_sysex, MaybeParsed, ParsedSysExBody};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A kind of synth that [guess_target] can guess.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    Gm,
+    Gm2,
+    /// Roland GS, as on the SC-55.
+    Gs,
+    /// Roland GS with the SC-88's extensions.
+    Sc88,
+    Xg,
+    Mt32,
+}
+impl Target {
+    /// The target that this one extends, if any. Evidence for that target
+    /// counts for this one too, as long as there's some of its own.
+    fn base(self) -> Option<Target> {
+        match self {
+            Target::Gm2 => Some(Target::Gm),
+            Target::Sc88 => Some(Target::Gs),
+            _ => None,
+        }
+    }
+}
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Target::Gm => write!(f, "General MIDI"),
+            Target::Gm2 => write!(f, "General MIDI 2"),
+            Target::Gs => write!(f, "Roland GS / SC-55"),
+            Target::Sc88 => write!(f, "Roland SC-88"),
+            Target::Xg => write!(f, "Yamaha XG"),
+            Target::Mt32 => write!(f, "Roland MT-32"),
+        }
+    }
+}
+
+/// A clue found by [guess_target]. Repeats of the same clue are counted, but
+/// only add to the score once, so that e.g. a long run of DT1s doesn't outweigh
+/// a reset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Evidence {
+    /// The time of the first occurrence.
+    pub time: AbsoluteTime,
+    pub count: usize,
+    pub weight: u32,
+    pub description: String,
+}
+
+/// A [Target] and the [Evidence] for it, see [guess_target].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Guess {
+    pub target: Target,
+    pub score: u32,
+    pub evidence: Vec<Evidence>,
+}
+
+/// The evidence found so far, by target.
+#[derive(Default)]
+struct Clues(Vec<(Target, Evidence)>);
+impl Clues {
+    fn add(&mut self, target: Target, time: AbsoluteTime, weight: u32, description: &str) {
+        if let Some((_, evidence)) = self
+            .0
+            .iter_mut()
+            .find(|(t, evidence)| *t == target && evidence.description == description)
+        {
+            evidence.time = evidence.time.min(time);
+            evidence.count += 1;
+        } else {
+            self.0.push((
+                target,
+                Evidence {
+                    time,
+                    count: 1,
+                    weight,
+                    description: description.to_string(),
+                },
+            ));
+        }
+    }
+
+    fn for_target(&self, target: Target) -> impl Iterator<Item = &Evidence> {
+        self.0
+            .iter()
+            .filter(move |&&(t, _)| t == target)
+            .map(|(_, evidence)| evidence)
+    }
+}
+
+/// Add the clues from a SysEx, and return whether it's a reset of some kind.
+fn add_sysex_clues(clues: &mut Clues, time: AbsoluteTime, sysex: &[u8]) -> bool {
+    let Ok(parsed) = parse_sysex(sysex) else {
+        return false;
+    };
+    let MaybeParsed::Parsed(body) = parsed.content else {
+        return false;
+    };
+    match body {
+        ParsedSysExBody::Universal(ParsedUniversalSysExBody {
+            real_time: false,
+            sub_id1: SI1_NRT_GENERAL_MIDI,
+            sub_id2,
+            ..
+        }) => match sub_id2 {
+            SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON => {
+                clues.add(Target::Gm, time, 20, "General MIDI System On");
+                return true;
+            }
+            SI2_NRT_GM_GENERAL_MIDI_2_SYSTEM_ON => {
+                clues.add(Target::Gm2, time, 20, "General MIDI 2 System On");
+                return true;
+            }
+            _ => (),
+        },
+        ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+            model_id,
+            model_name,
+            command,
+            ..
+        }) => {
+            let address = match command {
+                MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 { address, .. }) => address,
+                _ => &[][..],
+            };
+            match (model_id, address) {
+                ([0x42], [0x40, 0x00, 0x7F]) => {
+                    clues.add(Target::Gs, time, 20, "GS reset");
+                    return true;
+                }
+                // SYSTEM MODE SET, which switches between single and double
+                // module mode.
+                ([0x42], [0x00, 0x00, 0x7F]) => {
+                    clues.add(Target::Sc88, time, 20, "SC-88 system mode set")
+                }
+                ([0x42], [0x50, ..]) => clues.add(
+                    Target::Sc88,
+                    time,
+                    10,
+                    "GS DT1 for the SC-88's second block of parts",
+                ),
+                ([0x42], _) => clues.add(Target::Gs, time, 10, "GS SysEx"),
+                // The SC-55's own model ID, used for the display.
+                ([0x45], _) => clues.add(Target::Gs, time, 10, "SC-55 SysEx"),
+                // The LA synth model ID shared by the MT-32, CM-32L and
+                // CM-64.
+                ([0x16], _) => clues.add(Target::Mt32, time, 30, "MT-32 SysEx"),
+                _ => clues.add(
+                    Target::Gs,
+                    time,
+                    5,
+                    &format!("Roland {} SysEx", model_name.unwrap_or("(unknown model)")),
+                ),
+            }
+        }
+        ParsedSysExBody::Yamaha(ParsedYamahaSysExBody::XgParameterChange {
+            address: [0x00, 0x00, 0x7E],
+            data: [0x00],
+            ..
+        }) => {
+            clues.add(Target::Xg, time, 20, "XG System On");
+            return true;
+        }
+        ParsedSysExBody::Yamaha(ParsedYamahaSysExBody::XgParameterChange { .. }) => {
+            clues.add(Target::Xg, time, 10, "XG parameter change")
+        }
+        _ => (),
+    }
+    false
+}
+
+/// Guess which kind of synth `data` was written for, from the resets and other
+/// SysExes it sends, its bank selects, how it uses the drum channel, and which
+/// RPNs and NRPNs it uses. The result has a [Guess] for each [Target] that
+/// there is some evidence for, most likely first.
+pub fn guess_target(data: &MidiData) -> Vec<Guess> {
+    let mut clues = Clues::default();
+
+    let mut any_sysex = false;
+    let mut any_reset = false;
+    for &(time, ref event) in &data.other_events {
+        any_sysex |= event.first() == Some(&0xF0);
+        any_reset |= add_sysex_clues(&mut clues, time, event);
+    }
+
+    let mut any_bank_select = false;
+    let mut channels_with_notes = 0u16;
+    let mut first_note = AbsoluteTime::MAX;
+    for &(time, ref message) in &data.channel_messages {
+        let drum_channel = message.channel == 9;
+        match message.kind {
+            ChannelMessageKind::NoteOn { velocity, .. } if velocity > 0 => {
+                channels_with_notes |= 1 << message.channel;
+                first_note = first_note.min(time);
+                if drum_channel {
+                    clues.add(Target::Gm, time, 2, "Drums on channel 10");
+                }
+            }
+            ChannelMessageKind::ControlChange {
+                control: 0,
+                value: msb,
+            } if msb != 0 => {
+                any_bank_select = true;
+                match msb {
+                    0x78 | 0x79 => {
+                        clues.add(Target::Gm2, time, 10, "GM2 bank select (MSB 78h or 79h)")
+                    }
+                    0x40 | 0x7E if !drum_channel => clues.add(
+                        Target::Xg,
+                        time,
+                        10,
+                        "XG bank select for SFX voices or kits (MSB 40h or 7Eh)",
+                    ),
+                    0x01..=0x3F if !drum_channel => clues.add(
+                        Target::Gs,
+                        time,
+                        5,
+                        "GS variation tone bank select (MSB 01h–3Fh)",
+                    ),
+                    _ => (),
+                }
+            }
+            ChannelMessageKind::ControlChange {
+                control: 32,
+                value: lsb,
+            } if lsb != 0 => {
+                any_bank_select = true;
+                match lsb {
+                    0x01..=0x03 => clues.add(
+                        Target::Sc88,
+                        time,
+                        10,
+                        "SC-88 map select (bank select LSB 01h–03h)",
+                    ),
+                    _ => clues.add(Target::Xg, time, 5, "XG variation bank select (LSB 04h+)"),
+                }
+            }
+            ChannelMessageKind::ProgramChange(program) if drum_channel && program != 0 => {
+                clues.add(Target::Gs, time, 3, "Drum set change on channel 10");
+                clues.add(Target::Xg, time, 3, "Drum kit change on channel 10");
+            }
+            _ => (),
+        }
+    }
+
+    for entry in decode_data_entries(data) {
+        let time = entry.time;
+        match entry.number {
+            ParameterNumber::Registered(0x00, 0x00..=0x02) => clues.add(
+                Target::Gm,
+                time,
+                2,
+                "General MIDI RPN (pitch bend sensitivity or tuning)",
+            ),
+            ParameterNumber::Registered(0x00, 0x05) => clues.add(
+                Target::Gm2,
+                time,
+                10,
+                "GM2 RPN 0005h (modulation depth range)",
+            ),
+            ParameterNumber::NonRegistered(0x01 | 0x18..=0x1F, _) => {
+                clues.add(Target::Gs, time, 3, "NRPN shared by GS and XG");
+                clues.add(Target::Xg, time, 3, "NRPN shared by GS and XG");
+            }
+            ParameterNumber::NonRegistered(0x14..=0x17, _) => clues.add(
+                Target::Xg,
+                time,
+                5,
+                "XG drum filter or EG NRPN (MSB 14h–17h)",
+            ),
+            _ => (),
+        }
+    }
+
+    if !any_sysex && !any_bank_select && channels_with_notes != 0 {
+        clues.add(Target::Gm, first_note, 5, "No SysEx or bank select");
+    }
+    // The MT-32 receives on channels 2–10 by default, and has no reset.
+    if !any_reset && channels_with_notes != 0 && channels_with_notes & !0b11_1111_1110 == 0 {
+        clues.add(Target::Mt32, first_note, 5, "Notes only on channels 2–10");
+    }
+
+    let mut guesses: Vec<Guess> = Vec::new();
+    for target in [
+        Target::Gm,
+        Target::Gm2,
+        Target::Gs,
+        Target::Sc88,
+        Target::Xg,
+        Target::Mt32,
+    ] {
+        let mut evidence: Vec<Evidence> = clues.for_target(target).cloned().collect();
+        if evidence.is_empty() {
+            continue;
+        }
+        if let Some(base) = target.base() {
+            evidence.extend(clues.for_target(base).cloned());
+        }
+        evidence.sort_by(|a, b| b.weight.cmp(&a.weight).then(a.time.cmp(&b.time)));
+        guesses.push(Guess {
+            target,
+            score: evidence.iter().map(|evidence| evidence.weight).sum(),
+            evidence,
+        });
+    }
+    guesses.sort_by(|a, b| b.score.cmp(&a.score).then(a.target.cmp(&b.target)));
+    guesses
+}
+
+#[cfg(test)]
+#[test]
+fn test_guess_target() {
+    use crate::midi::{cc, ChannelMessage, Division};
+    use crate::state::GS_RESET;
+    use crate::sysex::yamaha::XG_SYSTEM_ON;
+
+    let note = |channel| ChannelMessage {
+        channel,
+        kind: ChannelMessageKind::NoteOn {
+            key: 60,
+            velocity: 100,
+        },
+    };
+    let targets = |data: &MidiData| -> Vec<(Target, u32)> {
+        guess_target(data)
+            .into_iter()
+            .map(|guess| (guess.target, guess.score))
+            .collect()
+    };
+
+    // Nothing but notes.
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![(0, note(0)), (0, note(9)), (10, note(9))],
+        other_events: Vec::new(),
+    };
+    assert_eq!(targets(&data), &[(Target::Gm, 7)]);
+    assert_eq!(
+        guess_target(&data)[0].evidence[1],
+        Evidence {
+            time: 0,
+            count: 2,
+            weight: 2,
+            description: "Drums on channel 10".to_string(),
+        }
+    );
+
+    // GS reset, GS DT1 and SC-88 map select.
+    data.other_events = vec![
+        (0, GS_RESET.to_vec()),
+        (
+            5,
+            vec![
+                0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x22, 0x28, 0x65, 0xF7,
+            ],
+        ),
+    ];
+    data.channel_messages.push((5, cc(0, 32, 2)));
+    assert_eq!(
+        targets(&data),
+        &[(Target::Sc88, 40), (Target::Gs, 30), (Target::Gm, 2)]
+    );
+
+    // Adding an XG System On with some XG bank selects tips it.
+    data.other_events.push((10, XG_SYSTEM_ON.to_vec()));
+    data.other_events.push((
+        10,
+        vec![0xF0, 0x43, 0x10, 0x4C, 0x08, 0x00, 0x07, 0x02, 0xF7],
+    ));
+    data.channel_messages.push((15, cc(1, 0, 0x40)));
+    data.channel_messages.push((15, cc(1, 32, 0x10)));
+    data.channel_messages.push((15, cc(2, 0, 0x7E)));
+    data.channel_messages.push((15, cc(3, 98, 0x10)));
+    data.channel_messages.push((15, cc(3, 99, 0x14)));
+    data.channel_messages.push((15, cc(3, 6, 0x50)));
+    assert_eq!(
+        targets(&data),
+        &[
+            (Target::Xg, 50),
+            (Target::Sc88, 40),
+            (Target::Gs, 30),
+            (Target::Gm, 2)
+        ]
+    );
+
+    // An MT-32 file.
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![(0, note(1)), (0, note(9))],
+        other_events: vec![(
+            0,
+            vec![
+                0xF0, 0x41, 0x10, 0x16, 0x12, 0x10, 0x00, 0x01, 0x00, 0x6F, 0xF7,
+            ],
+        )],
+    };
+    assert_eq!(targets(&data), &[(Target::Mt32, 35), (Target::Gm, 2)]);
+}
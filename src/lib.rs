@@ -10,8 +10,14 @@
 // These are internal interfaces and the safety properties are usually obvious.
 #![allow(clippy::missing_safety_doc)]
 
+pub mod compat;
+pub mod guess;
+pub mod lint;
 pub mod midi;
+pub mod nrpn;
+pub mod state;
 pub mod sysex;
+pub mod transform;
 pub mod ui;
 pub mod wasm_ffi;
 
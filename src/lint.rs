@@ -0,0 +1,533 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Finding problems with the SysExes in a [MidiData], see [lint_sysexes].
+//!
+//! Most of these aren't outright errors, but they're usually mistakes, or at
+//! least wasted time on the synth's end: SysExes that have no effect because
+//! of what comes after them, or that are sent too soon after a reset.
+
+use crate::midi::{
+    format_bytes, AbsoluteTime, ChannelMessage, ChannelMessageKind, MidiData, TimeConverter,
+};
+use crate::state::{dt1_writes, parse_reset, SynthState};
+use crate::sysex::roland::{
+    DeviceId, ParsedRolandSysExBody, ParsedRolandSysExCommand, DV_ID_BROADCAST, MODELS,
+};
+use crate::sysex::{parse_sysex, MaybeParsed, ParsedSysExBody};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// SC-55mkII and SC-7 manuals both say a GM or GS reset takes about 50ms to
+/// complete, see also [crate::ui::add_sysex_to_collection].
+pub const RESET_TIME_MS: f64 = 50.0;
+
+/// A problem with a SysEx, see [lint_sysexes].
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// A Roland SysEx's checksum is wrong, so the synth will ignore it.
+    BadChecksum,
+    /// A DT1's data ends partway through a parameter, or runs on into addresses
+    /// with no known parameter. Writing several whole consecutive parameters
+    /// at once is fine.
+    WrongSize,
+    /// A DT1 only writes values that are already in effect.
+    Redundant,
+    /// Everything a DT1 writes is overwritten by later SysExes before any
+    /// notes play. The time is that of the SysEx that finishes the job.
+    Overwritten { time: AbsoluteTime },
+    /// Everything a DT1 writes is wiped by a reset before any notes play.
+    WipedByReset { time: AbsoluteTime },
+    /// A Roland SysEx is for a different model than most of the others.
+    OtherModel {
+        model_id: Vec<u8>,
+        usual_model_id: Vec<u8>,
+    },
+    /// A SysEx is sent before the synth has had time to finish a reset.
+    TooSoonAfterReset { time: AbsoluteTime, ms: f64 },
+}
+
+fn model_name(model_id: &[u8]) -> impl Display + '_ {
+    struct ModelName<'a>(&'a [u8]);
+    impl Display for ModelName<'_> {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            match MODELS.iter().find(|model| model.model_id == self.0) {
+                Some(model_info) => write!(f, "{}", model_info.name),
+                None => write!(f, "model {}", format_bytes(self.0)),
+            }
+        }
+    }
+    ModelName(model_id)
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Problem::BadChecksum => write!(f, "Bad checksum, the synth will ignore this"),
+            Problem::WrongSize => write!(f, "Data is the wrong size for the parameter"),
+            Problem::Redundant => write!(f, "Redundant, the values are already in effect"),
+            Problem::Overwritten { time } => {
+                write!(f, "Overwritten by time {} before any notes play", time)
+            }
+            Problem::WipedByReset { time } => write!(
+                f,
+                "Wiped by the reset at time {} before any notes play",
+                time
+            ),
+            Problem::OtherModel {
+                model_id,
+                usual_model_id,
+            } => write!(
+                f,
+                "For {}, but the other SysExes are for {}",
+                model_name(model_id),
+                model_name(usual_model_id)
+            ),
+            Problem::TooSoonAfterReset { time, ms } => write!(
+                f,
+                "Only {:.1}ms after the reset at time {}, which takes about {}ms",
+                ms, time, RESET_TIME_MS
+            ),
+        }
+    }
+}
+
+/// Record that an address `writer` was the last writer of has been written
+/// again, and report `problem` if that was the last of them and no notes
+/// played in between.
+fn overwrite(
+    lints: &mut Vec<Lint>,
+    data: &MidiData,
+    live_counts: &mut [usize],
+    heard: &mut [bool],
+    writer: usize,
+    notes_played: bool,
+    problem: Problem,
+) {
+    heard[writer] |= notes_played;
+    live_counts[writer] -= 1;
+    if live_counts[writer] == 0 && !heard[writer] {
+        lints.push(Lint {
+            index: writer,
+            time: data.other_events[writer].0,
+            problem,
+        });
+    }
+}
+
+/// The device IDs that the Roland SysExes in `data` are sent to, other than
+/// the broadcast ID, in increasing order. If there are none, 10h is assumed.
+pub fn device_ids(data: &MidiData) -> Vec<DeviceId> {
+    let mut device_ids: Vec<DeviceId> = data
+        .other_events
+        .iter()
+        .filter_map(|(_, bytes)| match parse_sysex(bytes).ok()?.content {
+            MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                device_id,
+                ..
+            })) if device_id != DV_ID_BROADCAST => Some(device_id),
+            _ => None,
+        })
+        .collect();
+    device_ids.sort();
+    device_ids.dedup();
+    if device_ids.is_empty() {
+        device_ids.push(0x10);
+    }
+    device_ids
+}
+
+/// A [Problem] with the SysEx at `index` in [MidiData::other_events].
+#[derive(Debug, PartialEq)]
+pub struct Lint {
+    pub index: usize,
+    pub time: AbsoluteTime,
+    pub problem: Problem,
+}
+
+/// Find problems with the SysExes in `data`, sorted by time. SysExes and
+/// channel messages are replayed in time order (see [SynthState]), with a
+/// synth for each device ID the Roland SysExes are sent to. Channel messages
+/// go to every synth.
+///
+/// Nothing is assumed about a synth's state before the first reset, so a DT1
+/// is only redundant if a reset, another DT1 or a channel message already set
+/// the same values. A DT1 whose values are all overwritten, by SysExes or
+/// channel messages, is only a problem if no notes play in between.
+pub fn lint_sysexes(data: &MidiData) -> Vec<Lint> {
+    let time_converter = TimeConverter::new(data);
+    let mut note_on_times: Vec<AbsoluteTime> = data
+        .channel_messages
+        .iter()
+        .filter(|(_, message)| {
+            matches!(message.kind, ChannelMessageKind::NoteOn { velocity, .. } if velocity != 0)
+        })
+        .map(|&(time, _)| time)
+        .collect();
+    note_on_times.sort();
+    // Notes at the same time as a SysEx come after it, see SynthState::replay.
+    let notes_between = |from: AbsoluteTime, until: AbsoluteTime| {
+        let i = note_on_times.partition_point(|&time| time < from);
+        note_on_times.get(i).is_some_and(|&time| time < until)
+    };
+
+    let mut order: Vec<usize> = (0..data.other_events.len())
+        .filter(|&index| data.other_events[index].1.first() == Some(&0xF0))
+        .collect();
+    order.sort_by_key(|&index| data.other_events[index].0);
+
+    let mut lints = Vec::new();
+
+    // Roland SysExes for a model other than the most common one.
+    let mut model_counts: BTreeMap<&[u8], usize> = BTreeMap::new();
+    let mut models = Vec::new();
+    for &index in &order {
+        if let Ok(sysex) = parse_sysex(&data.other_events[index].1) {
+            if let MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                model_id,
+                ..
+            })) = sysex.content
+            {
+                *model_counts.entry(model_id).or_default() += 1;
+                models.push((index, model_id));
+            }
+        }
+    }
+    let mut counts: Vec<_> = model_counts
+        .iter()
+        .map(|(&id, &count)| (count, id))
+        .collect();
+    counts.sort_by(|a, b| b.cmp(a));
+    if let [(most, usual_model_id), (second, _), ..] = counts[..] {
+        if most > second {
+            for &(index, model_id) in models.iter().filter(|&&(_, id)| id != usual_model_id) {
+                lints.push(Lint {
+                    index,
+                    time: data.other_events[index].0,
+                    problem: Problem::OtherModel {
+                        model_id: model_id.to_vec(),
+                        usual_model_id: usual_model_id.to_vec(),
+                    },
+                });
+            }
+        }
+    }
+
+    // Each device ID gets its own synth.
+    let mut states: BTreeMap<DeviceId, SynthState> = device_ids(data)
+        .into_iter()
+        .map(|device_id| {
+            let mut state = SynthState::unknown(device_id);
+            state.record_writes();
+            (device_id, state)
+        })
+        .collect();
+    // For each address of each synth that has been written to by a SysEx, the
+    // SysEx that last did so and the byte it wrote.
+    let mut last_writers = BTreeMap::new();
+    // For each SysEx, how many addresses it was the last writer of, and
+    // whether any notes played while it was.
+    let mut live_counts = vec![0usize; data.other_events.len()];
+    let mut heard = vec![false; data.other_events.len()];
+    let mut last_reset = None;
+
+    // SysExes come before channel messages at the same time, as in
+    // SynthState::apply_events().
+    enum Event<'a> {
+        SysEx(usize),
+        Channel(&'a ChannelMessage),
+    }
+    let mut events: Vec<(AbsoluteTime, Event)> = order
+        .iter()
+        .map(|&index| (data.other_events[index].0, Event::SysEx(index)))
+        .chain(
+            data.channel_messages
+                .iter()
+                .map(|(time, message)| (*time, Event::Channel(message))),
+        )
+        .collect();
+    events.sort_by_key(|&(time, ref event)| (time, matches!(event, Event::Channel(_))));
+
+    for (time, event) in events {
+        let index = match event {
+            Event::SysEx(index) => index,
+            Event::Channel(message) => {
+                for (&device_id, state) in &mut states {
+                    state.take_writes();
+                    state.apply_channel_message(message);
+                    for (model_id, address) in state.take_writes() {
+                        let key = (device_id, model_id, address);
+                        let Some(&(writer, byte)) = last_writers.get(&key) else {
+                            continue;
+                        };
+                        let model_info = MODELS
+                            .iter()
+                            .find(|model| model.model_id == model_id)
+                            .unwrap();
+                        if state.get(model_info, &key.2, 1) == Some(vec![byte]) {
+                            continue;
+                        }
+                        last_writers.remove(&key);
+                        overwrite(
+                            &mut lints,
+                            data,
+                            &mut live_counts,
+                            &mut heard,
+                            writer,
+                            notes_between(data.other_events[writer].0, time),
+                            Problem::Overwritten { time },
+                        );
+                    }
+                }
+                continue;
+            }
+        };
+        let bytes = &data.other_events[index].1;
+
+        if let Some((reset_time, reset_ms)) = last_reset {
+            let ms = time_converter.to_ms(time) - reset_ms;
+            if ms < RESET_TIME_MS {
+                lints.push(Lint {
+                    index,
+                    time,
+                    problem: Problem::TooSoonAfterReset {
+                        time: reset_time,
+                        ms,
+                    },
+                });
+            }
+        }
+
+        if let Some((_, reset_device_id)) = parse_reset(bytes) {
+            let wiped: Vec<_> = last_writers
+                .keys()
+                .filter(|&&(device_id, _, _)| {
+                    reset_device_id == device_id || reset_device_id == DV_ID_BROADCAST
+                })
+                .cloned()
+                .collect();
+            for key in wiped {
+                let (writer, _) = last_writers.remove(&key).unwrap();
+                overwrite(
+                    &mut lints,
+                    data,
+                    &mut live_counts,
+                    &mut heard,
+                    writer,
+                    notes_between(data.other_events[writer].0, time),
+                    Problem::WipedByReset { time },
+                );
+            }
+            for state in states.values_mut() {
+                state.apply_sysex(bytes);
+            }
+            last_reset = Some((time, time_converter.to_ms(time)));
+            continue;
+        }
+
+        let Ok(sysex) = parse_sysex(bytes) else {
+            continue;
+        };
+        let MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+            device_id,
+            model_id,
+            command:
+                MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                    address,
+                    data: dt1_data,
+                    valid_checksum,
+                    invalid_size,
+                    ..
+                }),
+            ..
+        })) = sysex.content
+        else {
+            continue;
+        };
+        // A DT1 that writes several whole parameters (see BlockWrite) isn't
+        // counted as the wrong size by the parser.
+        if invalid_size {
+            lints.push(Lint {
+                index,
+                time,
+                problem: Problem::WrongSize,
+            });
+        }
+        if !valid_checksum {
+            lints.push(Lint {
+                index,
+                time,
+                problem: Problem::BadChecksum,
+            });
+            continue;
+        }
+        let Some(&model_info) = MODELS.iter().find(|model| model.model_id == model_id) else {
+            continue;
+        };
+
+        let mut changed_any = false;
+        for (&state_device_id, state) in &mut states {
+            if device_id != state_device_id && device_id != DV_ID_BROADCAST {
+                continue;
+            }
+            for (address, byte) in dt1_writes(model_info, address, dt1_data) {
+                if state.get(model_info, &address, 1) == Some(vec![byte]) {
+                    continue;
+                }
+                changed_any = true;
+                state.set(model_info, &address, &[byte]);
+                let key = (state_device_id, model_info.model_id, address);
+                match last_writers.insert(key, (index, byte)) {
+                    Some((writer, _)) if writer == index => continue,
+                    Some((writer, _)) => overwrite(
+                        &mut lints,
+                        data,
+                        &mut live_counts,
+                        &mut heard,
+                        writer,
+                        notes_between(data.other_events[writer].0, time),
+                        Problem::Overwritten { time },
+                    ),
+                    None => (),
+                }
+                live_counts[index] += 1;
+            }
+        }
+        if !changed_any {
+            lints.push(Lint {
+                index,
+                time,
+                problem: Problem::Redundant,
+            });
+        }
+    }
+    lints.sort_by_key(|lint| (lint.time, lint.index));
+    lints
+}
+
+#[cfg(test)]
+#[test]
+fn test_lint_sysexes() {
+    use crate::midi::{cc, ChannelMessage, Division};
+    use crate::state::{gs_model, GS_RESET};
+    use crate::sysex::roland::dt1;
+
+    // Part 1 REVERB SEND LEVEL = 100, 90 and 90 again
+    let reverb_send_100 = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x22, 0x64, 0x29, 0xF7,
+    ];
+    let reverb_send_90 = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x22, 0x5A, 0x33, 0xF7,
+    ];
+    let mut bad_checksum = reverb_send_90.clone();
+    bad_checksum[9] = 0x00;
+    // SC-7 CHORUS DEPTH = 100, with a second byte for an address that has no
+    // parameter
+    let sc_7 = vec![
+        0xF0, 0x41, 0x10, 0x56, 0x12, 0x00, 0x00, 0x09, 0x64, 0x00, 0x13, 0xF7,
+    ];
+    let note_on = || ChannelMessage {
+        channel: 0,
+        kind: ChannelMessageKind::NoteOn {
+            key: 60,
+            velocity: 100,
+        },
+    };
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![(60, note_on())],
+        other_events: vec![
+            (0, reverb_send_100.clone()),
+            (12, GS_RESET.to_vec()),
+            (18, reverb_send_100),
+            (30, reverb_send_90.clone()),
+            (40, reverb_send_90),
+            (50, bad_checksum),
+            (50, sc_7),
+            (70, GS_RESET.to_vec()),
+        ],
+    };
+
+    // At 120bpm and 120 ticks per quarter note, 6 ticks is 25ms.
+    assert_eq!(
+        lint_sysexes(&data),
+        &[
+            Lint {
+                index: 0,
+                time: 0,
+                problem: Problem::WipedByReset { time: 12 },
+            },
+            Lint {
+                index: 2,
+                time: 18,
+                problem: Problem::TooSoonAfterReset { time: 12, ms: 25.0 },
+            },
+            Lint {
+                index: 2,
+                time: 18,
+                problem: Problem::Overwritten { time: 30 },
+            },
+            Lint {
+                index: 4,
+                time: 40,
+                problem: Problem::Redundant,
+            },
+            Lint {
+                index: 5,
+                time: 50,
+                problem: Problem::BadChecksum,
+            },
+            Lint {
+                index: 6,
+                time: 50,
+                problem: Problem::OtherModel {
+                    model_id: vec![0x56],
+                    usual_model_id: vec![0x42],
+                },
+            },
+            Lint {
+                index: 6,
+                time: 50,
+                problem: Problem::WrongSize,
+            },
+        ]
+    );
+
+    // Channel messages change the state too, and each device ID has its own.
+    let gs = gs_model();
+    let mut gs_reset_11 = GS_RESET.to_vec();
+    gs_reset_11[2] = 0x11;
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            (100, cc(0, 7, 50)),
+            (150, cc(0, 10, 0x40)),
+            (200, note_on()),
+        ],
+        other_events: vec![
+            (0, GS_RESET.to_vec()),
+            (20, gs_reset_11),
+            // PART LEVEL is back to its default after CC#7.
+            (110, dt1(gs, 0x10, &[0x40, 0x11, 0x19], &[100])),
+            // The same REVERB SEND LEVEL for each unit.
+            (120, dt1(gs, 0x10, &[0x40, 0x11, 0x22], &[100])),
+            (130, dt1(gs, 0x11, &[0x40, 0x11, 0x22], &[100])),
+            // PART PANPOT is put back by CC#10.
+            (140, dt1(gs, 0x10, &[0x40, 0x11, 0x1C], &[0x20])),
+            // CHORUS SEND LEVEL and REVERB SEND LEVEL in one DT1 are fine.
+            (160, dt1(gs, 0x10, &[0x40, 0x11, 0x21], &[0x10, 0x64])),
+        ],
+    };
+    assert_eq!(
+        lint_sysexes(&data),
+        &[Lint {
+            index: 5,
+            time: 140,
+            problem: Problem::Overwritten { time: 150 },
+        }]
+    );
+}
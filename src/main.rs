@@ -8,9 +8,21 @@
 // This crate will be called SoundPalette whether Rust likes it or not.
 #![allow(non_snake_case)]
 
-use libSoundPalette::midi::{format_bytes, read_midi, write_midi};
+use libSoundPalette::compat::DEVICES;
+use libSoundPalette::midi::{format_bytes, read_midi, write_midi, AbsoluteTime};
+use libSoundPalette::state::{generate_setup, SynthState};
 use libSoundPalette::sysex::{generate_sysex, SysExGenerator};
-use libSoundPalette::ui::{list_other_events, print_menu, StderrTableStream};
+use libSoundPalette::transform::{
+    convert_gs_to_xg, convert_nrpns_to_sysex, convert_sysex_to_nrpns, optimize_sysexes,
+    translate_gs_to_sc_7, translate_sc_7_to_gs, Curve,
+};
+use libSoundPalette::ui::{
+    add_ramp, add_random_to_collection, add_text_to_collection, annotate_sysex, copy_part_settings,
+    decode_sysex, explain_unit_assignment, list_compatibility, list_data_entries, list_lints,
+    list_other_events, list_parameter_timeline, list_synth_state, list_target_guesses,
+    new_sysex_collection, print_menu, repair_and_list_checksums, repair_sysex, retarget_collection,
+    write_sysexes_as_text, StderrTableStream,
+};
 
 use std::error::Error;
 use std::fs::File;
@@ -23,8 +35,16 @@ SoundPalette CLI tool by hikari_no_yume
 Usage:
 
     SoundPalette arpeggio.mid [-o unarpegg.mid] [-s] [-v]
+    SoundPalette --text setup.txt [-o setup.mid]
+    SoundPalette --randomize choices.txt [--seed <n>] [-o random.mid]
 
-The input file is Standard MIDI File format 0 or format 1.
+The input file is Standard MIDI File format 0 or format 1, or with --text, a
+text file of parameter assignments like:
+
+    gs part 3 \"REVERB SEND LEVEL\" = 40
+
+A guess at what synth the input was written for (General MIDI, GM2, GS, SC-88,
+XG or MT-32) is listed first, with the evidence for it, then the events.
 
 Options:
 
@@ -44,8 +64,150 @@ Options:
     -v
         Verbose mode.
 
+    --text <path>
+        Read SysExes from a text file of parameter assignments instead of a
+        MIDI file. Each SysEx is placed 50ms after the previous one.
+
+    --randomize <path>
+        Like --text, but the text file picks parameters to set to random
+        values rather than assigning them, e.g.
+
+            gs part 3
+            gs part 3 \"PART LEVEL\" = 60..100
+            gs part 3 \"TONE MODIFY*\" keep
+            gs common \"REVERB MACRO\" except \"Delay\" \"Panning Delay\"
+
+        A line without a parameter name picks every parameter in the block.
+
+    --seed <number>
+        The seed for --randomize. The same seed and text always give the
+        same values. If there is none, one is picked and printed.
+
+    --device-id <hex>
+        Change the device ID of every Roland SysEx to <hex>, e.g. 11h for
+        the second unit in a multi-unit setup, or 7Fh for broadcast.
+
+    --ramp <from> <to> <start tick> <end tick>
+        Insert DT1 SysExes that sweep a parameter from one value to another,
+        from <start tick> to <end tick>. <from> and <to> are assignments to
+        the same parameter, as in a --text file, e.g.
+        --ramp 'gs part 5 \"CHORUS SEND LEVEL\" = 0'
+               'gs part 5 \"CHORUS SEND LEVEL\" = 127' 0 7680.
+        Can be given more than once.
+
+    --ramp-curve <curve>
+        The curve for --ramp: linear (the default) or exponential, which
+        makes smaller steps at the low end and bigger ones at the high end.
+
+    --ramp-rate <number>
+        The most DT1s per second for --ramp, 20 by default. There are never
+        more than the synth can process or than would crowd out other
+        messages.
+
+    --copy-part <from> <to> <tick>
+        Copy the settings of GS part <from> onto part <to> as they are at
+        <tick>: the tone, levels, sends, TONE MODIFY, Rx. switches and
+        controller assignments, but not the Rx. CHANNEL. The DT1 SysExes
+        (and bank select and program change) are inserted at <tick>. Use the
+        device ID from --device-id, or 10h by default. Can be given more than
+        once.
+
+    --swap-parts <part> <part> <tick>
+        Like --copy-part, but swap the settings of the two parts.
+
+    --state-at <tick>
+        After listing the events, list the parameters that differ from their
+        defaults at time <tick>, as worked out by replaying the SysExes and
+        channel messages up to then. Use the device ID from --device-id, or
+        10h by default. The drum instrument settings that GS drum instrument
+        NRPNs and drum setup SysExes have made since the drum set was
+        selected are listed after the parameters.
+
+    --timeline
+        After listing the events, list each parameter that changes over the
+        course of the input, as worked out by replaying the SysExes and
+        channel messages, with each value it takes and when, as
+        bar:beat:tick. Use the device ID from --device-id, or 10h by default.
+
+    --list-nrpns
+        After listing the events, list each RPN and NRPN Data Entry, with
+        the equivalent GS part parameter if there is one.
+
+    --nrpns-to-sysex
+        Replace RPNs and NRPNs that have an equivalent GS part parameter
+        with DT1 SysExes, assuming each part receives on its default
+        channel. Use the device ID from --device-id, or 10h by default.
+
+    --sysex-to-nrpns
+        Replace GS DT1 SysExes for part parameters that have an equivalent
+        RPN or NRPN with control changes on the part's default channel.
+
+    --gs-to-sc-7
+        Replace GS DT1 SysExes with the SC-7's own DT1 SysExes where the
+        SC-7 has an equivalent parameter, and list the parameters that
+        don't have one. Those SysExes are left alone.
+
+    --sc-7-to-gs
+        The reverse of --gs-to-sc-7.
+
+    --gs-to-xg
+        Replace the GS reset and GS DT1 SysExes with Yamaha XG equivalents,
+        using the nearest XG reverb, chorus and drum kit types, and list
+        the parameters that have no equivalent or only a near one. SysExes
+        with parameters that have no equivalent are kept. Bank selects and
+        drum kit program changes are converted to XG's banks too.
+
+    --repair-checksums
+        Fix the checksum of every Roland SysEx in the input that has a wrong
+        one, which the synth would otherwise silently ignore, and list the
+        fixes.
+
+    --repair-sysex <hex bytes>
+        Fix the checksum of a single SysEx and print the result, e.g.
+        --repair-sysex \"F0 41 10 42 12 40 00 7F 00 00 F7\".
+
+    --optimize
+        Improve the SysExes in the input: fix checksums, drop redundant or
+        overwritten SysExes, merge DT1s that continue where the one before
+        left off, and space out the SysExes so the synth has time to process
+        them, delaying the first notes if the setup would run under them.
+        Prints a report of what changed.
+
+    --lint
+        After listing the events, list problems with the SysExes, like ones
+        that are overwritten or wiped by a reset before any notes play, or
+        that come less than 50ms after a reset.
+
+    --compatibility <devices>
+        After listing the events, list what won't work on each of the
+        comma-separated <devices>: SysExes that are ignored, unsupported
+        parameters, and program changes whose bank or drum set isn't
+        available. The devices are gm (General MIDI only), sc-55, sc-55mkii
+        and sc-7, or all of them with \"all\".
+
+    --minimal-setup
+        Replace the input with the smallest sequence of SysExes that gets a
+        synth into the same state as the whole input does, starting with a
+        GS reset. Channel messages are dropped, but their effect on the
+        parameters is kept. Use the device ID from --device-id, or 10h by
+        default.
+
+    --to-text
+        Print the SysExes from the input as text to stdout, in a form that
+        can be edited and then read back in with --text. The bytes are
+        preserved exactly, including any errors.
+
     --list-sysex-generators
         List all types of SysEx that can be generated.
+
+    --annotate-sysex <hex bytes>
+        Explain what each byte of a SysEx means, e.g.
+        --annotate-sysex \"F0 41 10 42 12 40 00 7F 00 41 F7\".
+
+    --value-for-unit <assignment>
+        Find the nearest value for a parameter given in a real-world unit,
+        print how far off it is and the SysEx, e.g.
+        --value-for-unit 'gs part 1 controllers \"MOD TVF CUT OFF CONTROL\" = -1200 cents'.
 ";
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -54,7 +216,29 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut in_path = None;
     let mut out_path = None;
+    let mut text_path = None;
+    let mut random_path = None;
+    let mut seed = None;
     let mut verbose = false;
+    let mut to_text = false;
+    let mut device_id = None;
+    let mut state_at = None;
+    let mut ramps = Vec::new();
+    let mut ramp_curve = Curve::Linear;
+    let mut ramp_rate = 20.0;
+    let mut part_copies = Vec::new();
+    let mut timeline = false;
+    let mut minimal_setup = false;
+    let mut lint = false;
+    let mut compatibility = None;
+    let mut optimize = false;
+    let mut repair_checksums = false;
+    let mut list_nrpns = false;
+    let mut nrpns_to_sysex = false;
+    let mut sysex_to_nrpns = false;
+    let mut gs_to_sc_7 = false;
+    let mut sc_7_to_gs = false;
+    let mut gs_to_xg = false;
     while let Some(arg) = args.next() {
         if arg == "-h" || arg == "--help" {
             eprintln!("{}", USAGE);
@@ -73,13 +257,185 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         } else if arg == "-v" {
             verbose = true;
+        } else if arg == "--device-id" {
+            let Some(hex) = args.next() else {
+                return Err("Missing device ID after --device-id".into());
+            };
+            let hex = hex.to_str().ok_or("Device ID is not valid Unicode")?;
+            let hex = hex.strip_suffix(['h', 'H']).unwrap_or(hex);
+            device_id = Some(
+                u8::from_str_radix(hex, 16)
+                    .ok()
+                    .filter(|&device_id| device_id <= 0x7F)
+                    .ok_or("Device ID must be a hex byte from 00h to 7Fh")?,
+            );
+        } else if arg == "--state-at" {
+            let Some(tick) = args.next() else {
+                return Err("Missing time after --state-at".into());
+            };
+            state_at = Some(
+                tick.to_str()
+                    .and_then(|tick| tick.parse::<u32>().ok())
+                    .ok_or("Time must be a whole number of ticks")?,
+            );
+        } else if arg == "--ramp" {
+            let mut ramp_args = Vec::new();
+            for _ in 0..4 {
+                let Some(arg) = args.next() else {
+                    return Err("--ramp needs two assignments and two times".into());
+                };
+                ramp_args.push(
+                    arg.into_string()
+                        .map_err(|_| "Ramp arguments are not valid Unicode")?,
+                );
+            }
+            let [from, to, start, end] = ramp_args.try_into().unwrap();
+            let start = start
+                .parse::<u32>()
+                .map_err(|_| "Time must be a whole number of ticks")?;
+            let end = end
+                .parse::<u32>()
+                .map_err(|_| "Time must be a whole number of ticks")?;
+            ramps.push((from, to, start..=end));
+        } else if arg == "--ramp-curve" {
+            ramp_curve = match args.next().as_ref().and_then(|arg| arg.to_str()) {
+                Some("linear") => Curve::Linear,
+                Some("exponential") => Curve::Exponential,
+                _ => return Err("Ramp curve must be \"linear\" or \"exponential\"".into()),
+            };
+        } else if arg == "--ramp-rate" {
+            ramp_rate = args
+                .next()
+                .as_ref()
+                .and_then(|arg| arg.to_str())
+                .and_then(|arg| arg.parse::<f64>().ok())
+                .ok_or("Missing or invalid number after --ramp-rate")?;
+        } else if arg == "--copy-part" || arg == "--swap-parts" {
+            let mut numbers = Vec::new();
+            for _ in 0..3 {
+                numbers.push(
+                    args.next()
+                        .as_ref()
+                        .and_then(|arg| arg.to_str())
+                        .and_then(|arg| arg.parse::<u32>().ok())
+                        .ok_or("--copy-part and --swap-parts need two parts and a time")?,
+                );
+            }
+            let [a, b, time] = numbers.try_into().unwrap();
+            let (a, b) = (
+                u8::try_from(a).map_err(|_| format!("There is no Part {}", a))?,
+                u8::try_from(b).map_err(|_| format!("There is no Part {}", b))?,
+            );
+            let moves = if arg == "--swap-parts" {
+                vec![(a, b), (b, a)]
+            } else {
+                vec![(a, b)]
+            };
+            part_copies.push((moves, time));
+        } else if arg == "--timeline" {
+            timeline = true;
+        } else if arg == "--list-nrpns" {
+            list_nrpns = true;
+        } else if arg == "--nrpns-to-sysex" {
+            nrpns_to_sysex = true;
+        } else if arg == "--sysex-to-nrpns" {
+            sysex_to_nrpns = true;
+        } else if arg == "--gs-to-sc-7" {
+            gs_to_sc_7 = true;
+        } else if arg == "--sc-7-to-gs" {
+            sc_7_to_gs = true;
+        } else if arg == "--gs-to-xg" {
+            gs_to_xg = true;
+        } else if arg == "--repair-checksums" {
+            repair_checksums = true;
+        } else if arg == "--repair-sysex" {
+            let Some(hex) = args.next() else {
+                return Err("Missing SysEx after --repair-sysex".into());
+            };
+            let hex = hex.to_str().ok_or("SysEx is not valid Unicode")?;
+            let mut output = String::new();
+            let result = repair_sysex(&mut output, hex);
+            eprint!("{}", output);
+            return result.map_err(|()| "Could not decode SysEx".into());
+        } else if arg == "--optimize" {
+            optimize = true;
+        } else if arg == "--lint" {
+            lint = true;
+        } else if arg == "--compatibility" {
+            let Some(ids) = args.next() else {
+                return Err("Missing devices after --compatibility".into());
+            };
+            let ids = ids.to_str().ok_or("Devices are not valid Unicode")?;
+            let mut devices = Vec::new();
+            for id in ids.split(',') {
+                if id == "all" {
+                    devices.extend(DEVICES);
+                } else if let Some(device) = DEVICES.iter().find(|device| device.id == id) {
+                    devices.push(device);
+                } else {
+                    return Err(format!("Unknown device: {:?}", id).into());
+                }
+            }
+            compatibility = Some(devices);
+        } else if arg == "--minimal-setup" {
+            minimal_setup = true;
+        } else if arg == "--to-text" {
+            to_text = true;
+        } else if arg == "--text" {
+            if text_path.is_some() {
+                return Err("Only one text path can be specified".into());
+            }
+            text_path = args.next().map(PathBuf::from);
+            if text_path.is_none() {
+                return Err("Missing text path after --text".into());
+            }
+        } else if arg == "--randomize" {
+            if random_path.is_some() {
+                return Err("Only one randomizer path can be specified".into());
+            }
+            random_path = args.next().map(PathBuf::from);
+            if random_path.is_none() {
+                return Err("Missing randomizer path after --randomize".into());
+            }
+        } else if arg == "--seed" {
+            seed = Some(
+                args.next()
+                    .as_ref()
+                    .and_then(|arg| arg.to_str())
+                    .and_then(|arg| arg.parse::<u64>().ok())
+                    .ok_or("Missing or invalid number after --seed")?,
+            );
         } else if arg == "--list-sysex-generators" {
             print_menu(&generate_sysex(), &|generator: Box<dyn SysExGenerator>| {
-                let mut sysex_bytes = Vec::new();
-                generator.generate(&mut sysex_bytes);
-                eprint!("{}", format_bytes(&sysex_bytes));
+                for (idx, sysex_bytes) in generator.generate_all().iter().enumerate() {
+                    if idx != 0 {
+                        eprint!("; ");
+                    }
+                    eprint!("{}", format_bytes(sysex_bytes));
+                }
             });
             return Ok(());
+        } else if arg == "--annotate-sysex" {
+            let Some(hex) = args.next() else {
+                return Err("Missing SysEx after --annotate-sysex".into());
+            };
+            let hex = hex.to_str().ok_or("SysEx is not valid Unicode")?;
+            let mut error = String::new();
+            let Ok(sysex_bytes) = decode_sysex(&mut error, hex) else {
+                return Err(error.into());
+            };
+            annotate_sysex(&mut StderrTableStream::new(), &sysex_bytes)
+                .map_err(|err| err.to_string())?;
+            return Ok(());
+        } else if arg == "--value-for-unit" {
+            let Some(line) = args.next() else {
+                return Err("Missing assignment after --value-for-unit".into());
+            };
+            let line = line.to_str().ok_or("Assignment is not valid Unicode")?;
+            let mut output = String::new();
+            let result = explain_unit_assignment(&mut output, line);
+            eprint!("{}", output);
+            return result.map_err(|()| "Could not find a value".into());
         } else if in_path.is_none() {
             in_path = Some(PathBuf::from(arg));
         } else {
@@ -87,16 +443,122 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    let Some(in_path) = in_path else {
-        eprintln!("{}", USAGE);
-        return Err("No input path specified".into());
+    let mut data = match (in_path, text_path, random_path) {
+        (Some(in_path), None, None) => read_midi(
+            &mut BufReader::new(File::open(in_path)?),
+            verbose,
+            &mut std::io::stderr(),
+        )?,
+        (None, Some(text_path), None) => {
+            let text = std::fs::read_to_string(text_path)?;
+            let mut data = new_sysex_collection();
+            let mut errors = String::new();
+            let result = add_text_to_collection(&mut errors, &mut data, &text);
+            eprint!("{}", errors);
+            if result.is_err() {
+                return Err("Could not compile text".into());
+            }
+            data
+        }
+        (None, None, Some(random_path)) => {
+            let text = std::fs::read_to_string(random_path)?;
+            let seed = seed.unwrap_or_else(|| {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |time| time.as_nanos() as u64);
+                eprintln!("Seed: {}", seed);
+                seed
+            });
+            let mut data = new_sysex_collection();
+            let mut errors = String::new();
+            if add_random_to_collection(&mut errors, &mut data, &text, seed).is_err() {
+                eprint!("{}", errors);
+                return Err("Could not compile randomizer".into());
+            }
+            data
+        }
+        (None, None, None) => {
+            eprintln!("{}", USAGE);
+            return Err("No input path specified".into());
+        }
+        _ => {
+            return Err(
+                "Only one of a MIDI file, a text file and a randomizer file can be input".into(),
+            );
+        }
     };
 
-    let mut data = read_midi(
-        &mut BufReader::new(File::open(in_path)?),
-        verbose,
-        &mut std::io::stderr(),
-    )?;
+    // Guess from the data as read, before any transform changes it.
+    list_target_guesses(&mut StderrTableStream::new(), &data);
+    eprintln!();
+
+    for (from, to, ticks) in ramps {
+        let mut output = String::new();
+        let result = add_ramp(
+            &mut output,
+            &mut data,
+            &from,
+            &to,
+            ticks,
+            ramp_curve,
+            ramp_rate,
+        );
+        eprint!("{}", output);
+        result.map_err(|()| "Could not add ramp")?;
+    }
+
+    for (moves, time) in part_copies {
+        let mut output = String::new();
+        let result = copy_part_settings(
+            &mut output,
+            &mut data,
+            device_id.unwrap_or(0x10),
+            time,
+            &moves,
+        );
+        eprint!("{}", output);
+        result.map_err(|()| "Could not copy part settings")?;
+    }
+
+    if let Some(device_id) = device_id {
+        let mut output = String::new();
+        let result = retarget_collection(&mut output, &mut data, device_id);
+        eprint!("{}", output);
+        result.map_err(|()| "Could not change the device ID")?;
+    }
+
+    if minimal_setup {
+        let target = SynthState::replay(&data, device_id.unwrap_or(0x10), AbsoluteTime::MAX);
+        data = generate_setup(&target, None);
+    }
+
+    if nrpns_to_sysex {
+        let converted = convert_nrpns_to_sysex(&mut data, device_id.unwrap_or(0x10));
+        eprintln!("Replaced {} RPN/NRPN Data Entry(s) with SysEx.", converted);
+    }
+    if sysex_to_nrpns {
+        let converted = convert_sysex_to_nrpns(&mut data);
+        eprintln!("Replaced {} SysEx(es) with RPN/NRPN.", converted);
+    }
+
+    if gs_to_sc_7 {
+        eprint!("{}", translate_gs_to_sc_7(&mut data));
+    }
+    if sc_7_to_gs {
+        eprint!("{}", translate_sc_7_to_gs(&mut data));
+    }
+    if gs_to_xg {
+        eprint!("{}", convert_gs_to_xg(&mut data));
+    }
+
+    if repair_checksums {
+        repair_and_list_checksums(&mut StderrTableStream::new(), &mut data);
+        eprintln!();
+    }
+
+    if optimize {
+        eprint!("{}", optimize_sysexes(&mut data));
+    }
 
     list_other_events(
         &mut StderrTableStream::new(),
@@ -104,6 +566,46 @@ fn main() -> Result<(), Box<dyn Error>> {
         /* with_time_and_kind: */ true,
     );
 
+    if let Some(until) = state_at {
+        eprintln!();
+        list_synth_state(
+            &mut StderrTableStream::new(),
+            &data,
+            device_id.unwrap_or(0x10),
+            until,
+        );
+    }
+
+    if timeline {
+        eprintln!();
+        list_parameter_timeline(
+            &mut StderrTableStream::new(),
+            &data,
+            device_id.unwrap_or(0x10),
+        );
+    }
+
+    if list_nrpns {
+        eprintln!();
+        list_data_entries(&mut StderrTableStream::new(), &data);
+    }
+
+    if lint {
+        eprintln!();
+        list_lints(&mut StderrTableStream::new(), &data);
+    }
+
+    if let Some(devices) = compatibility {
+        eprintln!();
+        list_compatibility(&mut StderrTableStream::new(), &data, &devices);
+    }
+
+    if to_text {
+        let mut text = String::new();
+        write_sysexes_as_text(&mut text, &data);
+        print!("{}", text);
+    }
+
     if let Some(out_path) = out_path {
         let mut file = BufWriter::new(File::create(out_path)?);
         write_midi(&mut file, &mut data, &mut std::io::stderr())?;
@@ -91,13 +91,181 @@ pub struct MidiData {
     pub other_events: Vec<(AbsoluteTime, Vec<u8>)>,
 }
 
+/// Converts the [AbsoluteTime]s of a [MidiData] to milliseconds, following the
+/// Set Tempo meta events in it (the default is 120bpm, as in the SMF spec).
 #[derive(Debug)]
+pub struct TimeConverter {
+    division: Division,
+    /// Time of each tempo change, in ticks and milliseconds, and the new
+    /// tempo in microseconds per quarter note.
+    tempo_changes: Vec<(AbsoluteTime, f64, u32)>,
+}
+impl TimeConverter {
+    pub fn new(data: &MidiData) -> TimeConverter {
+        let mut tempos: Vec<(AbsoluteTime, u32)> = data
+            .other_events
+            .iter()
+            .filter_map(|(time, bytes)| match bytes[..] {
+                [0xFF, 0x51, a, b, c] => Some((*time, u32::from_be_bytes([0, a, b, c]))),
+                _ => None,
+            })
+            .collect();
+        tempos.sort_by_key(|&(time, _)| time);
+
+        let mut converter = TimeConverter {
+            division: data.division,
+            tempo_changes: vec![(0, 0.0, 500_000)],
+        };
+        for (time, tempo) in tempos {
+            let ms = converter.to_ms(time);
+            converter.tempo_changes.push((time, ms, tempo));
+        }
+        converter
+    }
+
+    pub fn to_ms(&self, time: AbsoluteTime) -> f64 {
+        match self.division {
+            Division::TicksPerQuarterNote(ticks_per_quarter_note) => {
+                let &(change_time, change_ms, tempo) = self
+                    .tempo_changes
+                    .iter()
+                    .rev()
+                    .find(|&&(change_time, _, _)| change_time <= time)
+                    .unwrap();
+                let ticks = f64::from(time - change_time);
+                change_ms + ticks * f64::from(tempo) / 1000.0 / f64::from(ticks_per_quarter_note)
+            }
+            Division::TicksPerFrame {
+                frame_rate,
+                ticks_per_frame,
+            } => {
+                let frames_per_second = match frame_rate {
+                    SMPTEFormat::SMPTEFormat29 => 29.97,
+                    _ => -f64::from(frame_rate as i8),
+                };
+                f64::from(time) * 1000.0 / frames_per_second / f64::from(ticks_per_frame)
+            }
+        }
+    }
+
+    /// The number of ticks after `time` that last at least `ms` milliseconds,
+    /// at the tempo in effect at `time`.
+    pub fn ms_to_ticks(&self, time: AbsoluteTime, ms: f64) -> AbsoluteTime {
+        let ticks = match self.division {
+            Division::TicksPerQuarterNote(ticks_per_quarter_note) => {
+                let &(_, _, tempo) = self
+                    .tempo_changes
+                    .iter()
+                    .rev()
+                    .find(|&&(change_time, _, _)| change_time <= time)
+                    .unwrap();
+                ms * 1000.0 * f64::from(ticks_per_quarter_note) / f64::from(tempo)
+            }
+            Division::TicksPerFrame { .. } => ms / self.to_ms(1),
+        };
+        ticks.ceil() as AbsoluteTime
+    }
+}
+
+/// A position in bars, beats and ticks, counting bars and beats from 1, see
+/// [BarBeatConverter].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BarBeatTick {
+    pub bar: u32,
+    pub beat: u32,
+    pub tick: u32,
+}
+impl std::fmt::Display for BarBeatTick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{:03}", self.bar, self.beat, self.tick)
+    }
+}
+
+/// Converts the [AbsoluteTime]s of a [MidiData] to [BarBeatTick]s, following
+/// the Time Signature meta events in it (the default is 4/4, as in the SMF
+/// spec). A beat is the note value of the time signature's denominator.
+#[derive(Debug)]
+pub struct BarBeatConverter {
+    /// [None] if the division is in frames, so there are no beats.
+    ticks_per_quarter_note: Option<u16>,
+    /// Time of each time signature change, the bar it starts (counting from
+    /// 0), the beats per bar, and the ticks per beat.
+    time_signatures: Vec<(AbsoluteTime, u32, u32, u32)>,
+}
+impl BarBeatConverter {
+    pub fn new(data: &MidiData) -> BarBeatConverter {
+        let Division::TicksPerQuarterNote(ticks_per_quarter_note) = data.division else {
+            return BarBeatConverter {
+                ticks_per_quarter_note: None,
+                time_signatures: Vec::new(),
+            };
+        };
+        let ticks_per_beat = |denominator_power: u8| {
+            (u32::from(ticks_per_quarter_note) * 4)
+                .checked_shr(denominator_power.into())
+                .unwrap_or(0)
+                .max(1)
+        };
+
+        let mut changes: Vec<(AbsoluteTime, u32, u32)> = data
+            .other_events
+            .iter()
+            .filter_map(|(time, bytes)| match bytes[..] {
+                [0xFF, 0x58, numerator, denominator_power, _, _] => Some((
+                    *time,
+                    u32::from(numerator).max(1),
+                    ticks_per_beat(denominator_power),
+                )),
+                _ => None,
+            })
+            .collect();
+        changes.sort_by_key(|&(time, _, _)| time);
+
+        let mut time_signatures = vec![(0, 0, 4, ticks_per_beat(2))];
+        for (time, beats_per_bar, ticks_per_beat) in changes {
+            let &(last_time, last_bar, last_beats_per_bar, last_ticks_per_beat) =
+                time_signatures.last().unwrap();
+            // A change in the middle of a bar starts a new one.
+            let bar =
+                last_bar + (time - last_time).div_ceil(last_beats_per_bar * last_ticks_per_beat);
+            if time == last_time {
+                time_signatures.pop();
+            }
+            time_signatures.push((time, bar, beats_per_bar, ticks_per_beat));
+        }
+
+        BarBeatConverter {
+            ticks_per_quarter_note: Some(ticks_per_quarter_note),
+            time_signatures,
+        }
+    }
+
+    /// Get the position of `time`, if the division is in ticks per quarter
+    /// note.
+    pub fn to_bar_beat_tick(&self, time: AbsoluteTime) -> Option<BarBeatTick> {
+        self.ticks_per_quarter_note?;
+        let &(change_time, change_bar, beats_per_bar, ticks_per_beat) = self
+            .time_signatures
+            .iter()
+            .rev()
+            .find(|&&(change_time, _, _, _)| change_time <= time)
+            .unwrap();
+        let beats = (time - change_time) / ticks_per_beat;
+        Some(BarBeatTick {
+            bar: change_bar + beats / beats_per_bar + 1,
+            beat: beats % beats_per_bar + 1,
+            tick: (time - change_time) % ticks_per_beat,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct ChannelMessage {
     pub channel: u8,
     pub kind: ChannelMessageKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ChannelMessageKind {
     NoteOff {
@@ -130,6 +298,287 @@ impl ChannelMessageKind {
     }
 }
 
+/// A Control Change message.
+pub fn cc(channel: u8, control: u8, value: u8) -> ChannelMessage {
+    ChannelMessage {
+        channel,
+        kind: ChannelMessageKind::ControlChange { control, value },
+    }
+}
+
+impl std::fmt::Display for ChannelMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Channel {}: ", self.channel + 1)?;
+        match self.kind {
+            ChannelMessageKind::NoteOff { key, velocity } => write!(
+                f,
+                "Note Off {}, velocity {}",
+                describe_note(key, OctaveConvention::MiddleC4),
+                velocity
+            ),
+            ChannelMessageKind::NoteOn { key, velocity } => write!(
+                f,
+                "Note On {}, velocity {}",
+                describe_note(key, OctaveConvention::MiddleC4),
+                velocity
+            ),
+            ChannelMessageKind::PolyKeyPressure { key, pressure } => write!(
+                f,
+                "Poly Key Pressure {}, pressure {}",
+                describe_note(key, OctaveConvention::MiddleC4),
+                pressure
+            ),
+            ChannelMessageKind::ControlChange { control, value } => {
+                write!(f, "{} = {}", describe_controller(control), value)
+            }
+            ChannelMessageKind::ProgramChange(program) => {
+                write!(f, "Program Change #{}", program as u16 + 1)
+            }
+            ChannelMessageKind::ChannelPressure(pressure) => {
+                write!(f, "Channel Pressure {}", pressure)
+            }
+            ChannelMessageKind::PitchBendChange(bend) => {
+                write!(f, "Pitch Bend {:+}", bend as i32 - 0x2000)
+            }
+        }
+    }
+}
+
+/// Which octave number middle C (note number 60) is in when naming notes.
+/// Manufacturers disagree about this.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OctaveConvention {
+    /// Middle C is C4, as used by Roland.
+    MiddleC4,
+    /// Middle C is C3, as used by Yamaha.
+    MiddleC3,
+}
+
+/// Name a MIDI note number, e.g. "C♯4".
+pub fn note_name(key: u8, convention: OctaveConvention) -> impl std::fmt::Display {
+    struct NoteName(u8, OctaveConvention);
+    impl std::fmt::Display for NoteName {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            const NAMES: [&str; 12] = [
+                "C", "C♯", "D", "D♯", "E", "F", "F♯", "G", "G♯", "A", "A♯", "B",
+            ];
+            let &NoteName(key, convention) = self;
+            let middle_c_octave = match convention {
+                OctaveConvention::MiddleC4 => 4,
+                OctaveConvention::MiddleC3 => 3,
+            };
+            let octave = (key / 12) as i8 - 5 + middle_c_octave;
+            write!(f, "{}{}", NAMES[(key % 12) as usize], octave)
+        }
+    }
+    NoteName(key, convention)
+}
+
+/// Parse a note name like those written by [note_name], e.g. "C♯4". A plain
+/// `#` can be used instead of `♯`, and `b` or `♭` for flats. Returns [None]
+/// if it's not a note name or it's outside the MIDI note range.
+pub fn parse_note_name(name: &str, convention: OctaveConvention) -> Option<u8> {
+    let mut chars = name.chars();
+    let semitone: i16 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (semitone, octave) = if let Some(octave) = rest.strip_prefix(['#', '♯']) {
+        (semitone + 1, octave)
+    } else if let Some(octave) = rest.strip_prefix(['b', '♭']) {
+        (semitone - 1, octave)
+    } else {
+        (semitone, rest)
+    };
+    let octave: i16 = octave.parse().ok()?;
+    let middle_c_octave = match convention {
+        OctaveConvention::MiddleC4 => 4,
+        OctaveConvention::MiddleC3 => 3,
+    };
+    u8::try_from((octave - middle_c_octave + 5) * 12 + semitone)
+        .ok()
+        .filter(|&key| key <= 0x7F)
+}
+
+/// Name a MIDI note number along with the number itself, e.g. "C4 (60)".
+pub fn describe_note(key: u8, convention: OctaveConvention) -> impl std::fmt::Display {
+    struct DescribeNote(u8, OctaveConvention);
+    impl std::fmt::Display for DescribeNote {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} ({})", note_name(self.0, self.1), self.0)
+        }
+    }
+    DescribeNote(key, convention)
+}
+
+/// The function of a control change (or channel mode) number, if it has one
+/// in the MIDI 1.0 specification. The names mostly follow General MIDI 2.
+/// For 32–63, the least significant byte of controllers 0–31, see
+/// [describe_controller].
+pub fn controller_name(control: u8) -> Option<&'static str> {
+    Some(match control {
+        0 => "Bank Select",
+        1 => "Modulation",
+        2 => "Breath Controller",
+        4 => "Foot Controller",
+        5 => "Portamento Time",
+        6 => "Data Entry",
+        7 => "Channel Volume",
+        8 => "Balance",
+        10 => "Pan",
+        11 => "Expression",
+        12 => "Effect Control 1",
+        13 => "Effect Control 2",
+        16 => "General Purpose Controller 1",
+        17 => "General Purpose Controller 2",
+        18 => "General Purpose Controller 3",
+        19 => "General Purpose Controller 4",
+        64 => "Sustain",
+        65 => "Portamento",
+        66 => "Sostenuto",
+        67 => "Soft Pedal",
+        68 => "Legato Footswitch",
+        69 => "Hold 2",
+        70 => "Sound Variation",
+        71 => "Timbre/Harmonic Intensity",
+        72 => "Release Time",
+        73 => "Attack Time",
+        74 => "Brightness",
+        75 => "Decay Time",
+        76 => "Vibrato Rate",
+        77 => "Vibrato Depth",
+        78 => "Vibrato Delay",
+        79 => "Sound Controller 10",
+        80 => "General Purpose Controller 5",
+        81 => "General Purpose Controller 6",
+        82 => "General Purpose Controller 7",
+        83 => "General Purpose Controller 8",
+        84 => "Portamento Control",
+        88 => "High Resolution Velocity Prefix",
+        91 => "Effects 1 Depth (Reverb)",
+        92 => "Effects 2 Depth (Tremolo)",
+        93 => "Effects 3 Depth (Chorus)",
+        94 => "Effects 4 Depth (Celeste)",
+        95 => "Effects 5 Depth (Phaser)",
+        96 => "Data Increment",
+        97 => "Data Decrement",
+        98 => "NRPN LSB",
+        99 => "NRPN MSB",
+        100 => "RPN LSB",
+        101 => "RPN MSB",
+        120 => "All Sound Off",
+        121 => "Reset All Controllers",
+        122 => "Local Control",
+        123 => "All Notes Off",
+        124 => "Omni Mode Off",
+        125 => "Omni Mode On",
+        126 => "Mono Mode On",
+        127 => "Poly Mode On",
+        _ => return None,
+    })
+}
+
+/// Describe a control change number, e.g. "CC#74 Brightness", or just
+/// "CC#3" if it's undefined.
+pub fn describe_controller(control: u8) -> impl std::fmt::Display {
+    struct DescribeController(u8);
+    impl std::fmt::Display for DescribeController {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let control = self.0;
+            write!(f, "CC#{}", control)?;
+            if let Some(name) = controller_name(control) {
+                write!(f, " {}", name)
+            } else if let Some(name) = (32..64)
+                .contains(&control)
+                .then(|| controller_name(control - 32))
+                .flatten()
+            {
+                write!(f, " {} (LSB)", name)
+            } else {
+                Ok(())
+            }
+        }
+    }
+    DescribeController(control)
+}
+
+#[cfg(test)]
+#[test]
+fn test_time_converter() {
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        other_events: vec![(240, vec![0xFF, 0x51, 0x0F, 0x42, 0x40])], // 60bpm
+    };
+    let converter = TimeConverter::new(&data);
+    assert_eq!(converter.to_ms(60), 250.0);
+    assert_eq!(converter.to_ms(240), 1000.0);
+    assert_eq!(converter.to_ms(300), 1500.0);
+    assert_eq!(converter.ms_to_ticks(0, 50.0), 12);
+    assert_eq!(converter.ms_to_ticks(240, 50.0), 6);
+
+    data.division = Division::TicksPerFrame {
+        frame_rate: SMPTEFormat::SMPTEFormat25,
+        ticks_per_frame: 40,
+    };
+    assert_eq!(TimeConverter::new(&data).to_ms(1000), 1000.0);
+}
+
+#[cfg(test)]
+#[test]
+fn test_bar_beat_converter() {
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        // 6/8 from the third bar, then 3/4 part-way through the fourth.
+        other_events: vec![
+            (960, vec![0xFF, 0x58, 0x06, 0x03, 0x18, 0x08]),
+            (1440, vec![0xFF, 0x58, 0x03, 0x02, 0x18, 0x08]),
+        ],
+    };
+    let converter = BarBeatConverter::new(&data);
+    let format = |time| converter.to_bar_beat_tick(time).unwrap().to_string();
+    assert_eq!(format(0), "1:1:000");
+    assert_eq!(format(500), "2:1:020");
+    assert_eq!(format(960 + 60 * 7 + 5), "4:2:005");
+    assert_eq!(format(1440), "5:1:000");
+    assert_eq!(format(1440 + 120 * 4), "6:2:000");
+}
+
+#[cfg(test)]
+#[test]
+fn test_names() {
+    assert_eq!(
+        describe_note(60, OctaveConvention::MiddleC4).to_string(),
+        "C4 (60)"
+    );
+    assert_eq!(note_name(60, OctaveConvention::MiddleC3).to_string(), "C3");
+    assert_eq!(note_name(0, OctaveConvention::MiddleC4).to_string(), "C-1");
+    assert_eq!(note_name(127, OctaveConvention::MiddleC4).to_string(), "G9");
+    assert_eq!(note_name(61, OctaveConvention::MiddleC4).to_string(), "C♯4");
+    for key in 0..=0x7F {
+        for convention in [OctaveConvention::MiddleC4, OctaveConvention::MiddleC3] {
+            let name = note_name(key, convention).to_string();
+            assert_eq!(parse_note_name(&name, convention), Some(key));
+        }
+    }
+    assert_eq!(parse_note_name("Db4", OctaveConvention::MiddleC4), Some(61));
+    assert_eq!(parse_note_name("G#9", OctaveConvention::MiddleC4), None);
+    assert_eq!(describe_controller(74).to_string(), "CC#74 Brightness");
+    assert_eq!(
+        describe_controller(39).to_string(),
+        "CC#39 Channel Volume (LSB)"
+    );
+    assert_eq!(describe_controller(3).to_string(), "CC#3");
+}
+
 /// Read Standard MIDI File format 0 or 1 data.
 pub fn read_midi<F, L>(file: &mut F, v: bool, log_to: &mut L) -> Result<MidiData, Box<dyn Error>>
 where
@@ -0,0 +1,429 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! RPNs and NRPNs: parameters that are set with control changes, by selecting
+//! a parameter number (CC#101 and CC#100 for an RPN, CC#99 and CC#98 for an
+//! NRPN) and then sending Data Entry (CC#6, and CC#38 for the LSB).
+//!
+//! Roland GS devices accept some NRPNs that do the same thing as a part
+//! parameter in the GS parameter map (see [crate::sysex::roland]), and so does
+//! the Pitch Bend Sensitivity RPN. This module can decode them and convert them
+//! to and from the equivalent DT1. See also [crate::transform] for doing that
+//! to a whole [MidiData].
+
+use crate::midi::{
+    describe_note, AbsoluteTime, ChannelMessage, ChannelMessageKind, MidiData, OctaveConvention,
+};
+use crate::state::{gs_drum_setup_address, gs_model, gs_part_address, GS_DRUM_PLAY_NOTE_NUMBER};
+use crate::sysex::roland::look_up_parameter;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+pub const CC_DATA_ENTRY_MSB: u8 = 6;
+pub const CC_DATA_ENTRY_LSB: u8 = 38;
+pub const CC_DATA_INCREMENT: u8 = 96;
+pub const CC_DATA_DECREMENT: u8 = 97;
+pub const CC_NRPN_LSB: u8 = 98;
+pub const CC_NRPN_MSB: u8 = 99;
+pub const CC_RPN_LSB: u8 = 100;
+pub const CC_RPN_MSB: u8 = 101;
+
+/// Selected parameter number for Data Entry, with its MSB and LSB.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParameterNumber {
+    /// RPN, set by CC#101 and CC#100.
+    Registered(u8, u8),
+    /// NRPN, set by CC#99 and CC#98.
+    NonRegistered(u8, u8),
+}
+/// The RPN that means "no parameter", which is also the initial state.
+pub const PARAMETER_NUMBER_NULL: ParameterNumber = ParameterNumber::Registered(0x7F, 0x7F);
+
+impl ParameterNumber {
+    /// Update the selected parameter number for a control change. Returns
+    /// [false] if the control isn't one that selects a parameter number.
+    pub fn select(&mut self, control: u8, value: u8) -> bool {
+        let (msb, lsb) = match *self {
+            ParameterNumber::Registered(msb, lsb) | ParameterNumber::NonRegistered(msb, lsb) => {
+                (msb, lsb)
+            }
+        };
+        *self = match control {
+            CC_NRPN_MSB => ParameterNumber::NonRegistered(value, lsb),
+            CC_NRPN_LSB => ParameterNumber::NonRegistered(msb, value),
+            CC_RPN_MSB => ParameterNumber::Registered(value, lsb),
+            CC_RPN_LSB => ParameterNumber::Registered(msb, value),
+            _ => return false,
+        };
+        true
+    }
+}
+
+impl Display for ParameterNumber {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let (kind, msb, lsb) = match *self {
+            ParameterNumber::Registered(msb, lsb) => ("RPN", msb, lsb),
+            ParameterNumber::NonRegistered(msb, lsb) => ("NRPN", msb, lsb),
+        };
+        write!(f, "{} {:02X}h {:02X}h", kind, msb, lsb)?;
+        let name = match *self {
+            ParameterNumber::NonRegistered(0x01, lsb) => GS_TONE_MODIFY_NRPNS
+                .iter()
+                .find(|&&(lsb2, _, _)| lsb2 == lsb)
+                .map(|&(_, _, name)| name),
+            ParameterNumber::NonRegistered(msb, key) => {
+                if let Some(&(_, _, name)) = GS_DRUM_NRPNS.iter().find(|&&(msb2, _, _)| msb2 == msb)
+                {
+                    return write!(
+                        f,
+                        " ({}, {})",
+                        name,
+                        describe_note(key, OctaveConvention::MiddleC4)
+                    );
+                }
+                None
+            }
+            ParameterNumber::Registered(..) => RPN_NAMES
+                .iter()
+                .find(|&&(number, _)| number == *self)
+                .map(|&(_, name)| name),
+        };
+        match name {
+            Some(name) => write!(f, " ({})", name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// RPNs from the MIDI 1.0 spec.
+const RPN_NAMES: &[(ParameterNumber, &str)] = &[
+    (
+        ParameterNumber::Registered(0x00, 0x00),
+        "Pitch Bend Sensitivity",
+    ),
+    (ParameterNumber::Registered(0x00, 0x01), "Fine Tuning"),
+    (ParameterNumber::Registered(0x00, 0x02), "Coarse Tuning"),
+    (PARAMETER_NUMBER_NULL, "Null"),
+];
+
+/// GS NRPNs (with MSB 01h) that set a TONE MODIFY parameter: the NRPN LSB, the
+/// address of the parameter, relative to a part's block (see
+/// [gs_part_address]), and the name. These take the same values as the
+/// parameter.
+const GS_TONE_MODIFY_NRPNS: &[(u8, u8, &str)] = &[
+    (0x08, 0x30, "Vibrato rate"),
+    (0x09, 0x31, "Vibrato depth"),
+    (0x0A, 0x37, "Vibrato delay"),
+    (0x20, 0x32, "TVF cutoff freq."),
+    (0x21, 0x33, "TVF resonance"),
+    (0x63, 0x34, "TVF & TVA Env. attack"),
+    (0x64, 0x35, "TVF & TVA Env. decay"),
+    (0x66, 0x36, "TVF & TVA Env. release"),
+];
+/// GS NRPNs for a drum instrument: the NRPN MSB, the address of the drum setup
+/// parameter it sets, relative to a drum map (see [gs_drum_setup_address]),
+/// and the name. The LSB is the note number of the instrument. These have no
+/// equivalent part parameter.
+const GS_DRUM_NRPNS: &[(u8, u8, &str)] = &[
+    (
+        0x18,
+        GS_DRUM_PLAY_NOTE_NUMBER,
+        "Drum instrument pitch coarse",
+    ),
+    (0x1A, 0x02, "Drum instrument TVA level"),
+    (0x1C, 0x04, "Drum instrument panpot"),
+    (0x1D, 0x05, "Drum instrument reverb send level"),
+    (0x1E, 0x06, "Drum instrument chorus send level"),
+];
+
+/// The GS part parameter equivalent to a parameter number: whether it's in the
+/// part's controllers block, its address relative to the block (see
+/// [gs_part_address]), and what to add to the Data Entry MSB to get its data.
+fn gs_equivalent(number: ParameterNumber) -> Option<(bool, u8, u8)> {
+    match number {
+        // BEND PITCH CONTROL, which is 40h plus the semitones.
+        ParameterNumber::Registered(0x00, 0x00) => Some((true, 0x10, 0x40)),
+        ParameterNumber::NonRegistered(0x01, lsb) => GS_TONE_MODIFY_NRPNS
+            .iter()
+            .find(|&&(lsb2, _, _)| lsb2 == lsb)
+            .map(|&(_, suffix, _)| (false, suffix, 0x00)),
+        _ => None,
+    }
+}
+
+/// If `number` has an equivalent GS part parameter, get the full address of it
+/// for the part at `part_idx` (see [gs_part_address]), and the data for the
+/// Data Entry MSB `value`, limited to the parameter's range.
+pub fn parameter_number_to_gs_dt1(
+    number: ParameterNumber,
+    value: u8,
+    part_idx: u8,
+) -> Option<([u8; 3], u8)> {
+    let (controllers, suffix, offset) = gs_equivalent(number)?;
+    let address = gs_part_address(part_idx, controllers, suffix);
+    let (_, Some(param)) = look_up_parameter(gs_model(), &address) else {
+        unreachable!();
+    };
+    let data = offset
+        .saturating_add(value)
+        .clamp(*param.range.start(), *param.range.end());
+    Some((address, data))
+}
+
+/// If `number` is a GS drum instrument NRPN, get the full address of the drum
+/// setup parameter it sets in the drum map `map` (see [gs_drum_setup_address]),
+/// and the data for the Data Entry MSB `value`.
+pub fn drum_nrpn_to_gs_dt1(number: ParameterNumber, value: u8, map: u8) -> Option<([u8; 3], u8)> {
+    let ParameterNumber::NonRegistered(msb, key) = number else {
+        return None;
+    };
+    let &(_, suffix, _) = GS_DRUM_NRPNS.iter().find(|&&(msb2, _, _)| msb2 == msb)?;
+    let data = if suffix == GS_DRUM_PLAY_NOTE_NUMBER {
+        // The NRPN is relative to the instrument's own pitch, with 40h meaning
+        // no change, whereas PLAY NOTE NUMBER is the note to play it at.
+        (key as i16 + value as i16 - 0x40).clamp(0x00, 0x7F) as u8
+    } else {
+        value
+    };
+    Some((gs_drum_setup_address(map, suffix, key), data))
+}
+
+/// The reverse of [parameter_number_to_gs_dt1]: if `address` is the full
+/// address of a GS part parameter with an equivalent parameter number, get the
+/// part index, the parameter number and the Data Entry MSB for `data`.
+pub fn gs_dt1_to_parameter_number(address: &[u8], data: u8) -> Option<(u8, ParameterNumber, u8)> {
+    let &[0x40, block, suffix] = address else {
+        return None;
+    };
+    let controllers = match block & 0xF0 {
+        0x10 => false,
+        0x20 => true,
+        _ => return None,
+    };
+    let part_idx = block & 0x0F;
+    std::iter::once(ParameterNumber::Registered(0x00, 0x00))
+        .chain(
+            GS_TONE_MODIFY_NRPNS
+                .iter()
+                .map(|&(lsb, _, _)| ParameterNumber::NonRegistered(0x01, lsb)),
+        )
+        .find_map(|number| {
+            let (controllers2, suffix2, offset) = gs_equivalent(number)?;
+            (controllers2 == controllers && suffix2 == suffix && data >= offset).then_some((
+                part_idx,
+                number,
+                data - offset,
+            ))
+        })
+}
+
+/// The MIDI channel (0 to 15) a GS part receives on by default, see
+/// [gs_part_address] for `part_idx`.
+pub fn gs_part_default_channel(part_idx: u8) -> u8 {
+    match part_idx {
+        0 => 9,
+        1..=9 => part_idx - 1,
+        _ => part_idx,
+    }
+}
+
+/// The GS part index (see [gs_part_address]) that receives on `channel` by
+/// default.
+pub fn gs_channel_default_part(channel: u8) -> u8 {
+    match channel {
+        9 => 0,
+        0..=8 => channel + 1,
+        _ => channel,
+    }
+}
+
+/// The control changes that set `number` to the Data Entry MSB `value` on
+/// `channel`, followed by the null RPN, so that a stray Data Entry later on
+/// can't change the parameter by accident.
+pub fn parameter_number_messages(
+    channel: u8,
+    number: ParameterNumber,
+    value: u8,
+) -> Vec<ChannelMessage> {
+    let (msb_control, lsb_control, msb, lsb) = match number {
+        ParameterNumber::Registered(msb, lsb) => (CC_RPN_MSB, CC_RPN_LSB, msb, lsb),
+        ParameterNumber::NonRegistered(msb, lsb) => (CC_NRPN_MSB, CC_NRPN_LSB, msb, lsb),
+    };
+    [
+        (msb_control, msb),
+        (lsb_control, lsb),
+        (CC_DATA_ENTRY_MSB, value),
+        (CC_RPN_MSB, 0x7F),
+        (CC_RPN_LSB, 0x7F),
+    ]
+    .into_iter()
+    .map(|(control, value)| ChannelMessage {
+        channel,
+        kind: ChannelMessageKind::ControlChange { control, value },
+    })
+    .collect()
+}
+
+/// A Data Entry for an RPN or NRPN, see [decode_data_entries].
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataEntry {
+    pub time: AbsoluteTime,
+    pub channel: u8,
+    pub number: ParameterNumber,
+    pub msb: u8,
+    /// The Data Entry LSB (CC#38), if one came right after the MSB.
+    pub lsb: Option<u8>,
+}
+
+/// Find each Data Entry in the channel messages of `data`, with the parameter
+/// number it is for. Data Entry while the null RPN is selected does nothing, so
+/// it is left out.
+pub fn decode_data_entries(data: &MidiData) -> Vec<DataEntry> {
+    let mut messages: Vec<_> = data.channel_messages.iter().collect();
+    messages.sort_by_key(|&&(time, _)| time);
+
+    let mut numbers = [PARAMETER_NUMBER_NULL; 16];
+    let mut entries: Vec<DataEntry> = Vec::new();
+    let mut last_entry_idx: [Option<usize>; 16] = [None; 16];
+    for &(time, ref message) in messages {
+        let channel = message.channel;
+        let ChannelMessageKind::ControlChange { control, value } = message.kind else {
+            continue;
+        };
+        let number = &mut numbers[channel as usize];
+        if number.select(control, value) {
+            last_entry_idx[channel as usize] = None;
+            continue;
+        }
+        match control {
+            CC_DATA_ENTRY_MSB if *number != PARAMETER_NUMBER_NULL => {
+                last_entry_idx[channel as usize] = Some(entries.len());
+                entries.push(DataEntry {
+                    time,
+                    channel,
+                    number: *number,
+                    msb: value,
+                    lsb: None,
+                });
+            }
+            CC_DATA_ENTRY_LSB => {
+                if let Some(entry_idx) = last_entry_idx[channel as usize].take() {
+                    entries[entry_idx].lsb = Some(value);
+                }
+            }
+            // Reset All Controllers
+            121 => {
+                *number = PARAMETER_NUMBER_NULL;
+                last_entry_idx[channel as usize] = None;
+            }
+            _ => (),
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+#[test]
+fn test_parameter_numbers() {
+    use crate::midi::{cc, Division};
+
+    let mut channel_messages = vec![
+        (0, cc(1, 99, 0x01)),
+        (0, cc(1, 98, 0x20)),
+        (0, cc(1, 6, 0x50)),
+        (10, cc(9, 99, 0x1A)),
+        (10, cc(9, 98, 36)),
+        (10, cc(9, 6, 0x60)),
+        (10, cc(9, 38, 0x00)),
+        (20, cc(1, 121, 0)),
+        (20, cc(1, 6, 0x10)),
+    ];
+    channel_messages.extend(
+        parameter_number_messages(0, ParameterNumber::Registered(0x00, 0x00), 12)
+            .into_iter()
+            .map(|message| (30, message)),
+    );
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages,
+        other_events: Vec::new(),
+    };
+
+    let entries = decode_data_entries(&data);
+    assert_eq!(
+        entries,
+        &[
+            DataEntry {
+                time: 0,
+                channel: 1,
+                number: ParameterNumber::NonRegistered(0x01, 0x20),
+                msb: 0x50,
+                lsb: None,
+            },
+            DataEntry {
+                time: 10,
+                channel: 9,
+                number: ParameterNumber::NonRegistered(0x1A, 36),
+                msb: 0x60,
+                lsb: Some(0x00),
+            },
+            DataEntry {
+                time: 30,
+                channel: 0,
+                number: ParameterNumber::Registered(0x00, 0x00),
+                msb: 12,
+                lsb: None,
+            },
+        ]
+    );
+    assert_eq!(
+        entries[0].number.to_string(),
+        "NRPN 01h 20h (TVF cutoff freq.)"
+    );
+    assert_eq!(
+        entries[1].number.to_string(),
+        "NRPN 1Ah 24h (Drum instrument TVA level, C2 (36))"
+    );
+
+    // Part 2 TVF cutoff, and Part 1 BEND PITCH CONTROL
+    let part_idx = gs_channel_default_part(1);
+    assert_eq!(
+        parameter_number_to_gs_dt1(entries[0].number, 0x50, part_idx),
+        Some(([0x40, 0x12, 0x32], 0x50))
+    );
+    assert_eq!(
+        gs_dt1_to_parameter_number(&[0x40, 0x12, 0x32], 0x50),
+        Some((part_idx, entries[0].number, 0x50))
+    );
+    assert_eq!(
+        parameter_number_to_gs_dt1(entries[2].number, 12, 1),
+        Some(([0x40, 0x21, 0x10], 0x4C))
+    );
+    assert_eq!(
+        gs_dt1_to_parameter_number(&[0x40, 0x21, 0x10], 0x4C),
+        Some((1, entries[2].number, 12))
+    );
+    // No equivalents
+    assert_eq!(parameter_number_to_gs_dt1(entries[1].number, 0x60, 0), None);
+    assert_eq!(drum_nrpn_to_gs_dt1(entries[0].number, 0x50, 0), None);
+
+    // C2 (36) LEVEL in MAP2, and pitch coarse, which is relative
+    assert_eq!(
+        drum_nrpn_to_gs_dt1(entries[1].number, 0x60, 1),
+        Some(([0x41, 0x12, 36], 0x60))
+    );
+    let pitch = ParameterNumber::NonRegistered(0x18, 36);
+    assert_eq!(
+        drum_nrpn_to_gs_dt1(pitch, 0x3E, 0),
+        Some(([0x41, 0x01, 36], 34))
+    );
+    assert_eq!(
+        drum_nrpn_to_gs_dt1(pitch, 0x7F, 0),
+        Some(([0x41, 0x01, 36], 99))
+    );
+    assert_eq!(gs_dt1_to_parameter_number(&[0x40, 0x12, 0x19], 0x50), None);
+    assert_eq!(gs_part_default_channel(part_idx), 1);
+}
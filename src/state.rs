@@ -0,0 +1,771 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! A model of the effective state of a synth, built by replaying the SysExes
+//! and channel messages of a [MidiData] in time order. Looking at a single
+//! SysEx doesn't tell you much if a later one overrides it, or a reset wipes
+//! it, or a controller changes the same thing.
+//!
+//! The state is the data at each address of each model's parameter map (see
+//! [crate::sysex::roland]), like the synth's own memory. It starts out with the
+//! defaults, as after a reset. DT1 SysExes write to it directly (including the
+//! parameters a macro sets, see [crate::sysex::roland::MacroParameter]), and GS
+//! reset and GM System On put everything back to the defaults.
+//!
+//! Channel messages are only understood for Roland GS so far: bank select and
+//! program change set the TONE NUMBER of each part that receives on the
+//! channel, and some controllers, RPNs and NRPNs set the equivalent part
+//! parameters, if the part's Rx. switches allow it.
+//!
+//! The GS drum instrument NRPNs (pitch, level, panpot and sends of a single
+//! drum) set the drum setup parameters of the drum map that the part uses, at
+//! the addresses a drum setup DT1 would write (see [gs_drum_setup_address]).
+//! Unlike other parameters, these have no fixed defaults: selecting a drum set
+//! loads its own settings into the map, so they're unknown until set again.
+//! They're listed separately, see [SynthState::drum_instruments].
+
+use crate::midi::{AbsoluteTime, ChannelMessage, ChannelMessageKind, MidiData};
+use crate::nrpn::{
+    drum_nrpn_to_gs_dt1, parameter_number_to_gs_dt1, ParameterNumber, CC_DATA_ENTRY_MSB,
+    CC_NRPN_LSB, CC_RPN_MSB, PARAMETER_NUMBER_NULL,
+};
+use crate::sysex::roland::{
+    look_up_macro, next_address, DeviceId, ModelId, ModelInfo, Parameter, ParsedRolandSysExBody,
+    ParsedRolandSysExCommand, DV_ID_BROADCAST, MODELS,
+};
+use crate::sysex::universal::{
+    ParsedUniversalSysExBody, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON,
+};
+use crate::sysex::{parse_sysex, MaybeParsed, ParsedSysEx, ParsedSysExBody};
+use std::collections::BTreeMap;
+
+mod setup;
+mod timeline;
+pub use setup::generate_setup;
+pub use timeline::{parameter_timeline, ParameterHistory};
+
+/// Messages that put a synth back into its default state.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reset {
+    /// Universal Non-Real Time "General MIDI System On".
+    GmSystemOn,
+    /// Roland GS "MODE SET" aka "GS Reset".
+    GsReset,
+}
+
+/// If `sysex` is a reset (see [Reset]), returns which kind and the device ID
+/// it is sent to. A GS reset with a wrong checksum is ignored by the synth, so
+/// it doesn't count.
+pub fn parse_reset(sysex: &[u8]) -> Option<(Reset, DeviceId)> {
+    match parse_sysex(sysex).ok()?.content {
+        MaybeParsed::Parsed(ParsedSysExBody::Universal(ParsedUniversalSysExBody {
+            real_time: false,
+            device_id,
+            sub_id1: SI1_NRT_GENERAL_MIDI,
+            sub_id2: SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON,
+            ..
+        })) => Some((Reset::GmSystemOn, device_id)),
+        MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+            device_id,
+            model_id: GS_MODEL_ID,
+            command:
+                MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                    address: [0x40, 0x00, 0x7F],
+                    data: [0x00],
+                    valid_checksum: true,
+                    ..
+                }),
+            ..
+        })) => Some((Reset::GsReset, device_id)),
+        _ => None,
+    }
+}
+
+const GS_MODEL_ID: &[u8] = &[0x42];
+
+/// A GS reset for device ID 10h, for tests.
+#[cfg(test)]
+pub const GS_RESET: &[u8] = &[
+    0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x41, 0xF7,
+];
+
+// Addresses of GS part parameters, relative to a part's block. See
+// gs_part_address().
+pub(crate) const GS_TONE_NUMBER: u8 = 0x00;
+pub(crate) const GS_RX_CHANNEL: u8 = 0x02;
+const GS_RX_PROGRAM_CHANGE: u8 = 0x05;
+const GS_RX_CONTROL_CHANGE: u8 = 0x06;
+const GS_RX_RPN: u8 = 0x09;
+const GS_RX_NRPN: u8 = 0x0A;
+const GS_RX_VOLUME: u8 = 0x0C;
+const GS_RX_PANPOT: u8 = 0x0D;
+const GS_PART_LEVEL: u8 = 0x19;
+const GS_PART_PANPOT: u8 = 0x1C;
+const GS_CHORUS_SEND_LEVEL: u8 = 0x21;
+const GS_REVERB_SEND_LEVEL: u8 = 0x22;
+const GS_USE_FOR_RHYTHM_PART: u8 = 0x15;
+const GS_RX_BANK_SELECT: u8 = 0x23;
+
+// Address of the GS drum setup parameter that drum instrument pitch NRPNs
+// set, relative to a drum map. See gs_drum_setup_address().
+pub(crate) const GS_DRUM_PLAY_NOTE_NUMBER: u8 = 0x01;
+
+/// The GS drum setup parameters that [SynthState::drum_instruments] lists:
+/// the address of each, relative to a drum map (see [gs_drum_setup_address]),
+/// and the name.
+pub const GS_DRUM_SETUP_PARAMS: &[(u8, &str)] = &[
+    (GS_DRUM_PLAY_NOTE_NUMBER, "PLAY NOTE NUMBER"),
+    (0x02, "LEVEL"),
+    (0x04, "PANPOT"),
+    (0x05, "REVERB SEND LEVEL"),
+    (0x06, "CHORUS SEND LEVEL"),
+];
+
+/// The Roland GS model in [MODELS].
+pub fn gs_model() -> &'static ModelInfo {
+    MODELS
+        .iter()
+        .find(|model| model.model_id == GS_MODEL_ID)
+        .unwrap()
+}
+
+/// The full address of a GS part parameter. `part_idx` is the position of the
+/// part's block, which is 0 for Part 10, 1 to 9 for Parts 1 to 9 and 10 to 15
+/// for Parts 11 to 16. `controllers` picks the part's controllers block.
+pub fn gs_part_address(part_idx: u8, controllers: bool, suffix: u8) -> [u8; 3] {
+    [
+        0x40,
+        if controllers { 0x20 } else { 0x10 } | part_idx,
+        suffix,
+    ]
+}
+
+/// The full address of a GS drum setup parameter for the drum instrument at
+/// `key`. `map` is 0 for MAP1 and 1 for MAP2, and `suffix` picks the parameter,
+/// see [GS_DRUM_SETUP_PARAMS].
+pub fn gs_drum_setup_address(map: u8, suffix: u8, key: u8) -> [u8; 3] {
+    [0x41, map << 4 | suffix, key]
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct ChannelState {
+    /// Last Bank Select (CC#0) value, used by the next program change.
+    bank_select: u8,
+    parameter_number: ParameterNumber,
+}
+impl Default for ChannelState {
+    fn default() -> ChannelState {
+        ChannelState {
+            bank_select: 0,
+            parameter_number: PARAMETER_NUMBER_NULL,
+        }
+    }
+}
+
+/// The data a parameter has after a reset, see [Parameter::default] and
+/// [ModelInfo::default_overrides].
+fn default_data(model_info: &ModelInfo, address: &[u8], param: &Parameter) -> Option<Vec<u8>> {
+    let mut address = address.to_vec();
+    (0..param.size as usize)
+        .map(|i| {
+            let byte = model_info
+                .default_overrides
+                .iter()
+                .find(|&&(address2, _)| address2 == address)
+                .map(|&(_, data)| data[0])
+                .or_else(|| param.default.map(|default| default[i]));
+            next_address(&mut address);
+            byte
+        })
+        .collect()
+}
+
+/// The address and value of each byte a DT1 writes, in order, including the
+/// ones set by macro parameters (see [SynthState::apply_dt1]).
+pub fn dt1_writes(model_info: &ModelInfo, address: &[u8], data: &[u8]) -> Vec<(Vec<u8>, u8)> {
+    let mut writes = Vec::new();
+    let mut address = address.to_vec();
+    for &byte in data {
+        writes.push((address.clone(), byte));
+        if let Some(implied_values) = look_up_macro(model_info, &address)
+            .and_then(|macro_param| macro_param.implied_values(byte))
+        {
+            for (implied_address, value) in implied_values {
+                writes.push((implied_address.to_vec(), value));
+            }
+        }
+        next_address(&mut address);
+    }
+    writes
+}
+
+/// A drum instrument parameter in a [SynthState], see
+/// [SynthState::drum_instruments].
+#[derive(Debug)]
+pub struct DrumInstrumentState {
+    /// 0 for MAP1, 1 for MAP2.
+    pub map: u8,
+    /// The note number of the drum instrument.
+    pub key: u8,
+    /// The address of the parameter, relative to the drum map, see
+    /// [GS_DRUM_SETUP_PARAMS].
+    pub suffix: u8,
+    /// The name from [GS_DRUM_SETUP_PARAMS].
+    pub name: &'static str,
+    pub data: u8,
+}
+
+/// The current state of a synth, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SynthState {
+    /// The device ID the synth responds to, besides the broadcast ID.
+    pub device_id: DeviceId,
+    /// Data at each address, for each model in [MODELS], keyed by model ID.
+    memory: BTreeMap<&'static [u8], BTreeMap<Vec<u8>, u8>>,
+    channels: [ChannelState; 16],
+    /// The model ID and address of each byte written, if recording, see
+    /// [SynthState::record_writes].
+    written: Option<Vec<(ModelId<'static>, Vec<u8>)>>,
+}
+
+/// A parameter and its data in a [SynthState], see [SynthState::parameters].
+#[derive(Debug)]
+pub struct ParameterState {
+    pub model_info: &'static ModelInfo,
+    pub block_name: &'static str,
+    /// Full address of the parameter.
+    pub address: Vec<u8>,
+    pub param: &'static Parameter,
+    /// The current data, if known.
+    pub data: Option<Vec<u8>>,
+    /// The data after a reset, if known.
+    pub default: Option<Vec<u8>>,
+}
+
+impl SynthState {
+    /// The state of a synth with the given device ID, after a reset.
+    pub fn new(device_id: DeviceId) -> SynthState {
+        let mut state = SynthState::unknown(device_id);
+        state.reset();
+        state
+    }
+
+    /// The state of a synth with the given device ID that may have been used
+    /// before, so none of its data is known.
+    pub fn unknown(device_id: DeviceId) -> SynthState {
+        SynthState {
+            device_id,
+            memory: BTreeMap::new(),
+            channels: Default::default(),
+            written: None,
+        }
+    }
+
+    /// Replay the SysExes and channel messages in `data` up to and including
+    /// `until`, starting from the defaults. SysExes come before channel
+    /// messages at the same time, since that's usually setup for the notes.
+    pub fn replay(data: &MidiData, device_id: DeviceId, until: AbsoluteTime) -> SynthState {
+        let mut state = SynthState::new(device_id);
+        state.apply_events(data, until, |_, _| ());
+        state
+    }
+
+    /// Apply the SysExes and channel messages in `data` up to and including
+    /// `until`, in the same order as [SynthState::replay], calling
+    /// `after_event` with the time of each one after it is applied.
+    pub fn apply_events(
+        &mut self,
+        data: &MidiData,
+        until: AbsoluteTime,
+        mut after_event: impl FnMut(AbsoluteTime, &mut SynthState),
+    ) {
+        let mut sysexes: Vec<_> = data
+            .other_events
+            .iter()
+            .filter(|&&(time, _)| time <= until)
+            .collect();
+        sysexes.sort_by_key(|&&(time, _)| time);
+        let mut messages: Vec<_> = data
+            .channel_messages
+            .iter()
+            .filter(|&&(time, _)| time <= until)
+            .collect();
+        messages.sort_by_key(|&&(time, _)| time);
+
+        let mut messages = messages.into_iter().peekable();
+        for &(sysex_time, ref sysex) in sysexes {
+            while let Some(&(time, ref message)) = messages.next_if(|&&(time, _)| time < sysex_time)
+            {
+                self.apply_channel_message(message);
+                after_event(time, self);
+            }
+            self.apply_sysex(sysex);
+            after_event(sysex_time, self);
+        }
+        for &(time, ref message) in messages {
+            self.apply_channel_message(message);
+            after_event(time, self);
+        }
+    }
+
+    /// Start recording the address of each byte that is written, or that
+    /// becomes unknown, see [SynthState::take_writes].
+    pub fn record_writes(&mut self) {
+        self.written = Some(Vec::new());
+    }
+
+    /// Get the model ID and address of each byte written since the last call,
+    /// in order, with repeats. Nothing is recorded unless
+    /// [SynthState::record_writes] was called first.
+    pub fn take_writes(&mut self) -> Vec<(ModelId<'static>, Vec<u8>)> {
+        self.written
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Put every model's parameters back to their defaults, see
+    /// [Parameter::default] and [ModelInfo::default_overrides].
+    pub fn reset(&mut self) {
+        if let Some(written) = &mut self.written {
+            for (&model_id, memory) in &self.memory {
+                written.extend(memory.keys().map(|address| (model_id, address.clone())));
+            }
+        }
+        self.memory.clear();
+        for &model_info in MODELS {
+            for &(prefix, _, pam) in model_info.address_block_map {
+                for (suffix, param) in pam {
+                    if let Some(default) = param.default {
+                        self.set(model_info, &[prefix, suffix].concat(), default);
+                    }
+                }
+            }
+            for &(address, data) in model_info.default_overrides {
+                self.set(model_info, address, data);
+            }
+        }
+        self.channels = Default::default();
+    }
+
+    /// Get `size` bytes of data starting at `address`, if they're known.
+    pub fn get(&self, model_info: &ModelInfo, address: &[u8], size: usize) -> Option<Vec<u8>> {
+        let memory = self.memory.get(model_info.model_id)?;
+        let mut address = address.to_vec();
+        let mut data = Vec::with_capacity(size);
+        for _ in 0..size {
+            data.push(*memory.get(&address)?);
+            next_address(&mut address);
+        }
+        Some(data)
+    }
+
+    /// Write `data` to consecutive addresses starting at `address`, without
+    /// any side effects (see [SynthState::apply_dt1]).
+    pub fn set(&mut self, model_info: &'static ModelInfo, address: &[u8], data: &[u8]) {
+        let memory = self.memory.entry(model_info.model_id).or_default();
+        let mut address = address.to_vec();
+        for &byte in data {
+            if let Some(written) = &mut self.written {
+                written.push((model_info.model_id, address.clone()));
+            }
+            memory.insert(address.clone(), byte);
+            next_address(&mut address);
+        }
+    }
+
+    fn responds_to(&self, device_id: DeviceId) -> bool {
+        device_id == self.device_id || device_id == DV_ID_BROADCAST
+    }
+
+    /// Apply a SysEx, if the synth would respond to it. Anything that isn't a
+    /// reset or a DT1 for a model in [MODELS] with a correct checksum is
+    /// ignored.
+    pub fn apply_sysex(&mut self, sysex: &[u8]) {
+        if let Some((reset, device_id)) = parse_reset(sysex) {
+            if self.responds_to(device_id) {
+                self.reset();
+                if reset == Reset::GmSystemOn {
+                    // The SC-55mkII ignores bank select in GM mode.
+                    for part_idx in 0..16 {
+                        let address = gs_part_address(part_idx, false, GS_RX_BANK_SELECT);
+                        self.set_gs(&address, 0x00);
+                    }
+                }
+            }
+            return;
+        }
+
+        let Ok(ParsedSysEx {
+            content:
+                MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                    device_id,
+                    model_id,
+                    command:
+                        MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                            address,
+                            data,
+                            valid_checksum: true,
+                            ..
+                        }),
+                    ..
+                })),
+            ..
+        }) = parse_sysex(sysex)
+        else {
+            return;
+        };
+        let Some(&model_info) = MODELS.iter().find(|model| model.model_id == model_id) else {
+            return;
+        };
+        if !self.responds_to(device_id) {
+            return;
+        }
+
+        self.apply_dt1(model_info, address, data);
+    }
+
+    /// Write `data` starting at `address` the way a DT1 does: each byte is
+    /// written in order, and a macro parameter immediately sets the parameters
+    /// it implies.
+    pub fn apply_dt1(&mut self, model_info: &'static ModelInfo, address: &[u8], data: &[u8]) {
+        for (address, byte) in dt1_writes(model_info, address, data) {
+            self.set(model_info, &address, &[byte]);
+            if let (GS_MODEL_ID, &[0x40, block @ 0x10..=0x1F, 0x00 | 0x01]) =
+                (model_info.model_id, &address[..])
+            {
+                self.gs_drum_set_changed(block & 0x0F);
+            }
+        }
+    }
+
+    fn get_gs(&self, address: &[u8]) -> Option<u8> {
+        self.get(gs_model(), address, 1).map(|data| data[0])
+    }
+    fn set_gs(&mut self, address: &[u8], value: u8) {
+        self.set(gs_model(), address, &[value])
+    }
+    /// Whether a GS part's Rx. switch at `suffix` is on.
+    fn gs_part_receives(&self, part_idx: u8, suffix: u8) -> bool {
+        self.get_gs(&gs_part_address(part_idx, false, suffix)) == Some(0x01)
+    }
+
+    /// The drum map a GS part uses (0 for MAP1, 1 for MAP2), if it's a rhythm
+    /// part.
+    fn gs_part_drum_map(&self, part_idx: u8) -> Option<u8> {
+        match self.get_gs(&gs_part_address(part_idx, false, GS_USE_FOR_RHYTHM_PART))? {
+            0x01 => Some(0),
+            0x02 => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Selecting a drum set for a rhythm part loads the set's own drum
+    /// instrument settings into the part's drum map, which aren't known.
+    fn gs_drum_set_changed(&mut self, part_idx: u8) {
+        let Some(map) = self.gs_part_drum_map(part_idx) else {
+            return;
+        };
+        let Some(memory) = self.memory.get_mut(GS_MODEL_ID) else {
+            return;
+        };
+        let start = gs_drum_setup_address(map, 0x00, 0x00).to_vec();
+        let end = gs_drum_setup_address(map + 1, 0x00, 0x00).to_vec();
+        let forgotten: Vec<_> = memory
+            .range(start..end)
+            .map(|(address, _)| address.clone())
+            .collect();
+        for address in forgotten {
+            memory.remove(&address);
+            if let Some(written) = &mut self.written {
+                written.push((GS_MODEL_ID, address));
+            }
+        }
+    }
+
+    /// The last Bank Select (CC#0) value on `channel`, which the next program
+    /// change on it uses.
+    pub fn bank_select(&self, channel: u8) -> u8 {
+        self.channels[channel as usize].bank_select
+    }
+
+    /// Apply a channel message to the GS parts that receive on its channel.
+    pub fn apply_channel_message(&mut self, message: &ChannelMessage) {
+        let channel = message.channel;
+        let parts: Vec<u8> = (0..16)
+            .filter(|&part_idx| {
+                self.get_gs(&gs_part_address(part_idx, false, GS_RX_CHANNEL)) == Some(channel)
+            })
+            .collect();
+
+        match message.kind {
+            ChannelMessageKind::ProgramChange(program) => {
+                let bank_select = self.channels[channel as usize].bank_select;
+                for part_idx in parts {
+                    if !self.gs_part_receives(part_idx, GS_RX_PROGRAM_CHANGE) {
+                        continue;
+                    }
+                    let bank_select = if self.gs_part_receives(part_idx, GS_RX_BANK_SELECT) {
+                        bank_select
+                    } else {
+                        0x00
+                    };
+                    self.set(
+                        gs_model(),
+                        &gs_part_address(part_idx, false, GS_TONE_NUMBER),
+                        &[bank_select, program],
+                    );
+                    self.gs_drum_set_changed(part_idx);
+                }
+            }
+            ChannelMessageKind::ControlChange { control, value } => {
+                self.apply_control_change(channel, &parts, control, value)
+            }
+            _ => (),
+        }
+    }
+
+    fn apply_control_change(&mut self, channel: u8, parts: &[u8], control: u8, value: u8) {
+        let channel_state = &mut self.channels[channel as usize];
+        let (rx_switch, suffix, value) = match control {
+            0 => {
+                channel_state.bank_select = value;
+                return;
+            }
+            7 => (GS_RX_VOLUME, GS_PART_LEVEL, value),
+            10 => (GS_RX_PANPOT, GS_PART_PANPOT, value),
+            91 => (GS_RX_CONTROL_CHANGE, GS_REVERB_SEND_LEVEL, value),
+            93 => (GS_RX_CONTROL_CHANGE, GS_CHORUS_SEND_LEVEL, value),
+            CC_NRPN_LSB..=CC_RPN_MSB => {
+                channel_state.parameter_number.select(control, value);
+                return;
+            }
+            CC_DATA_ENTRY_MSB => {
+                for &part_idx in parts {
+                    if self.gs_part_receives(part_idx, GS_RX_CONTROL_CHANGE) {
+                        self.apply_data_entry(channel, part_idx, value);
+                    }
+                }
+                return;
+            }
+            // Reset All Controllers
+            121 => {
+                channel_state.parameter_number = PARAMETER_NUMBER_NULL;
+                return;
+            }
+            _ => return,
+        };
+        for &part_idx in parts {
+            if self.gs_part_receives(part_idx, GS_RX_CONTROL_CHANGE)
+                && self.gs_part_receives(part_idx, rx_switch)
+            {
+                self.set_gs(&gs_part_address(part_idx, false, suffix), value);
+            }
+        }
+    }
+
+    fn apply_data_entry(&mut self, channel: u8, part_idx: u8, value: u8) {
+        let number = self.channels[channel as usize].parameter_number;
+        let rx_switch = match number {
+            ParameterNumber::Registered(..) => GS_RX_RPN,
+            ParameterNumber::NonRegistered(..) => GS_RX_NRPN,
+        };
+        let dt1 = parameter_number_to_gs_dt1(number, value, part_idx)
+            .or_else(|| drum_nrpn_to_gs_dt1(number, value, self.gs_part_drum_map(part_idx)?));
+        if let Some((address, data)) = dt1 {
+            if self.gs_part_receives(part_idx, rx_switch) {
+                self.set_gs(&address, data);
+            }
+        }
+    }
+
+    /// Iterate over every parameter of every model in [MODELS], with its
+    /// current data and default.
+    pub fn parameters(&self) -> impl Iterator<Item = ParameterState> + '_ {
+        MODELS.iter().flat_map(move |&model_info| {
+            model_info
+                .address_block_map
+                .iter()
+                .flat_map(move |&(prefix, block_name, pam)| {
+                    pam.iter().map(move |(suffix, param)| {
+                        let address = [prefix, suffix].concat();
+                        let default = default_data(model_info, &address, param);
+                        ParameterState {
+                            model_info,
+                            block_name,
+                            data: self.get(model_info, &address, param.size as usize),
+                            address,
+                            param,
+                            default,
+                        }
+                    })
+                })
+        })
+    }
+
+    /// Iterate over the GS drum instrument parameters (see
+    /// [GS_DRUM_SETUP_PARAMS]) whose data is known, in address order. These
+    /// have no defaults, so they're only known once they've been set since the
+    /// drum set was selected (see the [module documentation](self)).
+    pub fn drum_instruments(&self) -> impl Iterator<Item = DrumInstrumentState> + '_ {
+        let start = gs_drum_setup_address(0, 0x00, 0x00).to_vec();
+        let end = gs_drum_setup_address(2, 0x00, 0x00).to_vec();
+        self.memory
+            .get(GS_MODEL_ID)
+            .into_iter()
+            .flat_map(move |memory| memory.range(start.clone()..end.clone()))
+            .filter_map(|(address, &data)| {
+                let &[_, map_and_suffix, key] = &address[..] else {
+                    return None;
+                };
+                let &(suffix, name) = GS_DRUM_SETUP_PARAMS
+                    .iter()
+                    .find(|&&(suffix, _)| suffix == map_and_suffix & 0x0F)?;
+                Some(DrumInstrumentState {
+                    map: map_and_suffix >> 4,
+                    key,
+                    suffix,
+                    name,
+                    data,
+                })
+            })
+    }
+
+    /// Like [SynthState::parameters], but only the parameters whose data is
+    /// known and isn't the default.
+    pub fn changed_parameters(&self) -> impl Iterator<Item = ParameterState> + '_ {
+        self.parameters().filter(|param_state| {
+            param_state.data.is_some() && param_state.data != param_state.default
+        })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_replay() {
+    use crate::midi::{cc, Division};
+    use crate::sysex::roland::dt1;
+
+    let gs = gs_model();
+    // Part 1 REVERB SEND LEVEL = 100
+    let reverb_send = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x22, 0x64, 0x29, 0xF7,
+    ];
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            (10, cc(0, 7, 90)),
+            (10, cc(0, 0, 8)),
+            (
+                10,
+                ChannelMessage {
+                    channel: 0,
+                    kind: ChannelMessageKind::ProgramChange(4),
+                },
+            ),
+            // Pitch bend sensitivity on channel 10 (Part 10)
+            (20, cc(9, 101, 0)),
+            (20, cc(9, 100, 0)),
+            (20, cc(9, 6, 12)),
+            // NRPN TVF cutoff on channel 2 (Part 2)
+            (20, cc(1, 99, 0x01)),
+            (20, cc(1, 98, 0x20)),
+            (20, cc(1, 6, 0x50)),
+        ],
+        other_events: vec![
+            (0, reverb_send),
+            (10, GS_RESET.to_vec()),
+            (30, GS_RESET.to_vec()),
+        ],
+    };
+
+    // Before anything happens, everything is at the defaults.
+    let state = SynthState::replay(&data, 0x10, 0);
+    assert_eq!(state.get(gs, &[0x40, 0x11, 0x22], 1), Some(vec![100]));
+    assert_eq!(state.get(gs, &[0x40, 0x12, 0x22], 1), Some(vec![0x28]));
+    assert_eq!(state.changed_parameters().count(), 1);
+
+    // The reset at the same tick comes before the channel messages.
+    let state = SynthState::replay(&data, 0x10, 20);
+    assert_eq!(state.get(gs, &[0x40, 0x11, 0x22], 1), Some(vec![0x28]));
+    assert_eq!(state.get(gs, &[0x40, 0x11, 0x19], 1), Some(vec![90]));
+    assert_eq!(state.get(gs, &[0x40, 0x11, 0x00], 2), Some(vec![8, 4]));
+    assert_eq!(state.get(gs, &[0x40, 0x20, 0x10], 1), Some(vec![0x4C]));
+    assert_eq!(state.get(gs, &[0x40, 0x12, 0x32], 1), Some(vec![0x50]));
+    let changed: Vec<_> = state
+        .changed_parameters()
+        .map(|param_state| param_state.param.name)
+        .collect();
+    assert_eq!(
+        changed,
+        &[
+            "TONE NUMBER",
+            "PART LEVEL",
+            "TONE MODIFY 3, TVF cutoff freq.",
+            "BEND PITCH CONTROL"
+        ]
+    );
+
+    // A synth with another device ID ignores the GS SysExes.
+    let state = SynthState::replay(&data, 0x11, 0);
+    assert_eq!(state.get(gs, &[0x40, 0x11, 0x22], 1), Some(vec![0x28]));
+
+    let state = SynthState::replay(&data, 0x10, 30);
+    assert_eq!(state.changed_parameters().count(), 0);
+
+    // Drum instrument NRPNs set the drum map of a rhythm part, until a drum
+    // set is selected.
+    let nrpn = |channel, msb, lsb, value| {
+        [
+            cc(channel, 99, msb),
+            cc(channel, 98, lsb),
+            cc(channel, 6, value),
+        ]
+    };
+    let mut channel_messages = Vec::new();
+    for (time, messages) in [
+        // Part 10 (MAP1) C2 (36) level, and D2 (38) two semitones up
+        (0, nrpn(9, 0x1A, 36, 0x60)),
+        (0, nrpn(9, 0x18, 38, 0x42)),
+        // Part 1 (MAP2) E2 (40) panpot
+        (10, nrpn(0, 0x1C, 40, 0x20)),
+        // Part 3 isn't a rhythm part
+        (10, nrpn(2, 0x1D, 36, 0x05)),
+    ] {
+        channel_messages.extend(messages.into_iter().map(|message| (time, message)));
+    }
+    channel_messages.push((
+        20,
+        ChannelMessage {
+            channel: 9,
+            kind: ChannelMessageKind::ProgramChange(0x19),
+        },
+    ));
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages,
+        // Part 1 USE FOR RHYTHM PART = MAP2
+        other_events: vec![(0, dt1(gs, 0x10, &[0x40, 0x11, 0x15], &[0x02]))],
+    };
+    let drums = |until| {
+        SynthState::replay(&data, 0x10, until)
+            .drum_instruments()
+            .map(|drum| (drum.map, drum.key, drum.name, drum.data))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        drums(10),
+        &[
+            (0, 38, "PLAY NOTE NUMBER", 40),
+            (0, 36, "LEVEL", 0x60),
+            (1, 40, "PANPOT", 0x20),
+        ]
+    );
+    let state = SynthState::replay(&data, 0x10, 10);
+    assert_eq!(state.get(gs, &[0x41, 0x02, 36], 1), Some(vec![0x60]));
+    assert_eq!(drums(20), &[(1, 40, "PANPOT", 0x20)]);
+}
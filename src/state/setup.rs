@@ -0,0 +1,222 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Generating the smallest SysEx sequence that takes a synth from one
+//! [SynthState] to another, see [generate_setup].
+
+use super::{gs_model, SynthState};
+use crate::midi::MidiData;
+use crate::sysex::roland::{generate_dt1, look_up_macro, next_address, ModelInfo, MODELS};
+use crate::ui::{add_sysex_to_collection, new_sysex_collection};
+use std::collections::BTreeMap;
+
+/// A DT1 to send: the start address and the data.
+type Dt1 = (Vec<u8>, Vec<u8>);
+
+/// Number of messages first, since each one costs time on the synth's end,
+/// then number of data bytes.
+fn cost(dt1s: &[Dt1]) -> (usize, usize) {
+    (dt1s.len(), dt1s.iter().map(|(_, data)| data.len()).sum())
+}
+
+/// Work out the DT1s for one model that take `state` to `target`, given the
+/// macro parameters that will be sent (`sent_macros`). Addresses that need
+/// writing are coalesced into multi-byte DT1s, which may re-send bytes that
+/// already have the right value to bridge a gap.
+fn plan_dt1s(
+    model_info: &'static ModelInfo,
+    state: &SynthState,
+    target: &BTreeMap<Vec<u8>, u8>,
+    sent_macros: &[&[u8]],
+) -> Vec<Dt1> {
+    // The other parameters have to be compared with what the macros leave
+    // behind, not with the current state.
+    let mut after_macros = state.clone();
+    for &address in sent_macros {
+        after_macros.apply_dt1(model_info, address, &[target[address]]);
+    }
+
+    let mut dt1s: Vec<Dt1> = Vec::new();
+    // The address just after the end of the last DT1.
+    let mut end: Option<Vec<u8>> = None;
+    for (address, &value) in target {
+        let needed = sent_macros.contains(&&address[..])
+            || after_macros.get(model_info, address, 1) != Some(vec![value]);
+        if !needed {
+            continue;
+        }
+
+        // Bridging the gap is only safe if everything in it is known, and
+        // none of it is a macro parameter, which would undo other parameters.
+        let gap = end.as_ref().and_then(|end| {
+            let mut gap = Vec::new();
+            let mut gap_address = end.clone();
+            while gap_address != *address {
+                match target.get(&gap_address) {
+                    Some(&value) if look_up_macro(model_info, &gap_address).is_none() => {
+                        gap.push(value)
+                    }
+                    _ => return None,
+                }
+                next_address(&mut gap_address);
+            }
+            Some(gap)
+        });
+        if let Some(gap) = gap {
+            let data = &mut dt1s.last_mut().unwrap().1;
+            data.extend(gap);
+            data.push(value);
+        } else {
+            dt1s.push((address.clone(), vec![value]));
+        }
+
+        let mut next = address.clone();
+        next_address(&mut next);
+        end = Some(next);
+    }
+    dt1s
+}
+
+/// Generate the smallest sequence of SysExes that takes a synth from the state
+/// `from` to the state `target`, for the device ID of `target`. Only the
+/// parameter data is considered, not the channel state.
+///
+/// If `from` is [None], the synth's state is unknown, so the sequence starts
+/// with a GS reset, and continues from the defaults.
+///
+/// The DT1s for each model are sent in address order. A macro parameter (see
+/// [crate::sysex::roland::MacroParameter]) that has to change is sent, and one
+/// that doesn't is re-sent if that means fewer messages (or fewer bytes). The
+/// parameters a macro sets come after it in the address map, so address order
+/// also sends each macro before anything it would undo. Parameters at
+/// contiguous addresses are coalesced into multi-byte DT1s.
+///
+/// The result has the SysExes spaced out as by [add_sysex_to_collection], so
+/// the synth has time to finish the reset.
+pub fn generate_setup(target: &SynthState, from: Option<&SynthState>) -> MidiData {
+    let device_id = target.device_id;
+
+    let mut sysexes = Vec::new();
+    let mut state = match from {
+        Some(from) => from.clone(),
+        None => {
+            let mut gs_reset = Vec::new();
+            generate_dt1(
+                gs_model(),
+                device_id,
+                &[0x40, 0x00, 0x7F],
+                &[0x00],
+                &mut gs_reset,
+            );
+            sysexes.push(gs_reset);
+            SynthState::new(device_id)
+        }
+    };
+
+    for &model_info in MODELS {
+        let Some(target_memory) = target.memory.get(model_info.model_id) else {
+            continue;
+        };
+
+        let known_macros = model_info
+            .macro_map
+            .iter()
+            .map(|macro_param| macro_param.address)
+            .filter(|&address| target_memory.contains_key(address));
+        let (mut sent_macros, unchanged_macros): (Vec<_>, Vec<_>) =
+            known_macros.partition(|&address| {
+                state.get(model_info, address, 1) != Some(vec![target_memory[address]])
+            });
+        for address in unchanged_macros {
+            let without = plan_dt1s(model_info, &state, target_memory, &sent_macros);
+            sent_macros.push(address);
+            let with = plan_dt1s(model_info, &state, target_memory, &sent_macros);
+            if cost(&with) >= cost(&without) {
+                sent_macros.pop();
+            }
+        }
+
+        for (address, data) in plan_dt1s(model_info, &state, target_memory, &sent_macros) {
+            state.apply_dt1(model_info, &address, &data);
+            let mut sysex = Vec::new();
+            generate_dt1(model_info, device_id, &address, &data, &mut sysex);
+            sysexes.push(sysex);
+        }
+        debug_assert!(target_memory
+            .iter()
+            .all(|(address, &value)| state.get(model_info, address, 1) == Some(vec![value])));
+    }
+
+    let mut midi_data = new_sysex_collection();
+    for sysex in sysexes {
+        add_sysex_to_collection(&mut midi_data, sysex);
+    }
+    midi_data
+}
+
+#[cfg(test)]
+#[test]
+fn test_generate_setup() {
+    use crate::state::GS_RESET;
+
+    let gs = gs_model();
+    let sysexes = |midi_data: MidiData| -> Vec<Vec<u8>> {
+        midi_data
+            .other_events
+            .into_iter()
+            .map(|(_, sysex)| sysex)
+            .collect()
+    };
+
+    // REVERB MACRO = Plate, REVERB TIME = 100, Part 1 PART LEVEL = 90 and
+    // PANPOT = 32.
+    let mut target = SynthState::new(0x10);
+    target.apply_dt1(gs, &[0x40, 0x01, 0x30], &[0x05]);
+    target.apply_dt1(gs, &[0x40, 0x01, 0x34], &[100]);
+    target.apply_dt1(gs, &[0x40, 0x11, 0x19], &[90]);
+    target.apply_dt1(gs, &[0x40, 0x11, 0x1C], &[32]);
+
+    // The macro and the tweak fit in one DT1, and so do the two part
+    // parameters, by re-sending the two in between.
+    let gap = target.get(gs, &[0x40, 0x11, 0x1A], 2).unwrap();
+    assert_eq!(
+        sysexes(generate_setup(&target, None)),
+        &[
+            GS_RESET.to_vec(),
+            vec![
+                0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x01, 0x30, 0x05, 0x05, 0x00, 0x40, 0x64, 0x61,
+                0xF7
+            ],
+            {
+                let mut sysex = Vec::new();
+                generate_dt1(
+                    gs,
+                    0x10,
+                    &[0x40, 0x11, 0x19],
+                    &[&[90][..], &gap, &[32]].concat(),
+                    &mut sysex,
+                );
+                sysex
+            },
+        ]
+    );
+
+    // Nothing to do.
+    assert!(sysexes(generate_setup(&target, Some(&target))).is_empty());
+
+    // Undoing three tweaks is cheapest by re-sending the unchanged macro.
+    let mut from = SynthState::new(0x10);
+    from.apply_dt1(gs, &[0x40, 0x01, 0x31], &[0x07]);
+    from.apply_dt1(gs, &[0x40, 0x01, 0x34], &[0x10]);
+    from.apply_dt1(gs, &[0x40, 0x01, 0x35], &[0x05]);
+    assert_eq!(
+        sysexes(generate_setup(&SynthState::new(0x10), Some(&from))),
+        &[vec![
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x01, 0x30, 0x04, 0x0B, 0xF7
+        ]]
+    );
+}
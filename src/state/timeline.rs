@@ -0,0 +1,164 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Following each parameter through a [MidiData], see [parameter_timeline].
+
+use super::SynthState;
+use crate::midi::{AbsoluteTime, MidiData};
+use crate::sysex::roland::{next_address, DeviceId, ModelId, ModelInfo, Parameter, MODELS};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The changes to one parameter over time, see [parameter_timeline].
+#[derive(Debug)]
+pub struct ParameterHistory {
+    pub model_info: &'static ModelInfo,
+    pub block_name: &'static str,
+    /// Full address of the parameter.
+    pub address: Vec<u8>,
+    pub param: &'static Parameter,
+    /// The data at the start, if known.
+    pub initial: Option<Vec<u8>>,
+    /// The time of each change, and the new data, if known.
+    pub changes: Vec<(AbsoluteTime, Option<Vec<u8>>)>,
+}
+
+/// Where to find the parameter a byte belongs to: the index of its model in
+/// [MODELS], and the full address of the parameter.
+type ParameterKey = (usize, Vec<u8>);
+
+/// Replay `data` for a synth with the device ID `device_id` (see
+/// [SynthState::replay]), and find each time that a parameter's data changes,
+/// whether because of a SysEx or a channel message. Parameters that never
+/// change are left out. The result is in the order of [MODELS] and then of
+/// the addresses, so parameters in the same block (e.g. the same part) are
+/// together.
+pub fn parameter_timeline(data: &MidiData, device_id: DeviceId) -> Vec<ParameterHistory> {
+    let mut byte_owners: BTreeMap<(ModelId<'static>, Vec<u8>), ParameterKey> = BTreeMap::new();
+    let mut params: BTreeMap<ParameterKey, (&'static str, &'static Parameter)> = BTreeMap::new();
+    for (model_idx, &model_info) in MODELS.iter().enumerate() {
+        for &(prefix, block_name, pam) in model_info.address_block_map {
+            for (suffix, param) in pam {
+                let address = [prefix, suffix].concat();
+                let mut byte_address = address.clone();
+                for _ in 0..param.size {
+                    byte_owners.insert(
+                        (model_info.model_id, byte_address.clone()),
+                        (model_idx, address.clone()),
+                    );
+                    next_address(&mut byte_address);
+                }
+                params.insert((model_idx, address), (block_name, param));
+            }
+        }
+    }
+
+    let mut state = SynthState::new(device_id);
+    let initial = state.clone();
+    state.record_writes();
+
+    let mut histories: BTreeMap<ParameterKey, ParameterHistory> = BTreeMap::new();
+    state.apply_events(data, AbsoluteTime::MAX, |time, state| {
+        let touched: BTreeSet<&ParameterKey> = state
+            .take_writes()
+            .into_iter()
+            .filter_map(|write| byte_owners.get(&write))
+            .collect();
+        for key in touched {
+            let model_info = MODELS[key.0];
+            let (block_name, param) = params[key];
+            let data = state.get(model_info, &key.1, param.size as usize);
+            let history = histories
+                .entry(key.clone())
+                .or_insert_with(|| ParameterHistory {
+                    model_info,
+                    block_name,
+                    address: key.1.clone(),
+                    param,
+                    initial: initial.get(model_info, &key.1, param.size as usize),
+                    changes: Vec::new(),
+                });
+            let last = history
+                .changes
+                .last()
+                .map_or(&history.initial, |(_, data)| data);
+            if *last != data {
+                history.changes.push((time, data));
+            }
+        }
+    });
+
+    histories
+        .into_values()
+        .filter(|history| !history.changes.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn test_parameter_timeline() {
+    use crate::midi::{cc, ChannelMessage, ChannelMessageKind, Division};
+    use crate::state::GS_RESET;
+
+    // Part 1 REVERB SEND LEVEL = 100
+    let reverb_send = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x22, 0x64, 0x29, 0xF7,
+    ];
+    let data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            // Also REVERB SEND LEVEL, but the same as before.
+            (20, cc(0, 91, 100)),
+            (30, cc(0, 91, 50)),
+            (40, cc(0, 0, 8)),
+            (
+                40,
+                ChannelMessage {
+                    channel: 0,
+                    kind: ChannelMessageKind::ProgramChange(4),
+                },
+            ),
+        ],
+        other_events: vec![
+            (0, GS_RESET.to_vec()),
+            (10, reverb_send),
+            (50, GS_RESET.to_vec()),
+        ],
+    };
+
+    let timeline: Vec<_> = parameter_timeline(&data, 0x10)
+        .into_iter()
+        .map(|history| {
+            (
+                history.block_name,
+                history.param.name,
+                history.initial,
+                history.changes,
+            )
+        })
+        .collect();
+    assert_eq!(
+        timeline,
+        &[
+            (
+                "Patch parameters, Part 1",
+                "TONE NUMBER",
+                Some(vec![0, 0]),
+                vec![(40, Some(vec![8, 4])), (50, Some(vec![0, 0]))]
+            ),
+            (
+                "Patch parameters, Part 1",
+                "REVERB SEND LEVEL",
+                Some(vec![40]),
+                vec![
+                    (10, Some(vec![100])),
+                    (30, Some(vec![50])),
+                    (50, Some(vec![40]))
+                ]
+            ),
+        ]
+    );
+}
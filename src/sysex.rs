@@ -17,20 +17,56 @@
 //! The main reference here was the _MIDI 1.0 Detailed Specification_.
 
 pub mod roland;
+pub mod text;
 pub mod universal;
+pub mod yamaha;
 
 use crate::midi::format_bytes;
 use crate::ui::{Menu, MenuItemResult};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
-#[derive(Debug)]
+/// Reasons why [parse_sysex] could not make sense of some bytes. The byte
+/// offsets count from the start of the message, i.e. `F0h` is at offset 0.
+#[derive(Debug, PartialEq, Eq)]
 pub enum ParseFailure {
+    /// The message doesn't begin with `F0h`. This is the case for e.g. the
+    /// `F7h`-prefixed continuation events in Standard MIDI Files.
     NotSysEx,
-    IncompleteSysEx,
+    /// The message doesn't end with `F7h` ("End of Exclusive"). This can happen
+    /// if a SysEx has been split across several events.
+    MissingEndOfExclusive,
+    /// A byte with the high bit set (a status byte) was found somewhere other
+    /// than the start or end of the message.
+    IllegalStatusByte { offset: usize, byte: u8 },
+    /// The message is too short to contain the header fields it needs (e.g. a
+    /// manufacturer ID). `expected` is the minimum size in bytes (including
+    /// `F0h` and `F7h`) and `actual` is the size of the message.
+    TruncatedHeader { expected: usize, actual: usize },
+}
+impl Display for ParseFailure {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ParseFailure::NotSysEx => write!(f, "Not a SysEx (doesn't start with F0h)"),
+            ParseFailure::MissingEndOfExclusive => {
+                write!(f, "Incomplete SysEx (doesn't end with F7h)")
+            }
+            ParseFailure::IllegalStatusByte { offset, byte } => write!(
+                f,
+                "Illegal status byte {:02X}h at offset {} (data bytes must be 7Fh or less)",
+                byte, offset
+            ),
+            ParseFailure::TruncatedHeader { expected, actual } => write!(
+                f,
+                "Truncated SysEx header ({} bytes long, at least {} needed)",
+                actual, expected
+            ),
+        }
+    }
 }
 
 pub type ManufacturerId = u8;
 pub const MF_ID_ROLAND: ManufacturerId = 0x41;
+pub const MF_ID_YAMAHA: ManufacturerId = 0x43;
 pub const MF_ID_UNIVERSAL_NON_REAL_TIME: ManufacturerId = 0x7E;
 pub const MF_ID_UNIVERSAL_REAL_TIME: ManufacturerId = 0x7F;
 
@@ -47,15 +83,29 @@ pub struct ParsedSysEx<'a> {
 }
 impl Display for ParsedSysEx<'_> {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        match self.manufacturer_id {
-            MF_ID_ROLAND => write!(f, "Roland")?,
-            MF_ID_UNIVERSAL_NON_REAL_TIME => write!(f, "Universal Non-Real Time")?,
-            MF_ID_UNIVERSAL_REAL_TIME => write!(f, "Universal Real Time")?,
-            other => write!(f, "Manufacturer {:02X}h", other)?,
+        write!(
+            f,
+            "{}: {}",
+            manufacturer_name(self.manufacturer_id),
+            self.content
+        )
+    }
+}
+
+fn manufacturer_name(manufacturer_id: ManufacturerId) -> impl Display {
+    struct ManufacturerName(ManufacturerId);
+    impl Display for ManufacturerName {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            match self.0 {
+                MF_ID_ROLAND => write!(f, "Roland"),
+                MF_ID_YAMAHA => write!(f, "Yamaha"),
+                MF_ID_UNIVERSAL_NON_REAL_TIME => write!(f, "Universal Non-Real Time"),
+                MF_ID_UNIVERSAL_REAL_TIME => write!(f, "Universal Real Time"),
+                other => write!(f, "Manufacturer {:02X}h", other),
+            }
         }
-        write!(f, ": {}", self.content)?;
-        Ok(())
     }
+    ManufacturerName(manufacturer_id)
 }
 
 /// Generate a SysEx message or subcomponent of a SysEx message (depending on
@@ -68,6 +118,94 @@ pub trait SysExGenerator: std::fmt::Debug {
     /// message/subcomponent, and not to omit anything needed for this
     /// subcomponent.
     fn generate(&self, out: &mut Vec<u8>);
+
+    /// Generate every complete SysEx message a command from the
+    /// [generate_sysex] menu sends, each in its own [Vec]. Most commands send
+    /// just the one written by [SysExGenerator::generate], but some send
+    /// several, e.g. one per part.
+    fn generate_all(&self) -> Vec<Vec<u8>> {
+        let mut out = Vec::new();
+        self.generate(&mut out);
+        vec![out]
+    }
+}
+
+/// Kinds of byte ranges that can be identified within a SysEx message by
+/// [SysExAnnotator::annotate]. These are coarse categories, suitable for e.g.
+/// colour-coding; the precise meaning is given by [ByteSpan::label].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ByteSpanKind {
+    StartOfExclusive,
+    ManufacturerId,
+    DeviceId,
+    ModelId,
+    CommandId,
+    SubId,
+    Address,
+    Data,
+    Checksum,
+    EndOfExclusive,
+    /// Bytes that SoundPalette couldn't make sense of.
+    Unknown,
+}
+impl ByteSpanKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ByteSpanKind::StartOfExclusive => "Start of Exclusive",
+            ByteSpanKind::ManufacturerId => "Manufacturer ID",
+            ByteSpanKind::DeviceId => "Device ID",
+            ByteSpanKind::ModelId => "Model ID",
+            ByteSpanKind::CommandId => "Command ID",
+            ByteSpanKind::SubId => "Sub-ID",
+            ByteSpanKind::Address => "Address",
+            ByteSpanKind::Data => "Data",
+            ByteSpanKind::Checksum => "Checksum",
+            ByteSpanKind::EndOfExclusive => "End of Exclusive",
+            ByteSpanKind::Unknown => "Unknown",
+        }
+    }
+}
+
+/// A labelled range of bytes within a SysEx message. `range` is in bytes,
+/// counting from the `F0h` at the start of the message, which is offset 0.
+#[derive(Debug, PartialEq)]
+pub struct ByteSpan {
+    pub range: std::ops::Range<usize>,
+    pub kind: ByteSpanKind,
+    pub label: String,
+}
+
+/// Accumulates [ByteSpan]s for consecutive parts of a SysEx message, keeping
+/// track of the current byte offset.
+#[derive(Debug, Default)]
+pub struct ByteSpans {
+    offset: usize,
+    spans: Vec<ByteSpan>,
+}
+impl ByteSpans {
+    /// Add a span for the next `len` bytes.
+    pub fn push(&mut self, len: usize, kind: ByteSpanKind, label: std::fmt::Arguments) {
+        let start = self.offset;
+        self.offset += len;
+        self.spans.push(ByteSpan {
+            range: start..self.offset,
+            kind,
+            label: label.to_string(),
+        });
+    }
+
+    pub fn into_vec(self) -> Vec<ByteSpan> {
+        self.spans
+    }
+}
+
+/// Describe the individual bytes of a SysEx message or subcomponent of a SysEx
+/// message. This is the byte-level counterpart of the [Display]
+/// implementations and follows the same structure as [SysExGenerator].
+pub trait SysExAnnotator {
+    /// Push spans covering every byte that [SysExGenerator::generate] would
+    /// output for this message/subcomponent, in order.
+    fn annotate(&self, spans: &mut ByteSpans);
 }
 
 /// Contains a parsed version of something, if it was understood, or otherwise
@@ -100,16 +238,35 @@ where
     }
 }
 
+impl<T> SysExAnnotator for MaybeParsed<'_, T>
+where
+    T: SysExAnnotator,
+{
+    fn annotate(&self, spans: &mut ByteSpans) {
+        match self {
+            MaybeParsed::Parsed(parsed) => parsed.annotate(spans),
+            MaybeParsed::Unknown(&[]) => (),
+            MaybeParsed::Unknown(bytes) => spans.push(
+                bytes.len(),
+                ByteSpanKind::Unknown,
+                format_args!("(unknown)"),
+            ),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ParsedSysExBody<'a> {
     Roland(roland::ParsedRolandSysExBody<'a>),
     Universal(universal::ParsedUniversalSysExBody<'a>),
+    Yamaha(yamaha::ParsedYamahaSysExBody<'a>),
 }
 impl Display for ParsedSysExBody<'_> {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
             ParsedSysExBody::Roland(parsed) => write!(f, "{}", parsed),
             ParsedSysExBody::Universal(parsed) => write!(f, "{}", parsed),
+            ParsedSysExBody::Yamaha(parsed) => write!(f, "{}", parsed),
         }
     }
 }
@@ -118,6 +275,16 @@ impl SysExGenerator for ParsedSysExBody<'_> {
         match self {
             ParsedSysExBody::Roland(parsed) => parsed.generate(out),
             ParsedSysExBody::Universal(_) => todo!(),
+            ParsedSysExBody::Yamaha(parsed) => parsed.generate(out),
+        }
+    }
+}
+impl SysExAnnotator for ParsedSysExBody<'_> {
+    fn annotate(&self, spans: &mut ByteSpans) {
+        match self {
+            ParsedSysExBody::Roland(parsed) => parsed.annotate(spans),
+            ParsedSysExBody::Universal(parsed) => parsed.annotate(spans),
+            ParsedSysExBody::Yamaha(parsed) => parsed.annotate(spans),
         }
     }
 }
@@ -125,21 +292,41 @@ impl SysExGenerator for ParsedSysExBody<'_> {
 pub fn parse_sysex(data: &[u8]) -> Result<ParsedSysEx, ParseFailure> {
     // TODO: How to handle SysExes broken up across multiple messages?
     //       Probably the caller's responsibility?
+    let message_len = data.len();
     let &[0xF0, ref data @ ..] = data else {
         return Err(ParseFailure::NotSysEx);
     };
+
+    // Check for status bytes before checking for the terminating F7h, so that
+    // a stray F7h or other status byte somewhere in the middle can be pointed
+    // out precisely. The final byte is allowed to be F7h of course.
+    if let Some(offset) = data
+        .iter()
+        .enumerate()
+        .position(|(i, &byte)| byte > 0x7F && !(byte == 0xF7 && i == data.len() - 1))
+    {
+        return Err(ParseFailure::IllegalStatusByte {
+            // +1 for the F0h
+            offset: offset + 1,
+            byte: data[offset],
+        });
+    }
+
     let &[ref data @ .., 0xF7] = data else {
-        return Err(ParseFailure::IncompleteSysEx);
+        return Err(ParseFailure::MissingEndOfExclusive);
     };
 
-    assert!(!data.iter().any(|&byte| byte > 0x7F)); // TODO: return error?
-
     let &[manufacturer_id, ref data @ ..] = data else {
-        return Err(ParseFailure::IncompleteSysEx);
+        return Err(ParseFailure::TruncatedHeader {
+            // F0h, manufacturer ID, F7h
+            expected: 3,
+            actual: message_len,
+        });
     };
 
     let content = match (manufacturer_id, data) {
         (MF_ID_ROLAND, body) => roland::parse_sysex_body(body).map(ParsedSysExBody::Roland),
+        (MF_ID_YAMAHA, body) => yamaha::parse_sysex_body(body).map(ParsedSysExBody::Yamaha),
         (MF_ID_UNIVERSAL_NON_REAL_TIME, body) => {
             universal::parse_sysex_body(/* real_time: */ false, body)
                 .map(ParsedSysExBody::Universal)
@@ -159,6 +346,41 @@ pub fn parse_sysex(data: &[u8]) -> Result<ParsedSysEx, ParseFailure> {
     })
 }
 
+#[cfg(test)]
+#[test]
+fn test_parse_failures() {
+    assert_eq!(
+        parse_sysex(&[0xF7, 0x41, 0xF7]).unwrap_err(),
+        ParseFailure::NotSysEx
+    );
+    assert_eq!(
+        parse_sysex(&[0xF0, 0x41, 0x10]).unwrap_err(),
+        ParseFailure::MissingEndOfExclusive
+    );
+    assert_eq!(
+        parse_sysex(&[0xF0, 0x41, 0x10, 0x90, 0x7F, 0xF7]).unwrap_err(),
+        ParseFailure::IllegalStatusByte {
+            offset: 3,
+            byte: 0x90
+        }
+    );
+    assert_eq!(
+        parse_sysex(&[0xF0, 0x41, 0xF7, 0x42, 0xF7]).unwrap_err(),
+        ParseFailure::IllegalStatusByte {
+            offset: 2,
+            byte: 0xF7
+        }
+    );
+    assert_eq!(
+        parse_sysex(&[0xF0, 0xF7]).unwrap_err(),
+        ParseFailure::TruncatedHeader {
+            expected: 3,
+            actual: 2
+        }
+    );
+    assert!(parse_sysex(&[0xF0, 0x7E, 0x7F, 0x09, 0x01, 0xF7]).is_ok());
+}
+
 impl SysExGenerator for ParsedSysEx<'_> {
     fn generate(&self, out: &mut Vec<u8>) {
         out.push(0xF0);
@@ -168,6 +390,60 @@ impl SysExGenerator for ParsedSysEx<'_> {
     }
 }
 
+impl SysExAnnotator for ParsedSysEx<'_> {
+    fn annotate(&self, spans: &mut ByteSpans) {
+        spans.push(1, ByteSpanKind::StartOfExclusive, format_args!("F0h"));
+        spans.push(
+            1,
+            ByteSpanKind::ManufacturerId,
+            format_args!("{}", manufacturer_name(self.manufacturer_id)),
+        );
+        self.content.annotate(spans);
+        spans.push(1, ByteSpanKind::EndOfExclusive, format_args!("F7h"));
+    }
+}
+
+impl ParsedSysEx<'_> {
+    /// Get a list of labelled byte ranges covering the whole message. See
+    /// [SysExAnnotator].
+    pub fn byte_spans(&self) -> Vec<ByteSpan> {
+        let mut spans = ByteSpans::default();
+        self.annotate(&mut spans);
+        spans.into_vec()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_byte_spans() {
+    // Roland GS, MASTER VOLUME = 127, wrong checksum
+    let sysex = &[
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x04, 0x7F, 0x00, 0xF7,
+    ];
+    let spans = parse_sysex(sysex).unwrap().byte_spans();
+    let summary: Vec<_> = spans
+        .iter()
+        .map(|span| (span.range.clone(), span.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        &[
+            (0..1, ByteSpanKind::StartOfExclusive),
+            (1..2, ByteSpanKind::ManufacturerId),
+            (2..3, ByteSpanKind::DeviceId),
+            (3..4, ByteSpanKind::ModelId),
+            (4..5, ByteSpanKind::CommandId),
+            (5..8, ByteSpanKind::Address),
+            (8..9, ByteSpanKind::Data),
+            (9..10, ByteSpanKind::Checksum),
+            (10..11, ByteSpanKind::EndOfExclusive),
+        ]
+    );
+    assert_eq!(spans[5].label, "System parameters § MASTER VOLUME");
+    assert_eq!(spans[6].label, "7Fh = 127");
+    assert_eq!(spans[7].label, "WRONG (should be 3Dh)");
+}
+
 /// Convenience implementation of [SysExGenerator] for constant SysExes strings.
 #[derive(Debug)]
 pub struct StaticSysExGenerator(pub &'static [u8]);
@@ -194,6 +470,7 @@ pub fn generate_sysex() -> impl Menu<Box<dyn SysExGenerator>> {
             universal::generate_nrt_sysex,
         ),
         ("41h — Roland", roland::generate_sysex),
+        ("43h — Yamaha", yamaha::generate_sysex),
     ];
 
     impl Menu<Box<dyn SysExGenerator>> for SysExGeneratorMenu {
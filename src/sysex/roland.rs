@@ -14,10 +14,10 @@
 //! series use this too. I don't know about other Roland devices.
 
 use super::{
-    ManufacturerId, MaybeParsed, ParsedSysEx, ParsedSysExBody, SysExGenerator,
-    SysExGeneratorMenuTrait,
+    ByteSpanKind, ByteSpans, ManufacturerId, MaybeParsed, ParsedSysEx, ParsedSysExBody,
+    SysExAnnotator, SysExGenerator, SysExGeneratorMenuTrait,
 };
-use crate::midi::format_bytes;
+use crate::midi::{describe_controller, describe_note, format_bytes, OctaveConvention};
 use crate::ui::{Menu, MenuItemResult};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
@@ -25,6 +25,37 @@ pub const MF_ID_ROLAND: ManufacturerId = 0x41;
 
 pub type DeviceId = u8;
 
+/// Roland devices also respond to the "broadcast" device ID, like the
+/// universal SysExes.
+pub const DV_ID_BROADCAST: DeviceId = 0x7F;
+
+/// The Sound Canvas manuals number device IDs `10h` to `1Fh` as 17 to 32 on
+/// the front panel, but in a multi-unit setup it's more intuitive to talk about
+/// "unit 1" to "unit 16", counting from the default device ID `10h`.
+pub fn device_id_unit_number(device_id: DeviceId) -> Option<u8> {
+    match device_id {
+        0x10..=0x1F => Some(device_id - 0x10 + 1),
+        _ => None,
+    }
+}
+
+fn describe_device_id(device_id: DeviceId) -> impl Display {
+    struct DeviceIdDescription(DeviceId);
+    impl Display for DeviceIdDescription {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            let device_id = self.0;
+            if device_id == DV_ID_BROADCAST {
+                write!(f, "Broadcast")
+            } else if let Some(unit) = device_id_unit_number(device_id) {
+                write!(f, "Device {:02X}h (unit {})", device_id, unit)
+            } else {
+                write!(f, "Device {:02X}h", device_id)
+            }
+        }
+    }
+    DeviceIdDescription(device_id)
+}
+
 /// Variable-length quantity (see [consume_variable_length_id]).
 pub type ModelId<'a> = &'a [u8];
 
@@ -64,7 +95,7 @@ impl Display for ParsedRolandSysExBody<'_> {
                 command_id,
                 ref command,
             } => {
-                write!(f, "Device {:02X}h, ", device_id)?;
+                write!(f, "{}, ", describe_device_id(device_id))?;
                 match model_name {
                     Some(model_name) => write!(f, "{}", model_name)?,
                     _ => write!(f, "Model {}", format_bytes(model_id))?,
@@ -109,7 +140,7 @@ pub fn parse_sysex_body(body: &[u8]) -> Result<ParsedRolandSysExBody, ()> {
     // address is.
     let command = match model_info
         .ok_or(())
-        .and_then(|model_info| parse_sysex_command(model_info, command_id, body))
+        .and_then(|&model_info| parse_sysex_command(model_info, command_id, body))
     {
         Ok(parsed) => MaybeParsed::Parsed(parsed),
         Err(()) => MaybeParsed::Unknown(body),
@@ -141,6 +172,48 @@ impl SysExGenerator for ParsedRolandSysExBody<'_> {
     }
 }
 
+impl SysExAnnotator for ParsedRolandSysExBody<'_> {
+    fn annotate(&self, spans: &mut ByteSpans) {
+        let &ParsedRolandSysExBody::TypeIV {
+            device_id,
+            model_id,
+            model_name,
+            command_id,
+            ref command,
+        } = self;
+        spans.push(
+            1,
+            ByteSpanKind::DeviceId,
+            format_args!("{:02X}h", device_id),
+        );
+        match model_name {
+            Some(model_name) => spans.push(
+                model_id.len(),
+                ByteSpanKind::ModelId,
+                format_args!("{}", model_name),
+            ),
+            None => spans.push(
+                model_id.len(),
+                ByteSpanKind::ModelId,
+                format_args!("(unknown) {}", format_bytes(model_id)),
+            ),
+        }
+        match command {
+            MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 { .. }) => spans.push(
+                command_id.len(),
+                ByteSpanKind::CommandId,
+                format_args!("Data set 1"),
+            ),
+            MaybeParsed::Unknown(_) => spans.push(
+                command_id.len(),
+                ByteSpanKind::CommandId,
+                format_args!("(unknown) {}", format_bytes(command_id)),
+            ),
+        }
+        command.annotate(spans);
+    }
+}
+
 #[derive(Debug)]
 pub enum ParsedRolandSysExCommand<'a> {
     /// "Data set 1" aka "DT1". The `address` and `data` are the raw parsing
@@ -165,10 +238,117 @@ pub enum ParsedRolandSysExCommand<'a> {
         /// could be found.
         param_info: Option<&'static Parameter>,
         /// Whether the size of the data matches the parameter info that was
-        /// looked up.
+        /// looked up. A write to several whole parameters (see `block_write`)
+        /// isn't counted as the wrong size.
         invalid_size: bool,
+        /// If the parameter is a macro, the other parameters that this sets.
+        implied_values: Option<ImpliedValues>,
+        /// If the data covers several whole consecutive parameters, those
+        /// parameters.
+        block_write: Option<BlockWrite<'a>>,
     },
 }
+
+/// The full address, details and data of a parameter in a [BlockWrite].
+pub type BlockParam<'a> = (Vec<u8>, &'static Parameter, &'a [u8]);
+
+/// A DT1 whose data covers several whole consecutive parameters, e.g. one
+/// that sets a part's CHORUS SEND LEVEL and REVERB SEND LEVEL at once. Each
+/// parameter's data follows on from the previous one's.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockWrite<'a> {
+    model_info: &'static ModelInfo,
+    address: &'a [u8],
+    data: &'a [u8],
+}
+impl<'a> BlockWrite<'a> {
+    /// If `data` written at `address` covers more than one parameter, and ends
+    /// at the end of a parameter without covering any unknown addresses.
+    fn new(model_info: &'static ModelInfo, address: &'a [u8], data: &'a [u8]) -> Option<Self> {
+        let block_write = BlockWrite {
+            model_info,
+            address,
+            data,
+        };
+        let params = block_write.params()?;
+        (params.len() > 1).then_some(block_write)
+    }
+
+    fn params(&self) -> Option<Vec<BlockParam<'a>>> {
+        let mut params = Vec::new();
+        let mut address = self.address.to_vec();
+        let mut rest = self.data;
+        while !rest.is_empty() {
+            let (_, Some(param)) = look_up_parameter(self.model_info, &address) else {
+                return None;
+            };
+            if rest.len() < param.size as usize {
+                return None;
+            }
+            let (param_data, new_rest) = rest.split_at(param.size as usize);
+            params.push((address.clone(), param, param_data));
+            for _ in 0..param.size {
+                next_address(&mut address);
+            }
+            rest = new_rest;
+        }
+        Some(params)
+    }
+
+    /// Full addresses, details and data of the parameters written, in order.
+    pub fn iter(&self) -> impl Iterator<Item = BlockParam<'a>> {
+        self.params().unwrap().into_iter()
+    }
+}
+impl Display for BlockWrite<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, (_, param_info, data)) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{} => {}", param_info.name, format_bytes(data))?;
+            param_info.describe(data, f, false)?;
+            if data
+                .iter()
+                .any(|&data_byte| !param_info.value_is_valid(data_byte))
+            {
+                write!(f, " (out of range)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The parameters that a value of a [MacroParameter] also sets.
+#[derive(Clone, Copy, Debug)]
+pub struct ImpliedValues {
+    model_info: &'static ModelInfo,
+    macro_param: &'static MacroParameter,
+    value: u8,
+}
+impl ImpliedValues {
+    /// Addresses and values of the parameters that are set.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static [u8], u8)> + Clone {
+        self.macro_param.implied_values(self.value).unwrap()
+    }
+}
+impl Display for ImpliedValues {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, (address, value)) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            match look_up_parameter(self.model_info, address) {
+                (_, Some(param_info)) if param_info.size == 1 => {
+                    write!(f, "{}", param_info.name)?;
+                    param_info.describe(&[value], f, false)?;
+                }
+                _ => write!(f, "{} => {}", format_bytes(address), format_bytes(&[value]))?,
+            }
+        }
+        Ok(())
+    }
+}
 impl ParsedRolandSysExCommand<'_> {
     /// Validate the data field only. Returns [true] if enough information is
     /// available to validate the data, and it indicates an error; a return
@@ -176,15 +356,26 @@ impl ParsedRolandSysExCommand<'_> {
     /// value of [true] does not mean the data is meaningless (e.g. SoundPalette
     /// might not know of changes to a parameter in a newer model).
     fn data_is_out_of_range(&self) -> bool {
-        match self {
-            &ParsedRolandSysExCommand::DT1 {
+        match *self {
+            ParsedRolandSysExCommand::DT1 {
                 address: _,
                 data,
                 valid_checksum: _,
                 block_name_and_prefix_size: _,
-                param_info: Some(Parameter { range, .. }),
+                param_info: Some(param_info),
                 invalid_size: false,
-            } => data.iter().any(|&data_byte| !range.contains(&data_byte)),
+                implied_values: _,
+                block_write: None,
+            } => data
+                .iter()
+                .any(|&data_byte| !param_info.value_is_valid(data_byte)),
+            ParsedRolandSysExCommand::DT1 {
+                block_write: Some(block_write),
+                ..
+            } => block_write.iter().any(|(_, param_info, data)| {
+                data.iter()
+                    .any(|&data_byte| !param_info.value_is_valid(data_byte))
+            }),
             _ => false,
         }
     }
@@ -199,9 +390,21 @@ impl Display for ParsedRolandSysExCommand<'_> {
                 block_name_and_prefix_size,
                 param_info,
                 invalid_size,
+                implied_values,
+                block_write,
             } => {
                 write!(f, "Data set 1: ")?;
 
+                if let (Some((block_name, _)), Some(block_write)) =
+                    (block_name_and_prefix_size, block_write)
+                {
+                    write!(f, "{} § {}", block_name, block_write)?;
+                    if !valid_checksum {
+                        write!(f, " (WRONG CHECKSUM)")?;
+                    }
+                    return Ok(());
+                }
+
                 if let Some((block_name, prefix_size)) = block_name_and_prefix_size {
                     write!(f, "{} § ", block_name)?;
                     if let Some(param_info) = param_info {
@@ -233,7 +436,10 @@ impl Display for ParsedRolandSysExCommand<'_> {
                 }
 
                 if self.data_is_out_of_range() {
-                    write!(f, " (out of range")?;
+                    write!(f, " (out of range)")?;
+                }
+                if let Some(implied_values) = implied_values {
+                    write!(f, " (also sets {})", implied_values)?;
                 }
                 if !valid_checksum {
                     write!(f, " (WRONG CHECKSUM)")?;
@@ -244,6 +450,106 @@ impl Display for ParsedRolandSysExCommand<'_> {
     }
 }
 
+impl SysExAnnotator for ParsedRolandSysExCommand<'_> {
+    fn annotate(&self, spans: &mut ByteSpans) {
+        let &ParsedRolandSysExCommand::DT1 {
+            address,
+            data,
+            valid_checksum,
+            block_name_and_prefix_size,
+            param_info,
+            invalid_size,
+            implied_values,
+            block_write,
+        } = self;
+
+        match (block_name_and_prefix_size, param_info, block_write) {
+            (Some((block_name, _)), Some(param_info), Some(block_write)) => {
+                let (_, last_param_info, _) = block_write.iter().last().unwrap();
+                spans.push(
+                    address.len(),
+                    ByteSpanKind::Address,
+                    format_args!(
+                        "{} § {} to {}",
+                        block_name, param_info.name, last_param_info.name
+                    ),
+                )
+            }
+            (Some((block_name, _)), Some(param_info), _) => spans.push(
+                address.len(),
+                ByteSpanKind::Address,
+                format_args!("{} § {}", block_name, param_info.name),
+            ),
+            (Some((block_name, prefix_size)), None, _) => spans.push(
+                address.len(),
+                ByteSpanKind::Address,
+                format_args!(
+                    "{} § (unknown) {}",
+                    block_name,
+                    format_bytes(&address[prefix_size as usize..])
+                ),
+            ),
+            (None, _, _) => spans.push(
+                address.len(),
+                ByteSpanKind::Address,
+                format_args!("(unknown)"),
+            ),
+        }
+
+        if let Some(block_write) = block_write {
+            for (_, param_info, param_data) in block_write.iter() {
+                let mut label = format!("{} => {}", param_info.name, format_bytes(param_data));
+                param_info.describe(param_data, &mut label, false).unwrap();
+                if param_data
+                    .iter()
+                    .any(|&data_byte| !param_info.value_is_valid(data_byte))
+                {
+                    label.push_str(" (out of range)");
+                }
+                spans.push(
+                    param_data.len(),
+                    ByteSpanKind::Data,
+                    format_args!("{}", label),
+                );
+            }
+        } else {
+            for &data_byte in data {
+                let mut label = format_bytes(&[data_byte]).to_string();
+                match param_info {
+                    Some(param_info) if !invalid_size && param_info.size == 1 => {
+                        param_info
+                            .describe(&[data_byte], &mut label, false)
+                            .unwrap();
+                        if !param_info.value_is_valid(data_byte) {
+                            label.push_str(" (out of range)");
+                        }
+                    }
+                    Some(_) if invalid_size => label.push_str(" (WRONG SIZE)"),
+                    _ => (),
+                }
+                if let Some(implied_values) = implied_values {
+                    use std::fmt::Write;
+                    write!(label, " (also sets {})", implied_values).unwrap();
+                }
+                spans.push(1, ByteSpanKind::Data, format_args!("{}", label));
+            }
+        }
+
+        if valid_checksum {
+            spans.push(1, ByteSpanKind::Checksum, format_args!("correct"));
+        } else {
+            let mut body = Vec::with_capacity(address.len() + data.len());
+            body.extend_from_slice(address);
+            body.extend_from_slice(data);
+            spans.push(
+                1,
+                ByteSpanKind::Checksum,
+                format_args!("WRONG (should be {:02X}h)", generate_checksum(&body)),
+            );
+        }
+    }
+}
+
 fn compute_checksum(data: &[u8]) -> u8 {
     let mut sum: u8 = 0;
     for &byte in data {
@@ -260,7 +566,7 @@ pub fn generate_checksum(data_without_checksum: &[u8]) -> u8 {
 
 #[allow(clippy::result_unit_err)] // not much explanation can be given really
 pub fn parse_sysex_command<'a>(
-    model_info: &ModelInfo,
+    model_info: &'static ModelInfo,
     command_id: CommandId,
     body: &'a [u8],
 ) -> Result<ParsedRolandSysExCommand<'a>, ()> {
@@ -283,7 +589,19 @@ pub fn parse_sysex_command<'a>(
 
             let valid_checksum = validate_checksum(body);
             let (block_name_and_prefix_size, param_info) = look_up_parameter(model_info, address);
-            let invalid_size = param_info.map_or(false, |param| param.size as usize != data.len());
+            let block_write = BlockWrite::new(model_info, address, data);
+            let invalid_size = block_write.is_none()
+                && param_info.map_or(false, |param| param.size as usize != data.len());
+            let implied_values = match data {
+                &[value] => look_up_macro(model_info, address)
+                    .filter(|macro_param| macro_param.implied_values(value).is_some())
+                    .map(|macro_param| ImpliedValues {
+                        model_info,
+                        macro_param,
+                        value,
+                    }),
+                _ => None,
+            };
 
             Ok(ParsedRolandSysExCommand::DT1 {
                 address,
@@ -292,6 +610,8 @@ pub fn parse_sysex_command<'a>(
                 block_name_and_prefix_size,
                 param_info,
                 invalid_size,
+                implied_values,
+                block_write,
             })
         }
         _ => Err(()),
@@ -308,6 +628,8 @@ impl SysExGenerator for ParsedRolandSysExCommand<'_> {
             block_name_and_prefix_size: _,
             param_info: _,
             invalid_size: _,
+            implied_values: _,
+            block_write: _,
         } = self;
 
         let command_start = out.len();
@@ -317,6 +639,19 @@ impl SysExGenerator for ParsedRolandSysExCommand<'_> {
     }
 }
 
+/// Advance `address` by one byte. A multi-byte DT1 writes its data to
+/// consecutive addresses, but each byte of an address only has 7 bits, so e.g.
+/// the address after `40h 00h 7Fh` is `40h 01h 00h`.
+pub fn next_address(address: &mut [u8]) {
+    for byte in address.iter_mut().rev() {
+        if *byte < 0x7F {
+            *byte += 1;
+            return;
+        }
+        *byte = 0x00;
+    }
+}
+
 /// Uses [MODELS] to look up the name of the address block, the size of its
 /// address prefix, and the details of the parameter using an address, if
 /// possible.
@@ -350,13 +685,67 @@ pub fn look_up_parameter(
 ///
 /// `default_device_id` is the default, or sometimes only, device ID for this
 /// model. I've only seen `10h` but it seems reasonable to parameterise it.
+/// `device_id_configurable` is [false] if the device ID can't be changed, in
+/// which case only the default and broadcast device IDs are useful.
+///
+/// `macro_map` lists the parameters that also set other parameters, see
+/// [MacroParameter].
+///
+/// `default_overrides` lists full addresses whose default data byte differs
+/// from the [Parameter::default] for the parameter, because it depends on the
+/// block it's in (e.g. each part receives on a different channel by default).
 #[derive(Debug)]
 pub struct ModelInfo {
     pub model_id: ModelId<'static>,
     pub name: &'static str,
     pub default_device_id: DeviceId,
+    pub device_id_configurable: bool,
     pub address_size: u8,
     pub address_block_map: AddressBlockMap,
+    pub macro_map: MacroMap,
+    pub default_overrides: &'static [(&'static [u8], &'static [u8])],
+}
+
+/// All the [MacroParameter]s for a model.
+pub type MacroMap = &'static [MacroParameter];
+
+/// A parameter that, when set, also sets a group of other parameters to preset
+/// values, e.g. REVERB MACRO sets REVERB CHARACTER, REVERB LEVEL and so on. The
+/// manuals give these as a table with a row for each macro value.
+#[derive(Debug)]
+pub struct MacroParameter {
+    /// Full address of the macro parameter.
+    pub address: &'static [u8],
+    /// Full addresses of the parameters that the macro sets, in the order of
+    /// the values in `presets`.
+    pub implied_addresses: &'static [&'static [u8]],
+    /// For each value of the macro parameter, the values it sets the
+    /// parameters at `implied_addresses` to.
+    pub presets: &'static [(u8, &'static [u8])],
+}
+impl MacroParameter {
+    /// The addresses and values of the parameters that setting the macro
+    /// parameter to `value` also sets, if `value` is a known preset.
+    pub fn implied_values(
+        &self,
+        value: u8,
+    ) -> Option<impl Iterator<Item = (&'static [u8], u8)> + Clone> {
+        let &(_, values) = self.presets.iter().find(|&&(value2, _)| value2 == value)?;
+        Some(
+            self.implied_addresses
+                .iter()
+                .copied()
+                .zip(values.iter().copied()),
+        )
+    }
+}
+
+/// Find the [MacroParameter] at `address`, if there is one.
+pub fn look_up_macro(model_info: &ModelInfo, address: &[u8]) -> Option<&'static MacroParameter> {
+    model_info
+        .macro_map
+        .iter()
+        .find(|macro_param| macro_param.address == address)
 }
 
 /// "Address Block Map" in the style of the Roland SC-7 owner's manual.
@@ -380,10 +769,13 @@ pub struct Parameter {
     /// "Data" column. This is a [std::ops::RangeInclusive] because it's the
     /// style used in Roland documentation and it's compact.
     pub range: std::ops::RangeInclusive<u8>,
+    /// "Default Value": the data this parameter has after a reset, if known.
+    /// Some defaults depend on the block, e.g. a part's Rx. CHANNEL, see
+    /// [ModelInfo::default_overrides].
+    pub default: Option<&'static [u8]>,
     /// "Description": a meaning for the values of this parameter.
     /// Please ensure this matches the range.
     pub description: ParameterValueDescription,
-    // TODO: Default Value?
 }
 
 /// Meaning for the values of a parameter, trying to match the "Description" of
@@ -403,17 +795,65 @@ pub enum ParameterValueDescription {
     /// The range can be bigger, smaller, or the same size, so the mapping is
     /// usually unspecified and approximate. In these cases, `unit_in_range`
     /// gives the name of the unit and a range in that unit to map to.
+    ///
+    /// A few values may have a special meaning instead of a numeric one, e.g.
+    /// "Random" for a panpot. These are listed in `special_values` and are
+    /// displayed like a [ParameterValueDescription::Enum].
     Numeric {
         zero_offset: u8,
         unit_in_range: Option<(std::ops::RangeInclusive<f32>, &'static str)>,
+        special_values: &'static [(&'static [u8], &'static str)],
     },
-    /// There is an enumerated list of values for this parameter.
+    /// The value is a MIDI note number, e.g. for a key range. Displayed as a
+    /// note name using the given octave convention, plus the number.
+    Note(OctaveConvention),
+    /// The value is a MIDI control change number, e.g. for a controller
+    /// assignment. Displayed as the controller's name, plus the number.
+    Controller,
+    /// There is an enumerated list of values for this parameter. The list
+    /// doesn't have to cover every value in the range: values that aren't
+    /// listed are holes and are invalid.
     Enum(&'static [(&'static [u8], &'static str)]),
     /// Something else that isn't handled yet.
     Other,
 }
 
 impl Parameter {
+    /// The name for a value with a special meaning, if `data` is one. This is
+    /// the name of an enumerated value, or of a special value of a numeric
+    /// parameter.
+    pub fn value_name(&self, data: &[u8]) -> Option<&'static str> {
+        let values = match self.description {
+            ParameterValueDescription::Enum(values) => values,
+            ParameterValueDescription::Numeric { special_values, .. } => special_values,
+            _ => return None,
+        };
+        values
+            .iter()
+            .find(|&&(data2, _)| data2 == data)
+            .map(|&(_, name)| name)
+    }
+
+    /// Returns [true] if `data_byte` is valid for this parameter: it must be
+    /// within the range, and not in a hole in an enum.
+    pub fn value_is_valid(&self, data_byte: u8) -> bool {
+        self.range.contains(&data_byte)
+            && match self.description {
+                ParameterValueDescription::Enum(values) => {
+                    values.iter().any(|&(data2, _)| data2 == [data_byte])
+                }
+                _ => true,
+            }
+    }
+
+    /// Iterate over the valid values for a single-byte parameter, in order.
+    /// See [Parameter::value_is_valid].
+    pub fn valid_values(&self) -> impl Iterator<Item = u8> + Clone + '_ {
+        self.range
+            .clone()
+            .filter(|&data_byte| self.value_is_valid(data_byte))
+    }
+
     /// Write a human-readable description of the data `data`, if interpreted as
     /// a value for this parameter, to `write_to`. If the result is not empty,
     /// it always begins with a space, usually followed by an equals sign and a
@@ -428,15 +868,39 @@ impl Parameter {
     ) -> FmtResult {
         assert_eq!(data.len(), self.size as usize);
 
+        match (&self.description, data) {
+            (&ParameterValueDescription::Note(convention), &[key]) => {
+                return write!(write_to, " = {}", describe_note(key, convention));
+            }
+            (&ParameterValueDescription::Controller, &[control]) => {
+                return write!(write_to, " = {}", describe_controller(control));
+            }
+            _ => (),
+        }
+
         let zero_offset = match self.description {
             ParameterValueDescription::Numeric { zero_offset, .. } => zero_offset,
-            ParameterValueDescription::Enum(_) => 0,
+            ParameterValueDescription::Note(_)
+            | ParameterValueDescription::Controller
+            | ParameterValueDescription::Enum(_) => 0,
             ParameterValueDescription::Other => return Ok(()),
         };
 
         let differing_signs_at_range_ends =
             zero_offset != *self.range.start() && zero_offset != *self.range.end();
 
+        if let ParameterValueDescription::Numeric { .. } = self.description {
+            // Special values don't mean their number.
+            if let Some(name) = self.value_name(data) {
+                if em_dash {
+                    write!(write_to, " — {}", name)?;
+                } else {
+                    write!(write_to, " [{}]", name)?;
+                }
+                return Ok(());
+            }
+        }
+
         if let &[single_byte_value] = data {
             if differing_signs_at_range_ends {
                 write!(
@@ -454,8 +918,8 @@ impl Parameter {
         }
 
         match self.description {
-            ParameterValueDescription::Enum(values) => {
-                if let Some(&(_, name)) = values.iter().find(|&&(data2, _)| data2 == data) {
+            ParameterValueDescription::Enum(_) => {
+                if let Some(name) = self.value_name(data) {
                     if em_dash {
                         write!(write_to, " — {}", name)?;
                     } else {
@@ -464,57 +928,33 @@ impl Parameter {
                 }
             }
             ParameterValueDescription::Numeric {
-                zero_offset: midi_zero,
-                unit_in_range: Some((ref unit_range, unit)),
+                unit_in_range: Some(_),
+                ..
             } => {
                 let &[midi_value] = data else {
                     todo!();
                 };
-                let midi_value = midi_value as f32;
-
-                let midi_range = &self.range;
-                assert!(midi_range.start() < midi_range.end());
-                let midi_min = *midi_range.start() as f32;
-                let midi_max = *midi_range.end() as f32;
-                let midi_range = midi_max - midi_min;
-
-                // TODO: Support range flips eventually?
-                assert!(unit_range.start() < unit_range.end());
-                let unit_min: f32 = *unit_range.start();
-                let unit_max: f32 = *unit_range.end();
-                let unit_range = unit_max - unit_min;
+                let unit_value = self.value_in_unit(midi_value).unwrap();
+                let (unit_range, unit) = self.unit_range().unwrap();
 
                 // The exact way the MIDI data byte maps to the actual unit is
-                // not specified, hence the “approximately equal to” sign and
-                // imprecise figures. I don't and can't know if this rounding is
-                // correct. The most important property is that it rounds the
-                // known zero value to zero, to avoid confusion for the very
-                // common case where the range is symmetrical in the destination
-                // unit but not in the MIDI byte values, e.g. -20Hz to +20Hz
-                // versus 00h to 7Fh with with zero at 40h.
-                let unit_value = (midi_value - midi_zero as f32) * (unit_range / midi_range);
-
+                // usually not specified, hence the “approximately equal to”
+                // sign and imprecise figures. See [Parameter::value_in_unit].
                 // Occasionally the mapping is actually exact (e.g. key shift)
-                if unit_range == midi_range {
+                if self.unit_mapping_is_exact() {
                     write!(write_to, " [= ")?;
                 } else {
                     write!(write_to, " [≈ ")?;
                 }
 
-                // In order to not imply more precision than we actually have,
-                // only add decimal places if they're necessary to convey
-                // differences between steps.
-                let precision = (midi_range.log10() - unit_range.log10()).ceil().max(0.0) as usize;
-
-                let differing_signs_at_range_ends = unit_min < 0.0 && unit_max > 0.0;
-
-                if unit_value == 0.0 {
-                    write!(write_to, "0")?;
-                } else if differing_signs_at_range_ends {
-                    write!(write_to, "{:+.*}", precision, unit_value)?;
-                } else {
-                    write!(write_to, "{:.*}", precision, unit_value)?;
-                }
+                let differing_signs_at_range_ends =
+                    *unit_range.start() < 0.0 && *unit_range.end() > 0.0;
+                write_unit_value(
+                    write_to,
+                    unit_value,
+                    self.unit_precision(),
+                    differing_signs_at_range_ends,
+                )?;
 
                 write!(write_to, " {}]", unit)?;
             }
@@ -525,20 +965,396 @@ impl Parameter {
     }
 }
 
+impl Parameter {
+    /// If this parameter's values map to a range in some unit (see
+    /// [ParameterValueDescription::Numeric]), return that range and the name
+    /// of the unit.
+    pub fn unit_range(&self) -> Option<(std::ops::RangeInclusive<f32>, &'static str)> {
+        match self.description {
+            ParameterValueDescription::Numeric {
+                unit_in_range: Some((ref unit_range, unit)),
+                ..
+            } => Some((unit_range.clone(), unit)),
+            _ => None,
+        }
+    }
+
+    /// [true] if each data byte is exactly one step in the unit, e.g. for
+    /// key shifts in semitones.
+    fn unit_mapping_is_exact(&self) -> bool {
+        match self.description {
+            ParameterValueDescription::Numeric {
+                unit_in_range: Some((ref unit_range, _)),
+                ..
+            } => {
+                let midi_range = (*self.range.end() - *self.range.start()) as f32;
+                *unit_range.end() - *unit_range.start() == midi_range
+            }
+            _ => false,
+        }
+    }
+
+    /// If this parameter's values map to a range in some unit (see
+    /// [Parameter::unit_range]), convert the data byte `value` to that unit.
+    pub fn value_in_unit(&self, value: u8) -> Option<f32> {
+        let unit_range = match self.description {
+            ParameterValueDescription::Numeric {
+                zero_offset: midi_zero,
+                unit_in_range: Some((ref unit_range, _)),
+                ..
+            } => {
+                if self.value_name(&[value]).is_some() {
+                    return None;
+                }
+                (midi_zero, unit_range)
+            }
+            _ => return None,
+        };
+        let (midi_zero, unit_range) = unit_range;
+
+        let midi_range = &self.range;
+        assert!(midi_range.start() < midi_range.end());
+        let midi_range = (*midi_range.end() - *midi_range.start()) as f32;
+
+        // TODO: Support range flips eventually?
+        assert!(unit_range.start() < unit_range.end());
+        let unit_range = *unit_range.end() - *unit_range.start();
+
+        // I don't and can't know if this rounding is correct. The most
+        // important property is that it rounds the known zero value to zero, to
+        // avoid confusion for the very common case where the range is
+        // symmetrical in the destination unit but not in the MIDI byte values,
+        // e.g. -20Hz to +20Hz versus 00h to 7Fh with with zero at 40h.
+        Some((value as f32 - midi_zero as f32) * (unit_range / midi_range))
+    }
+
+    /// The number of decimal places to use when displaying a value in this
+    /// parameter's unit. In order to not imply more precision than we actually
+    /// have, decimal places are only added if they're necessary to convey
+    /// differences between steps.
+    fn unit_precision(&self) -> usize {
+        let ParameterValueDescription::Numeric {
+            unit_in_range: Some((ref unit_range, _)),
+            ..
+        } = self.description
+        else {
+            return 0;
+        };
+        let midi_range = (*self.range.end() - *self.range.start()) as f32;
+        let unit_range = *unit_range.end() - *unit_range.start();
+        (midi_range.log10() - unit_range.log10()).ceil().max(0.0) as usize
+    }
+
+    /// The inverse of [Parameter::value_in_unit]: find the data byte whose
+    /// value is nearest to `amount` in `unit`. `unit` doesn't have to be the
+    /// parameter's own unit if it can be converted, e.g. semitones can be used
+    /// for a parameter in cents. The amount must be within the parameter's
+    /// range, but is otherwise rounded to the nearest step.
+    pub fn value_for_unit(&self, amount: f32, unit: &str) -> Result<UnitValue, UnitValueError> {
+        let Some((unit_range, own_unit)) = self.unit_range() else {
+            return Err(UnitValueError::NoUnit);
+        };
+
+        let Some((unit, factor)) = unit_conversion(unit, own_unit) else {
+            return Err(UnitValueError::IncompatibleUnit {
+                requested: unit.to_string(),
+                unit: own_unit,
+            });
+        };
+
+        let target = amount * factor;
+        let candidates = self
+            .valid_values()
+            .filter_map(|value| Some((value, self.value_in_unit(value)?)));
+
+        // The mapping might not reach the ends of the documented range in the
+        // unit, or might overshoot them, so accept anything covered by either.
+        let (min, max) = candidates.clone().fold(
+            (*unit_range.start(), *unit_range.end()),
+            |(min, max), (_, unit_value)| (min.min(unit_value), max.max(unit_value)),
+        );
+        if !(min..=max).contains(&target) {
+            return Err(UnitValueError::OutOfRange {
+                range: (*unit_range.start() / factor)..=(*unit_range.end() / factor),
+                unit,
+            });
+        }
+
+        let (value, actual) = candidates
+            .min_by(|&(_, a), &(_, b)| (a - target).abs().total_cmp(&(b - target).abs()))
+            .unwrap();
+        let actual = actual / factor;
+        Ok(UnitValue {
+            value,
+            actual,
+            error: actual - amount,
+            unit,
+            precision: (self.unit_precision() as f32 + factor.log10())
+                .ceil()
+                .max(0.0) as usize,
+        })
+    }
+}
+
+/// Write `unit_value` with `precision` decimal places, in the style of
+/// [Parameter::describe]. If `signed` is [true], non-zero values get a sign.
+fn write_unit_value(
+    write_to: &mut (impl std::fmt::Write + ?Sized),
+    unit_value: f32,
+    precision: usize,
+    signed: bool,
+) -> FmtResult {
+    if unit_value.is_infinite() {
+        return write!(write_to, "{}∞", if unit_value < 0.0 { "-" } else { "+" });
+    }
+    // Avoid "+0.0" and "-0" when the value is only nearly zero.
+    if (unit_value * 10f32.powi(precision as i32)).round() == 0.0 {
+        write!(write_to, "0")
+    } else if signed {
+        write!(write_to, "{:+.*}", precision, unit_value)
+    } else {
+        write!(write_to, "{:.*}", precision, unit_value)
+    }
+}
+
+/// The units that can be converted between: a name, other accepted spellings,
+/// the unit it's measured in, and how many of those make one of this unit.
+const UNITS: &[(&str, &[&str], &str, f32)] = &[
+    ("cents", &["cent"], "cents", 1.0),
+    ("semitones", &["semitone"], "cents", 100.0),
+    ("%", &["percent"], "%", 1.0),
+    ("Hz", &[], "Hz", 1.0),
+];
+
+/// Find out how to convert an amount in the unit named `from` to the unit
+/// `to`. Returns the canonical name of `from` and the factor to multiply by.
+fn unit_conversion(from: &str, to: &'static str) -> Option<(&'static str, f32)> {
+    let find = |name: &str| {
+        UNITS.iter().find(|&&(unit, aliases, _, _)| {
+            unit.eq_ignore_ascii_case(name)
+                || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+        })
+    };
+    if from.eq_ignore_ascii_case(to) {
+        return Some((to, 1.0));
+    }
+    let &(from, _, from_base, from_factor) = find(from)?;
+    let &(_, _, to_base, to_factor) = find(to)?;
+    (from_base == to_base).then_some((from, from_factor / to_factor))
+}
+
+/// A data byte chosen by [Parameter::value_for_unit].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnitValue {
+    pub value: u8,
+    /// The value that the data byte actually maps to, in `unit`.
+    pub actual: f32,
+    /// The rounding error, i.e. `actual` minus the requested amount.
+    pub error: f32,
+    /// The canonical name of the unit the amount was requested in.
+    pub unit: &'static str,
+    /// The number of decimal places worth displaying for amounts in `unit`.
+    pub precision: usize,
+}
+
+impl Display for UnitValue {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{:02X}h (≈ ", self.value)?;
+        write_unit_value(f, self.actual, self.precision, false)?;
+        write!(f, " {}, off by ", self.unit)?;
+        write_unit_value(f, self.error, self.precision, true)?;
+        write!(f, " {})", self.unit)
+    }
+}
+
+/// Reasons [Parameter::value_for_unit] can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnitValueError {
+    /// The parameter's values aren't mapped to a unit.
+    NoUnit,
+    /// The requested unit can't be converted to the parameter's unit.
+    IncompatibleUnit {
+        requested: String,
+        unit: &'static str,
+    },
+    /// The requested amount is outside the parameter's range. The range is in
+    /// the requested unit.
+    OutOfRange {
+        range: std::ops::RangeInclusive<f32>,
+        unit: &'static str,
+    },
+}
+
+impl Display for UnitValueError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            UnitValueError::NoUnit => write!(f, "values are not in any unit"),
+            UnitValueError::IncompatibleUnit { requested, unit } => {
+                write!(f, "{:?} can't be converted to {}", requested, unit)
+            }
+            UnitValueError::OutOfRange { range, unit } => write!(
+                f,
+                "out of range ({} to {} {})",
+                range.start(),
+                range.end(),
+                unit
+            ),
+        }
+    }
+}
+
+/// If `sysex` is a Roland "Type IV" SysEx (see [ParsedRolandSysExBody]),
+/// change its device ID to `device_id` and return [true]. Otherwise, leave it
+/// untouched and return [false]. The checksum doesn't cover the device ID, so
+/// it remains valid. Returns an error if `device_id` isn't a data byte.
+pub fn set_device_id(sysex: &mut [u8], device_id: DeviceId) -> Result<bool, String> {
+    if device_id > 0x7F {
+        return Err(format!("{:02X}h is not a valid device ID", device_id));
+    }
+    let Ok(ParsedSysEx {
+        manufacturer_id: MF_ID_ROLAND,
+        content: MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV { .. })),
+    }) = super::parse_sysex(sysex)
+    else {
+        return Ok(false);
+    };
+    // F0h, manufacturer ID, device ID
+    sysex[2] = device_id;
+    Ok(true)
+}
+
+/// If `sysex` is a Roland DT1 with a wrong checksum, correct it and return the
+/// wrong and the correct checksum. Otherwise, leave it untouched.
+pub fn fix_checksum(sysex: &mut [u8]) -> Option<(u8, u8)> {
+    let Ok(ParsedSysEx {
+        manufacturer_id: MF_ID_ROLAND,
+        content:
+            MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                command:
+                    MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                        address,
+                        data,
+                        valid_checksum: false,
+                        ..
+                    }),
+                ..
+            })),
+    }) = super::parse_sysex(sysex)
+    else {
+        return None;
+    };
+    // The checksum covers the address and data, and is followed by F7h.
+    let checksum_idx = sysex.len() - 2;
+    let body_start = checksum_idx - address.len() - data.len();
+    let wrong = sysex[checksum_idx];
+    sysex[checksum_idx] = generate_checksum(&sysex[body_start..checksum_idx]);
+    Some((wrong, sysex[checksum_idx]))
+}
+
+/// Write a complete DT1 SysEx message (including `F0h` and `F7h`) for
+/// `model_info` to `out`, with a correct checksum.
+pub fn generate_dt1(
+    model_info: &ModelInfo,
+    device_id: DeviceId,
+    address: &[u8],
+    data: &[u8],
+    out: &mut Vec<u8>,
+) {
+    ParsedSysEx {
+        manufacturer_id: MF_ID_ROLAND,
+        content: MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+            device_id,
+            model_id: model_info.model_id,
+            model_name: None, // meaningless,
+            command_id: CM_ID_DT1,
+            command: MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                address,
+                data,
+                // meaningless stuff
+                param_info: None,
+                valid_checksum: false,
+                block_name_and_prefix_size: None,
+                invalid_size: false,
+                implied_values: None,
+                block_write: None,
+            }),
+        })),
+    }
+    .generate(out)
+}
+
+/// [generate_dt1] into a new [Vec], for tests.
+#[cfg(test)]
+pub fn dt1(model_info: &ModelInfo, device_id: DeviceId, address: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut sysex = Vec::new();
+    generate_dt1(model_info, device_id, address, data, &mut sysex);
+    sysex
+}
+
+/// Write DT1 SysExes to `out` that set the macro parameter at `macro_address`
+/// (see [MacroParameter]) to `value`, and then override some of the parameters
+/// it sets with `tweaks`, which are pairs of a full address and a value. The
+/// macro always comes first, because it would undo any tweak sent before it.
+/// Tweaks that set a parameter to the value the macro already gives it are
+/// left out.
+///
+/// Returns [Err] if there is no macro parameter at `macro_address`, if `value`
+/// isn't one of its presets, or if a tweak is for the macro parameter itself.
+#[allow(clippy::result_unit_err)]
+pub fn generate_macro_with_tweaks(
+    model_info: &ModelInfo,
+    device_id: DeviceId,
+    macro_address: &[u8],
+    value: u8,
+    tweaks: &[(&[u8], u8)],
+    out: &mut Vec<Vec<u8>>,
+) -> Result<(), ()> {
+    let macro_param = look_up_macro(model_info, macro_address).ok_or(())?;
+    let implied_values = macro_param.implied_values(value).ok_or(())?;
+    if tweaks.iter().any(|&(address, _)| address == macro_address) {
+        return Err(());
+    }
+
+    let mut sysex = Vec::new();
+    generate_dt1(model_info, device_id, macro_address, &[value], &mut sysex);
+    out.push(sysex);
+
+    for &(address, value) in tweaks {
+        if implied_values
+            .clone()
+            .any(|implied| implied == (address, value))
+        {
+            continue;
+        }
+        let mut sysex = Vec::new();
+        generate_dt1(model_info, device_id, address, &[value], &mut sysex);
+        out.push(sysex);
+    }
+    Ok(())
+}
+
 // All the maps are in their own module to keep this one small.
 mod maps;
 pub use maps::MODELS;
 
+mod parts;
+pub use parts::{generate_dt1_for_parts, part_blocks, spread_values, PartBlocks, PartSpread};
+
 /// Provides a menu for generating a SysEx.
 pub fn generate_sysex() -> Box<SysExGeneratorMenuTrait> {
     // These are nested like Matryoshki because the amount of state needed is
     // strictly increasing with each step.
     struct ModelsMenu;
     #[derive(Clone, Debug)]
-    struct AddressBlockMenu {
+    struct DeviceIdMenu {
         model_info: &'static ModelInfo,
     }
     #[derive(Clone, Debug)]
+    struct AddressBlockMenu {
+        up: DeviceIdMenu,
+        device_id: DeviceId,
+    }
+    #[derive(Clone, Debug)]
     struct ParameterAddressMenu {
         up: AddressBlockMenu,
         address_prefix: &'static [u8],
@@ -550,11 +1366,51 @@ pub fn generate_sysex() -> Box<SysExGeneratorMenuTrait> {
         address_suffix: &'static [u8],
         param: &'static Parameter,
     }
+    #[derive(Clone, Debug)]
+    struct ParameterUnitMenu {
+        up: ParameterValueMenu,
+        unit: &'static str,
+        /// Distance between the amounts offered.
+        step: f32,
+        /// The first amount offered, as a multiple of `step`.
+        first_step: i32,
+        /// The last amount offered, as a multiple of `step`.
+        last_step: i32,
+    }
     #[derive(Debug)]
     struct DT1Generator {
         up: ParameterValueMenu,
         value: u8,
     }
+    // For setting a parameter on several parts at once. These branch off from
+    // the AddressBlockMenu.
+    #[derive(Clone, Debug)]
+    struct PartSetMenu {
+        up: AddressBlockMenu,
+        parts: PartBlocks,
+        parameter_address_map: ParameterAddressMap,
+    }
+    #[derive(Clone, Debug)]
+    struct PartsParameterAddressMenu {
+        up: PartSetMenu,
+        parts: PartBlocks,
+    }
+    #[derive(Clone, Debug)]
+    struct PartsParameterValueMenu {
+        up: PartsParameterAddressMenu,
+        address_suffix: &'static [u8],
+        param: &'static Parameter,
+    }
+    #[derive(Clone, Debug)]
+    struct PartSpreadMenu {
+        up: PartsParameterValueMenu,
+        first: u8,
+    }
+    #[derive(Debug)]
+    struct PartsDT1Generator {
+        up: PartSpreadMenu,
+        spread: PartSpread,
+    }
 
     impl Menu<Box<dyn SysExGenerator>> for ModelsMenu {
         fn items_count(&self) -> usize {
@@ -579,27 +1435,125 @@ pub fn generate_sysex() -> Box<SysExGeneratorMenuTrait> {
             MODELS[item_idx].address_block_map.is_empty()
         }
         fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
-            MenuItemResult::Submenu(Box::new(AddressBlockMenu {
+            MenuItemResult::Submenu(Box::new(DeviceIdMenu {
                 model_info: MODELS[item_idx],
             }))
         }
     }
 
+    impl DeviceIdMenu {
+        fn item_device_id(&self, item_idx: usize) -> DeviceId {
+            // The default comes first so it's easy to pick.
+            if item_idx == 0 {
+                self.model_info.default_device_id
+            } else if item_idx == self.items_count() - 1 {
+                DV_ID_BROADCAST
+            } else {
+                // Skip over the default.
+                let device_id = 0x10 + (item_idx - 1) as u8;
+                if device_id >= self.model_info.default_device_id {
+                    device_id + 1
+                } else {
+                    device_id
+                }
+            }
+        }
+    }
+    impl Menu<Box<dyn SysExGenerator>> for DeviceIdMenu {
+        fn items_count(&self) -> usize {
+            if self.model_info.device_id_configurable {
+                // Default, the other 15 units, and broadcast
+                16 + 1
+            } else {
+                // Default and broadcast
+                2
+            }
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            let device_id = self.item_device_id(item_idx);
+            write!(
+                write_to,
+                "{:02X}h — {}",
+                device_id,
+                describe_device_id(device_id)
+            )?;
+            if device_id == self.model_info.default_device_id {
+                write!(write_to, " (default)")?;
+            }
+            Ok(())
+        }
+        fn item_redundant(&self, item_idx: usize) -> bool {
+            item_idx != 0
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            MenuItemResult::Submenu(Box::new(AddressBlockMenu {
+                up: self.clone(),
+                device_id: self.item_device_id(item_idx),
+            }))
+        }
+    }
+
+    impl AddressBlockMenu {
+        /// The per-part blocks that come in a full set (see [part_blocks]),
+        /// which get an extra item at the end of the menu for setting a
+        /// parameter on several parts at once. Each set is represented by the
+        /// index of its first part's block.
+        fn part_block_sets(&self) -> Vec<(usize, PartBlocks)> {
+            let model_info = self.up.model_info;
+            model_info
+                .address_block_map
+                .iter()
+                .enumerate()
+                .filter_map(|(block_idx, &(address_prefix, name, pam))| {
+                    let parts = part_blocks(model_info, name)?;
+                    (parts.len() > 1 && parts[0].1 == address_prefix && !pam.is_empty())
+                        .then_some((block_idx, parts))
+                })
+                .collect()
+        }
+    }
     impl Menu<Box<dyn SysExGenerator>> for AddressBlockMenu {
         fn items_count(&self) -> usize {
-            self.model_info.address_block_map.len()
+            self.up.model_info.address_block_map.len() + self.part_block_sets().len()
         }
         fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
-            let (address_prefix, name, _) = self.model_info.address_block_map[item_idx];
+            let address_block_map = self.up.model_info.address_block_map;
+            if let Some(set_idx) = item_idx.checked_sub(address_block_map.len()) {
+                let (block_idx, ref parts) = self.part_block_sets()[set_idx];
+                let (first, last) = (parts[0].0, parts[parts.len() - 1].0);
+                let name = address_block_map[block_idx].1.replacen(
+                    &format!("Part {}", first),
+                    &format!("Parts {}–{}", first, last),
+                    1,
+                );
+                return write!(write_to, "Several parts — {}", name);
+            }
+            let (address_prefix, name, _) = address_block_map[item_idx];
             write!(write_to, "{} — {}", format_bytes(address_prefix), name)
         }
         fn item_disabled(&self, item_idx: usize) -> bool {
-            let (_, _, parameter_address_map) = self.model_info.address_block_map[item_idx];
+            let Some(&(_, _, parameter_address_map)) =
+                self.up.model_info.address_block_map.get(item_idx)
+            else {
+                return false;
+            };
             parameter_address_map.is_empty()
         }
+        fn item_redundant(&self, item_idx: usize) -> bool {
+            // The SysExes are the same as for each part's own block.
+            item_idx >= self.up.model_info.address_block_map.len()
+        }
         fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
-            let (address_prefix, _, parameter_address_map) =
-                self.model_info.address_block_map[item_idx];
+            let address_block_map = self.up.model_info.address_block_map;
+            if let Some(set_idx) = item_idx.checked_sub(address_block_map.len()) {
+                let (block_idx, parts) = self.part_block_sets().swap_remove(set_idx);
+                return MenuItemResult::Submenu(Box::new(PartSetMenu {
+                    up: self.clone(),
+                    parts,
+                    parameter_address_map: address_block_map[block_idx].2,
+                }));
+            }
+            let (address_prefix, _, parameter_address_map) = address_block_map[item_idx];
             MenuItemResult::Submenu(Box::new(ParameterAddressMenu {
                 up: self.clone(),
                 address_prefix,
@@ -638,28 +1592,71 @@ pub fn generate_sysex() -> Box<SysExGeneratorMenuTrait> {
     }
 
     impl ParameterValueMenu {
-        fn values_range(&self) -> std::ops::Range<usize> {
-            // Change from inclusive to exclusive end bound
-            (*self.param.range.start() as usize)..(*self.param.range.end() as usize + 1)
+        /// If the parameter's values are mapped to a unit, but not one-to-one,
+        /// the first item is a submenu for choosing a value by its amount in
+        /// that unit, in round steps.
+        fn unit_menu(&self) -> Option<ParameterUnitMenu> {
+            let ParameterValueDescription::Numeric {
+                unit_in_range: Some((ref unit_range, unit)),
+                ..
+            } = self.param.description
+            else {
+                return None;
+            };
+            let midi_range = (*self.param.range.end() - *self.param.range.start()) as f32;
+            let unit_range_size = *unit_range.end() - *unit_range.start();
+            if unit_range_size == midi_range {
+                return None;
+            }
+
+            // Pick the smallest step of 1, 2 or 5 times a power of ten that
+            // is no finer than the steps between data bytes, so that no two
+            // items pick the same data byte.
+            let step_per_byte = unit_range_size / midi_range;
+            let magnitude = 10f32.powf(step_per_byte.log10().floor());
+            let step = [1.0, 2.0, 5.0, 10.0]
+                .into_iter()
+                .map(|multiple| multiple * magnitude)
+                .find(|&step| step >= step_per_byte)
+                .unwrap();
+
+            Some(ParameterUnitMenu {
+                up: self.clone(),
+                unit,
+                step,
+                first_step: (*unit_range.start() / step).ceil() as i32,
+                last_step: (*unit_range.end() / step).floor() as i32,
+            })
         }
         fn item_value(&self, item_idx: usize) -> u8 {
-            let value = self.values_range().start + item_idx;
-            assert!(self.values_range().contains(&value));
+            let item_idx = item_idx - self.unit_menu().is_some() as usize;
             // Currently, values can only be single MIDI data bytes (7-bit)
-            assert!(value < (1 << 7));
-            u8::try_from(value).unwrap()
+            self.param.valid_values().nth(item_idx).unwrap()
         }
     }
     impl Menu<Box<dyn SysExGenerator>> for ParameterValueMenu {
         fn items_count(&self) -> usize {
-            self.values_range().end - self.values_range().start
+            self.unit_menu().is_some() as usize + self.param.valid_values().count()
         }
         fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            if item_idx == 0 {
+                if let Some(unit_menu) = self.unit_menu() {
+                    return write!(write_to, "By amount in {}…", unit_menu.unit);
+                }
+            }
             let data = &[self.item_value(item_idx)];
             write!(write_to, "{}", format_bytes(data))?;
             self.param.describe(data, write_to, true)
         }
+        fn item_redundant(&self, item_idx: usize) -> bool {
+            item_idx == 0 && self.unit_menu().is_some()
+        }
         fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            if item_idx == 0 {
+                if let Some(unit_menu) = self.unit_menu() {
+                    return MenuItemResult::Submenu(Box::new(unit_menu));
+                }
+            }
             MenuItemResult::Command(Box::new(DT1Generator {
                 up: self.clone(),
                 value: self.item_value(item_idx),
@@ -667,35 +1664,310 @@ pub fn generate_sysex() -> Box<SysExGeneratorMenuTrait> {
         }
     }
 
+    impl ParameterUnitMenu {
+        fn item_amount(&self, item_idx: usize) -> f32 {
+            (self.first_step + item_idx as i32) as f32 * self.step
+        }
+        fn item_value(&self, item_idx: usize) -> UnitValue {
+            self.up
+                .param
+                .value_for_unit(self.item_amount(item_idx), self.unit)
+                .unwrap()
+        }
+    }
+    impl Menu<Box<dyn SysExGenerator>> for ParameterUnitMenu {
+        fn items_count(&self) -> usize {
+            (self.last_step - self.first_step + 1) as usize
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            let precision = (-self.step.log10()).ceil().max(0.0) as usize;
+            let signed = self.first_step < 0 && self.last_step > 0;
+            write_unit_value(write_to, self.item_amount(item_idx), precision, signed)?;
+            write!(write_to, " {}", self.unit)?;
+            let data = &[self.item_value(item_idx).value];
+            write!(write_to, " — {}", format_bytes(data))?;
+            self.up.param.describe(data, write_to, false)
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            MenuItemResult::Command(Box::new(DT1Generator {
+                up: self.up.clone(),
+                value: self.item_value(item_idx).value,
+            }))
+        }
+    }
+
     impl SysExGenerator for DT1Generator {
         fn generate(&self, out: &mut Vec<u8>) {
             let mut address =
                 Vec::with_capacity(self.up.up.address_prefix.len() + self.up.address_suffix.len());
             address.extend_from_slice(self.up.up.address_prefix);
             address.extend_from_slice(self.up.address_suffix);
-            ParsedSysEx {
-                manufacturer_id: MF_ID_ROLAND,
-                content: MaybeParsed::Parsed(ParsedSysExBody::Roland(
-                    ParsedRolandSysExBody::TypeIV {
-                        device_id: self.up.up.up.model_info.default_device_id,
-                        model_id: self.up.up.up.model_info.model_id,
-                        model_name: None, // meaningless,
-                        command_id: CM_ID_DT1,
-                        command: MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
-                            address: &address,
-                            data: &[self.value],
-                            param_info: Some(self.up.param),
-                            // meaningless stuff
-                            valid_checksum: false,
-                            block_name_and_prefix_size: None,
-                            invalid_size: false,
-                        }),
-                    },
-                )),
-            }
-            .generate(out)
+            generate_dt1(
+                self.up.up.up.up.model_info,
+                self.up.up.up.device_id,
+                &address,
+                &[self.value],
+                out,
+            )
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    const PART_SETS: &[(&str, fn(u8) -> bool)] = &[
+        ("All parts", |_| true),
+        ("All parts but Part 10", |part| part != 10),
+        ("Parts 1–8", |part| part <= 8),
+        ("Parts 9–16", |part| part >= 9),
+        ("Odd parts", |part| part % 2 == 1),
+        ("Even parts", |part| part % 2 == 0),
+    ];
+    impl PartSetMenu {
+        fn item_parts(&self, item_idx: usize) -> PartBlocks {
+            let (_, in_set) = PART_SETS[item_idx];
+            self.parts
+                .iter()
+                .copied()
+                .filter(|&(part, _)| in_set(part))
+                .collect()
+        }
+    }
+    impl Menu<Box<dyn SysExGenerator>> for PartSetMenu {
+        fn items_count(&self) -> usize {
+            PART_SETS.len()
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            let parts = self.item_parts(item_idx);
+            write!(
+                write_to,
+                "{} ({} parts)",
+                PART_SETS[item_idx].0,
+                parts.len()
+            )
+        }
+        fn item_disabled(&self, item_idx: usize) -> bool {
+            self.item_parts(item_idx).is_empty()
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            MenuItemResult::Submenu(Box::new(PartsParameterAddressMenu {
+                up: self.clone(),
+                parts: self.item_parts(item_idx),
+            }))
+        }
+    }
+
+    impl Menu<Box<dyn SysExGenerator>> for PartsParameterAddressMenu {
+        fn items_count(&self) -> usize {
+            self.up.parameter_address_map.len()
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            let (address_suffix, ref param) = self.up.parameter_address_map[item_idx];
+            write!(
+                write_to,
+                "{} — {}",
+                format_bytes(address_suffix),
+                param.name
+            )
+        }
+        fn item_disabled(&self, item_idx: usize) -> bool {
+            let (_, ref param) = self.up.parameter_address_map[item_idx];
+            param.size != 1 || matches!(param.description, ParameterValueDescription::Other)
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            let (address_suffix, ref param) = self.up.parameter_address_map[item_idx];
+            assert_eq!(param.size, 1);
+            MenuItemResult::Submenu(Box::new(PartsParameterValueMenu {
+                up: self.clone(),
+                address_suffix,
+                param,
+            }))
+        }
+    }
+
+    // The value chosen here is the first part's, and the next menu picks how
+    // the other parts' values follow on from it.
+    impl Menu<Box<dyn SysExGenerator>> for PartsParameterValueMenu {
+        fn items_count(&self) -> usize {
+            self.param.valid_values().count()
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            let data = &[self.param.valid_values().nth(item_idx).unwrap()];
+            write!(write_to, "{}", format_bytes(data))?;
+            self.param.describe(data, write_to, true)
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            MenuItemResult::Submenu(Box::new(PartSpreadMenu {
+                up: self.clone(),
+                first: self.param.valid_values().nth(item_idx).unwrap(),
+            }))
+        }
+    }
+
+    impl PartSpreadMenu {
+        fn spreads(&self) -> Vec<PartSpread> {
+            let steps = spread_values(self.up.param);
+            let mut spreads = vec![PartSpread::Same];
+            if !steps.contains(&self.first) {
+                return spreads;
+            }
+            for to in [*steps.last().unwrap(), steps[0]] {
+                if to != self.first {
+                    spreads.push(PartSpread::Evenly { to });
+                }
+            }
+            for step in [1, 2, 4, 8, -1, -2, -4, -8] {
+                spreads.push(PartSpread::Offset(step));
+            }
+            spreads
+        }
+    }
+    impl Menu<Box<dyn SysExGenerator>> for PartSpreadMenu {
+        fn items_count(&self) -> usize {
+            self.spreads().len()
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            let spread = self.spreads()[item_idx];
+            let values = spread.values(self.up.param, self.first, self.up.up.parts.len());
+            write!(write_to, "{} — {}", spread, format_bytes(&values))?;
+            Ok(())
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            MenuItemResult::Command(Box::new(PartsDT1Generator {
+                up: self.clone(),
+                spread: self.spreads()[item_idx],
+            }))
+        }
+    }
+
+    impl SysExGenerator for PartsDT1Generator {
+        /// A SysEx can only set the parameter on one part, so this is the
+        /// first part's. See [SysExGenerator::generate_all] for all of them.
+        fn generate(&self, out: &mut Vec<u8>) {
+            out.extend_from_slice(&self.generate_all()[0]);
+        }
+        fn generate_all(&self) -> Vec<Vec<u8>> {
+            let value_menu = &self.up.up;
+            let address_block_menu = &value_menu.up.up.up;
+            let mut sysexes = Vec::new();
+            generate_dt1_for_parts(
+                address_block_menu.up.model_info,
+                address_block_menu.device_id,
+                &value_menu.up.parts,
+                value_menu.address_suffix,
+                value_menu.param,
+                self.up.first,
+                self.spread,
+                &mut sysexes,
+            );
+            sysexes
         }
     }
 
     Box::new(ModelsMenu)
 }
+
+#[cfg(test)]
+#[test]
+fn test_enum_with_holes() {
+    let param = Parameter {
+        size: 1,
+        name: "TEST",
+        range: 0x00..=0x04,
+        default: None,
+        description: ParameterValueDescription::Enum(&[
+            (&[0x00], "Zero"),
+            (&[0x02], "Two"),
+            (&[0x04], "Four"),
+        ]),
+    };
+    assert!(param.value_is_valid(0x02));
+    assert!(!param.value_is_valid(0x03));
+    assert!(!param.value_is_valid(0x05));
+    assert_eq!(
+        param.valid_values().collect::<Vec<_>>(),
+        &[0x00, 0x02, 0x04]
+    );
+
+    let mut description = String::new();
+    param.describe(&[0x02], &mut description, true).unwrap();
+    assert_eq!(description, " = 2 — Two");
+}
+
+#[cfg(test)]
+#[test]
+fn test_block_write() {
+    let gs = MODELS
+        .iter()
+        .find(|model| model.name == "Roland GS")
+        .unwrap();
+    let describe = |address: &[u8], data: &[u8]| {
+        super::parse_sysex(&dt1(gs, 0x10, address, data))
+            .unwrap()
+            .to_string()
+    };
+
+    // Part 1 CHORUS SEND LEVEL = 16 and REVERB SEND LEVEL = 100.
+    assert!(describe(&[0x40, 0x11, 0x21], &[0x10, 0x64]).ends_with(
+        "Patch parameters, Part 1 § CHORUS SEND LEVEL => 10h = 16; \
+         REVERB SEND LEVEL => 64h = 100"
+    ));
+    // Part 1 TONE NUMBER and Rx. CHANNEL, the first of which is two bytes.
+    assert!(describe(&[0x40, 0x11, 0x00], &[0x00, 0x04, 0x00])
+        .contains("TONE NUMBER => 00h 04h; Rx. CHANNEL => 00h = 0 [Channel 1]"));
+    // Ending partway through TONE NUMBER.
+    assert!(describe(&[0x40, 0x11, 0x01], &[0x04, 0x00])
+        .contains("Patch parameters, Part 1 § (unknown) 01h => 04h 00h"));
+    assert!(describe(&[0x40, 0x11, 0x00], &[0x00]).contains("TONE NUMBER (WRONG SIZE)"));
+    // MODE SET and a byte for an address that has no parameter.
+    assert!(describe(&[0x40, 0x00, 0x7F], &[0x00, 0x00]).contains("MODE SET (WRONG SIZE)"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_macro_with_tweaks() {
+    let gs = MODELS
+        .iter()
+        .find(|model| model.name == "Roland GS")
+        .unwrap();
+
+    let mut sysexes = Vec::new();
+    generate_macro_with_tweaks(
+        gs,
+        0x10,
+        &[0x40, 0x01, 0x30], // REVERB MACRO
+        0x04,                // Hall 2
+        &[
+            (&[0x40, 0x01, 0x34], 100), // REVERB TIME
+            (&[0x40, 0x01, 0x33], 64),  // REVERB LEVEL, same as the macro's
+        ],
+        &mut sysexes,
+    )
+    .unwrap();
+    assert_eq!(
+        sysexes,
+        &[
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x01, 0x30, 0x04, 0x0B, 0xF7],
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x01, 0x34, 0x64, 0x27, 0xF7],
+        ]
+    );
+
+    let description = super::parse_sysex(&sysexes[0]).unwrap().to_string();
+    assert!(description.contains(
+        "(also sets REVERB CHARACTER = 4, REVERB PRE-LPF = 0, REVERB LEVEL = 64, \
+         REVERB TIME = 64, REVERB DELAY FEEDBACK = 0)"
+    ));
+
+    // Not a macro parameter.
+    assert!(
+        generate_macro_with_tweaks(gs, 0x10, &[0x40, 0x01, 0x33], 0, &[], &mut sysexes).is_err()
+    );
+    // The macro parameter can't be its own tweak.
+    assert!(generate_macro_with_tweaks(
+        gs,
+        0x10,
+        &[0x40, 0x01, 0x38],
+        0x00,
+        &[(&[0x40, 0x01, 0x38], 0x01)],
+        &mut sysexes
+    )
+    .is_err());
+}
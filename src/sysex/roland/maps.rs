@@ -10,27 +10,53 @@
 //! TODO: These should probably be stored as data files?
 
 use super::{
-    AddressBlockMap, ModelInfo, Parameter, ParameterAddressMap, ParameterValueDescription,
+    AddressBlockMap, MacroMap, MacroParameter, ModelInfo, Parameter, ParameterAddressMap,
+    ParameterValueDescription,
 };
+use crate::midi::OctaveConvention;
+
+// The default must be the right size and within the range.
+const fn check_default(
+    size: u8,
+    range: &std::ops::RangeInclusive<u8>,
+    default: Option<&'static [u8]>,
+) {
+    if let Some(default) = default {
+        if default.len() != size as usize {
+            panic!();
+        }
+        let &[default] = default else {
+            panic!(); // only single-byte for now
+        };
+        if default < *range.start() || default > *range.end() {
+            panic!();
+        }
+    }
+}
 
 const fn param_unsigned(
     lsb: &'static [u8],
     size: u8,
     name: &'static str,
     range: std::ops::RangeInclusive<u8>,
+    default: Option<&'static [u8]>,
 ) -> (&'static [u8], Parameter) {
     if size != 0x01 {
         panic!(); // only single-byte for now
     }
+    check_default(size, &range, default);
+
     (
         lsb,
         Parameter {
             size,
             name,
             range,
+            default,
             description: ParameterValueDescription::Numeric {
                 zero_offset: 0,
                 unit_in_range: None,
+                special_values: &[],
             },
         },
     )
@@ -41,23 +67,54 @@ const fn param_signed(
     name: &'static str,
     range: std::ops::RangeInclusive<u8>,
     zero_offset: u8,
+    default: Option<&'static [u8]>,
+) -> (&'static [u8], Parameter) {
+    param_signed_special(lsb, size, name, range, zero_offset, &[], default)
+}
+// Like param_signed, but some values have a special meaning rather than a
+// numeric one, e.g. "Random" for a panpot.
+const fn param_signed_special(
+    lsb: &'static [u8],
+    size: u8,
+    name: &'static str,
+    range: std::ops::RangeInclusive<u8>,
+    zero_offset: u8,
+    special_values: &'static [(&'static [u8], &'static str)],
+    default: Option<&'static [u8]>,
 ) -> (&'static [u8], Parameter) {
     if size != 0x01 {
         panic!(); // only single-byte for now
     }
+
+    let mut i = 0;
+    while i < special_values.len() {
+        let &[value] = special_values[i].0 else {
+            panic!();
+        };
+        if value < *range.start() || value > *range.end() {
+            panic!();
+        }
+        i += 1;
+    }
+
+    check_default(size, &range, default);
+
     (
         lsb,
         Parameter {
             size,
             name,
             range,
+            default,
             description: ParameterValueDescription::Numeric {
                 zero_offset,
                 unit_in_range: None,
+                special_values,
             },
         },
     )
 }
+#[allow(clippy::too_many_arguments)]
 const fn param_range(
     lsb: &'static [u8],
     size: u8,
@@ -66,29 +123,74 @@ const fn param_range(
     zero_midi: u8,
     range_unit: std::ops::RangeInclusive<f32>,
     unit: &'static str,
+    default: Option<&'static [u8]>,
 ) -> (&'static [u8], Parameter) {
     if size != 0x01 {
         panic!(); // only single-byte for now
     }
+    check_default(size, &range_midi, default);
+
     (
         lsb,
         Parameter {
             size,
             name,
             range: range_midi,
+            default,
             description: ParameterValueDescription::Numeric {
                 zero_offset: zero_midi,
                 unit_in_range: Some((range_unit, unit)),
+                special_values: &[],
             },
         },
     )
 }
+const fn param_note(
+    lsb: &'static [u8],
+    name: &'static str,
+    range: std::ops::RangeInclusive<u8>,
+    convention: OctaveConvention,
+    default: Option<&'static [u8]>,
+) -> (&'static [u8], Parameter) {
+    check_default(0x01, &range, default);
+
+    (
+        lsb,
+        Parameter {
+            size: 0x01,
+            name,
+            range,
+            default,
+            description: ParameterValueDescription::Note(convention),
+        },
+    )
+}
+const fn param_controller(
+    lsb: &'static [u8],
+    name: &'static str,
+    range: std::ops::RangeInclusive<u8>,
+    default: Option<&'static [u8]>,
+) -> (&'static [u8], Parameter) {
+    check_default(0x01, &range, default);
+
+    (
+        lsb,
+        Parameter {
+            size: 0x01,
+            name,
+            range,
+            default,
+            description: ParameterValueDescription::Controller,
+        },
+    )
+}
 const fn param_enum(
     lsb: &'static [u8],
     size: u8,
     name: &'static str,
     range: std::ops::RangeInclusive<u8>,
     values: &'static [(&'static [u8], &'static str)],
+    default: Option<&'static [u8]>,
 ) -> (&'static [u8], Parameter) {
     if size != 0x01 {
         panic!(); // only single-byte for now
@@ -119,33 +221,55 @@ const fn param_enum(
 
     // We could just generate the range from the values, but the references
     // specify both the range and the values, so this is a useful check that the
-    // data is correct. Also, maybe we'll need to support enums that only have
-    // partial coverage of the range, at some point.
+    // data is correct. The values don't have to cover the whole range though:
+    // any gaps are invalid values.
     match (value_min, value_max) {
         (Some(value_min), Some(value_max))
             if value_min == *range.start() && value_max == *range.end() => {}
         _ => panic!(),
     }
 
+    check_default(size, &range, default);
+    if let Some(&[default]) = default {
+        let mut i = 0;
+        while i < values.len() {
+            if let &[value] = values[i].0 {
+                if value == default {
+                    break;
+                }
+            }
+            i += 1;
+        }
+        if i == values.len() {
+            panic!(); // not one of the values
+        }
+    }
+
     (
         lsb,
         Parameter {
             size,
             name,
             range,
+            default,
             description: ParameterValueDescription::Enum(values),
         },
     )
 }
 // Only use this when it exactly matches the manual. Other single-byte two-value
 // enums should use param_enum.
-const fn param_bool(lsb: &'static [u8], name: &'static str) -> (&'static [u8], Parameter) {
+const fn param_bool(
+    lsb: &'static [u8],
+    name: &'static str,
+    default: Option<&'static [u8]>,
+) -> (&'static [u8], Parameter) {
     param_enum(
         lsb,
         0x01,
         name,
         0x00..=0x01,
         &[(&[0x00], "OFF"), (&[0x01], "ON")],
+        default,
     )
 }
 const fn param_other(
@@ -153,13 +277,22 @@ const fn param_other(
     size: u8,
     name: &'static str,
     range: std::ops::RangeInclusive<u8>,
+    default: Option<&'static [u8]>,
 ) -> (&'static [u8], Parameter) {
+    // The range isn't meaningful for these, so only the size is checked.
+    if let Some(default) = default {
+        if default.len() != size as usize {
+            panic!();
+        }
+    }
+
     (
         lsb,
         Parameter {
             size,
             name,
             range,
+            default,
             description: ParameterValueDescription::Other,
         },
     )
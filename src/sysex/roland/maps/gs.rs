@@ -13,17 +13,25 @@
 //! - Roland SC-7 Owner's Manual (not a GS device, only has a tiny subset).
 
 use super::{
-    param_bool, param_enum, param_other, param_range, param_signed, param_unsigned,
-    AddressBlockMap, ModelInfo, ParameterAddressMap,
+    param_bool, param_controller, param_enum, param_note, param_other, param_range, param_signed,
+    param_signed_special, param_unsigned, AddressBlockMap, MacroMap, MacroParameter, ModelInfo,
+    ParameterAddressMap,
 };
+use crate::midi::OctaveConvention;
+
+/// Roland manuals call note number 60 "C4".
+const ROLAND_OCTAVES: OctaveConvention = OctaveConvention::MiddleC4;
 
 /// Roland GS.
 pub const GS: ModelInfo = ModelInfo {
     model_id: &[0x42],
     name: "Roland GS",
     default_device_id: 0x10, // SC-55 and SC-7 respond to this, at least
+    device_id_configurable: true,
     address_size: 3,
     address_block_map: GS_ABM,
+    macro_map: GS_MACROS,
+    default_overrides: GS_DEFAULT_OVERRIDES,
 };
 
 const GS_ABM: AddressBlockMap = &[
@@ -147,8 +155,15 @@ const GS_ABM: AddressBlockMap = &[
 
 const GS_PAM_SYSTEM: ParameterAddressMap = &[
     // TODO: Proper type/range for MASTER TUNE (needs "nibblized data" support)
-    param_other(&[0x00], 0x04, "MASTER TUNE", 0x00..=0x0F),
-    param_unsigned(&[0x04], 0x01, "MASTER VOLUME", 0x00..=0x7F),
+    param_other(
+        &[0x00],
+        0x04,
+        "MASTER TUNE",
+        0x00..=0x0F,
+        Some(&[0x00, 0x04, 0x00, 0x00]),
+    ),
+    // The manuals don't say how the volume parameters map to decibels.
+    param_unsigned(&[0x04], 0x01, "MASTER VOLUME", 0x00..=0x7F, Some(&[0x7F])),
     param_range(
         &[0x05],
         0x01,
@@ -157,8 +172,16 @@ const GS_PAM_SYSTEM: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
+    ),
+    param_signed(
+        &[0x06],
+        0x01,
+        "MASTER PAN",
+        0x01..=0x7F,
+        0x40,
+        Some(&[0x40]),
     ),
-    param_signed(&[0x06], 0x01, "MASTER PAN", 0x01..=0x7F, 0x40),
     param_enum(
         &[0x7F],
         0x01,
@@ -171,12 +194,13 @@ const GS_PAM_SYSTEM: ParameterAddressMap = &[
         // it isn't in the original SC-55 manual.
         0x00..=0x00,
         &[(&[0x00], "GS Reset")],
+        None,
     ),
 ];
 
 const GS_PAM_PATCH_COMMON: ParameterAddressMap = &[
     // TODO: Proper type/range for PATCH NAME (needs ASCII data support)
-    param_other(&[0x00], 0x10, "PATCH NAME", 0x20..=0x7F),
+    param_other(&[0x00], 0x10, "PATCH NAME", 0x20..=0x7F, None),
     // TODO: Proper type/range for VOICE RESERVE (special)
     param_other(
         &[0x10],
@@ -187,6 +211,7 @@ const GS_PAM_PATCH_COMMON: ParameterAddressMap = &[
         // 24 for the SC-55, 28 for the SC-55mkII and SC-7. Presumably even
         // larger on later models. TODO: Special handling?
         0x00..=0x18,
+        None,
     ),
     param_enum(
         &[0x30],
@@ -203,13 +228,36 @@ const GS_PAM_PATCH_COMMON: ParameterAddressMap = &[
             (&[0x06], "Delay"),
             (&[0x07], "Panning Delay"),
         ],
+        Some(&[0x04]),
+    ),
+    param_unsigned(
+        &[0x31],
+        0x01,
+        "REVERB CHARACTER",
+        0x00..=0x07,
+        Some(&[0x04]),
+    ),
+    param_unsigned(&[0x32], 0x01, "REVERB PRE-LPF", 0x00..=0x07, Some(&[0x00])),
+    param_unsigned(&[0x33], 0x01, "REVERB LEVEL", 0x00..=0x7F, Some(&[0x40])),
+    // TODO: REVERB TIME, CHORUS DELAY and CHORUS RATE are non-linear, but
+    //       the SC-55 and SC-55mkII manuals give no figures for them, so they
+    //       can't be shown in a unit yet. The same goes for the TONE MODIFY
+    //       TVF and TVA envelope parameters.
+    param_unsigned(&[0x34], 0x01, "REVERB TIME", 0x00..=0x7F, Some(&[0x40])),
+    param_unsigned(
+        &[0x35],
+        0x01,
+        "REVERB DELAY FEEDBACK",
+        0x00..=0x7F,
+        Some(&[0x00]),
+    ),
+    param_unsigned(
+        &[0x36],
+        0x01,
+        "REVERB SEND LEVEL TO CHORUS",
+        0x00..=0x7F,
+        Some(&[0x00]),
     ),
-    param_unsigned(&[0x31], 0x01, "REVERB CHARACTER", 0x00..=0x07),
-    param_unsigned(&[0x32], 0x01, "REVERB PRE-LPF", 0x00..=0x07),
-    param_unsigned(&[0x33], 0x01, "REVERB LEVEL", 0x00..=0x7F),
-    param_unsigned(&[0x34], 0x01, "REVERB TIME", 0x00..=0x7F),
-    param_unsigned(&[0x35], 0x01, "REVERB DELAY FEEDBACK", 0x00..=0x7F),
-    param_unsigned(&[0x36], 0x01, "REVERB SEND LEVEL TO CHORUS", 0x00..=0x7F),
     // 37h is unoccupied!
     param_enum(
         &[0x38],
@@ -226,19 +274,103 @@ const GS_PAM_PATCH_COMMON: ParameterAddressMap = &[
             (&[0x06], "Short Delay"),
             (&[0x07], "Short Delay (FB)"),
         ],
+        Some(&[0x02]),
+    ),
+    param_unsigned(&[0x39], 0x01, "CHORUS PRE-LPF", 0x00..=0x07, Some(&[0x00])),
+    param_unsigned(&[0x3A], 0x01, "CHORUS LEVEL", 0x00..=0x7F, Some(&[0x40])),
+    param_unsigned(&[0x3B], 0x01, "CHORUS FEEDBACK", 0x00..=0x7F, Some(&[0x08])),
+    param_unsigned(&[0x3C], 0x01, "CHORUS DELAY", 0x00..=0x7F, Some(&[0x50])),
+    param_unsigned(&[0x3D], 0x01, "CHORUS RATE", 0x00..=0x7F, Some(&[0x03])),
+    param_unsigned(&[0x3E], 0x01, "CHORUS DEPTH", 0x00..=0x7F, Some(&[0x13])),
+    param_unsigned(
+        &[0x3F],
+        0x01,
+        "CHORUS SEND LEVEL TO REVERB",
+        0x00..=0x7F,
+        Some(&[0x00]),
     ),
-    param_unsigned(&[0x39], 0x01, "CHORUS PRE-LPF", 0x00..=0x07),
-    param_unsigned(&[0x3A], 0x01, "CHORUS LEVEL", 0x00..=0x7F),
-    param_unsigned(&[0x3B], 0x01, "CHORUS FEEDBACK", 0x00..=0x7F),
-    param_unsigned(&[0x3C], 0x01, "CHORUS DELAY", 0x00..=0x7F),
-    param_unsigned(&[0x3D], 0x01, "CHORUS RATE", 0x00..=0x7F),
-    param_unsigned(&[0x3E], 0x01, "CHORUS DEPTH", 0x00..=0x7F),
-    param_unsigned(&[0x3F], 0x01, "CHORUS SEND LEVEL TO REVERB", 0x00..=0x7F),
+];
+
+/// The values that REVERB MACRO and CHORUS MACRO set, from the tables in the
+/// Sound Canvas manuals. Note that REVERB SEND LEVEL TO CHORUS isn't set by
+/// the reverb macro.
+const GS_MACROS: MacroMap = &[
+    MacroParameter {
+        address: &[0x40, 0x01, 0x30],
+        implied_addresses: &[
+            &[0x40, 0x01, 0x31], // REVERB CHARACTER
+            &[0x40, 0x01, 0x32], // REVERB PRE-LPF
+            &[0x40, 0x01, 0x33], // REVERB LEVEL
+            &[0x40, 0x01, 0x34], // REVERB TIME
+            &[0x40, 0x01, 0x35], // REVERB DELAY FEEDBACK
+        ],
+        presets: &[
+            (0x00, &[0, 3, 64, 80, 0]),  // Room 1
+            (0x01, &[1, 4, 64, 56, 0]),  // Room 2
+            (0x02, &[2, 0, 64, 64, 0]),  // Room 3
+            (0x03, &[3, 4, 64, 72, 0]),  // Hall 1
+            (0x04, &[4, 0, 64, 64, 0]),  // Hall 2
+            (0x05, &[5, 0, 64, 88, 0]),  // Plate
+            (0x06, &[6, 0, 64, 32, 40]), // Delay
+            (0x07, &[7, 0, 64, 64, 32]), // Panning Delay
+        ],
+    },
+    MacroParameter {
+        address: &[0x40, 0x01, 0x38],
+        implied_addresses: &[
+            &[0x40, 0x01, 0x39], // CHORUS PRE-LPF
+            &[0x40, 0x01, 0x3A], // CHORUS LEVEL
+            &[0x40, 0x01, 0x3B], // CHORUS FEEDBACK
+            &[0x40, 0x01, 0x3C], // CHORUS DELAY
+            &[0x40, 0x01, 0x3D], // CHORUS RATE
+            &[0x40, 0x01, 0x3E], // CHORUS DEPTH
+            &[0x40, 0x01, 0x3F], // CHORUS SEND LEVEL TO REVERB
+        ],
+        presets: &[
+            (0x00, &[0, 64, 0, 112, 3, 5, 0]),    // Chorus 1
+            (0x01, &[0, 64, 5, 80, 9, 19, 0]),    // Chorus 2
+            (0x02, &[0, 64, 8, 80, 3, 19, 0]),    // Chorus 3
+            (0x03, &[0, 64, 16, 64, 9, 16, 0]),   // Chorus 4
+            (0x04, &[0, 64, 64, 127, 2, 24, 0]),  // Feedback Chorus
+            (0x05, &[0, 64, 112, 127, 1, 5, 0]),  // Flanger
+            (0x06, &[0, 64, 0, 127, 0, 127, 0]),  // Short Delay
+            (0x07, &[0, 64, 80, 127, 0, 127, 0]), // Short Delay (FB)
+        ],
+    },
+];
+
+/// Each part receives on the channel with its own number by default, and only
+/// Part 10 is a rhythm part.
+const GS_DEFAULT_OVERRIDES: &[(&[u8], &[u8])] = &[
+    (&[0x40, 0x10, 0x02], &[0x09]), // Part 10, Rx. CHANNEL
+    (&[0x40, 0x11, 0x02], &[0x00]), // Part 1, Rx. CHANNEL
+    (&[0x40, 0x12, 0x02], &[0x01]), // Part 2, Rx. CHANNEL
+    (&[0x40, 0x13, 0x02], &[0x02]), // Part 3, Rx. CHANNEL
+    (&[0x40, 0x14, 0x02], &[0x03]), // Part 4, Rx. CHANNEL
+    (&[0x40, 0x15, 0x02], &[0x04]), // Part 5, Rx. CHANNEL
+    (&[0x40, 0x16, 0x02], &[0x05]), // Part 6, Rx. CHANNEL
+    (&[0x40, 0x17, 0x02], &[0x06]), // Part 7, Rx. CHANNEL
+    (&[0x40, 0x18, 0x02], &[0x07]), // Part 8, Rx. CHANNEL
+    (&[0x40, 0x19, 0x02], &[0x08]), // Part 9, Rx. CHANNEL
+    (&[0x40, 0x1A, 0x02], &[0x0A]), // Part 11, Rx. CHANNEL
+    (&[0x40, 0x1B, 0x02], &[0x0B]), // Part 12, Rx. CHANNEL
+    (&[0x40, 0x1C, 0x02], &[0x0C]), // Part 13, Rx. CHANNEL
+    (&[0x40, 0x1D, 0x02], &[0x0D]), // Part 14, Rx. CHANNEL
+    (&[0x40, 0x1E, 0x02], &[0x0E]), // Part 15, Rx. CHANNEL
+    (&[0x40, 0x1F, 0x02], &[0x0F]), // Part 16, Rx. CHANNEL
+    (&[0x40, 0x10, 0x14], &[0x00]), // Part 10, ASSIGN MODE = SINGLE
+    (&[0x40, 0x10, 0x15], &[0x01]), // Part 10, USE FOR RHYTHM PART = MAP1
 ];
 
 const GS_PAM_PATCH: ParameterAddressMap = &[
     // TODO: Proper type/range for TONE NUMBER (special)
-    param_other(&[0x00], 0x02, "TONE NUMBER", 0x00..=0x7F),
+    param_other(
+        &[0x00],
+        0x02,
+        "TONE NUMBER",
+        0x00..=0x7F,
+        Some(&[0x00, 0x00]),
+    ),
     param_enum(
         &[0x02],
         0x01,
@@ -263,29 +395,31 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
             (&[0x0F], "Channel 16"),
             (&[0x10], "OFF"),
         ],
-    ),
-    param_bool(&[0x03], "Rx. PITCH BEND"),
-    param_bool(&[0x04], "Rx. CH PRESSURE (CAf)"),
-    param_bool(&[0x05], "Rx. PROGRAM CHANGE"),
-    param_bool(&[0x06], "Rx. CONTROL CHANGE"),
-    param_bool(&[0x07], "Rx. POLY PRESSURE (PAf)"),
-    param_bool(&[0x08], "Rx. NOTE MESSAGE"),
-    param_bool(&[0x09], "Rx. RPN"),
-    param_bool(&[0x0A], "Rx. NRPN"),
-    param_bool(&[0x0B], "Rx. MODULATION"),
-    param_bool(&[0x0C], "Rx. VOLUME"),
-    param_bool(&[0x0D], "Rx. PANPOT"),
-    param_bool(&[0x0E], "Rx. EXPRESSION"),
-    param_bool(&[0x0F], "Rx. HOLD1"),
-    param_bool(&[0x10], "Rx. PORTAMENTO"),
-    param_bool(&[0x11], "Rx. SOSTENUTO"),
-    param_bool(&[0x12], "Rx. SOFT"),
+        None,
+    ),
+    param_bool(&[0x03], "Rx. PITCH BEND", Some(&[0x01])),
+    param_bool(&[0x04], "Rx. CH PRESSURE (CAf)", Some(&[0x01])),
+    param_bool(&[0x05], "Rx. PROGRAM CHANGE", Some(&[0x01])),
+    param_bool(&[0x06], "Rx. CONTROL CHANGE", Some(&[0x01])),
+    param_bool(&[0x07], "Rx. POLY PRESSURE (PAf)", Some(&[0x01])),
+    param_bool(&[0x08], "Rx. NOTE MESSAGE", Some(&[0x01])),
+    param_bool(&[0x09], "Rx. RPN", Some(&[0x01])),
+    param_bool(&[0x0A], "Rx. NRPN", Some(&[0x01])),
+    param_bool(&[0x0B], "Rx. MODULATION", Some(&[0x01])),
+    param_bool(&[0x0C], "Rx. VOLUME", Some(&[0x01])),
+    param_bool(&[0x0D], "Rx. PANPOT", Some(&[0x01])),
+    param_bool(&[0x0E], "Rx. EXPRESSION", Some(&[0x01])),
+    param_bool(&[0x0F], "Rx. HOLD1", Some(&[0x01])),
+    param_bool(&[0x10], "Rx. PORTAMENTO", Some(&[0x01])),
+    param_bool(&[0x11], "Rx. SOSTENUTO", Some(&[0x01])),
+    param_bool(&[0x12], "Rx. SOFT", Some(&[0x01])),
     param_enum(
         &[0x13],
         0x01,
         "MONO/POLY MODE",
         0x00..=0x01,
         &[(&[0x00], "Mono"), (&[0x01], "Poly")],
+        Some(&[0x01]),
     ),
     param_enum(
         &[0x14],
@@ -297,6 +431,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
             (&[0x01], "LIMITED - MULTI"),
             (&[0x02], "FULL - MULTI"),
         ],
+        Some(&[0x01]),
     ),
     param_enum(
         &[0x15],
@@ -304,6 +439,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "USE FOR RHYTHM PART",
         0x00..=0x02,
         &[(&[0x00], "OFF"), (&[0x01], "MAP1"), (&[0x02], "MAP2")],
+        Some(&[0x00]),
     ),
     param_range(
         &[0x16],
@@ -313,32 +449,81 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
     ),
     // TODO: Proper type/range for PITCH OFFSET FINE (needs "nibblized data"
     //       support)
-    param_other(&[0x17], 0x02, "PITCH OFFSET FINE", 0x00..=0x0F),
-    param_unsigned(&[0x19], 0x01, "PART LEVEL", 0x00..=0x7F),
-    param_unsigned(&[0x1A], 0x01, "VELOCITY SENSE DEPTH", 0x00..=0x7F),
-    param_unsigned(&[0x1B], 0x01, "VELOCITY SENSE OFFSET", 0x00..=0x7F),
-    // TODO: how to accomodate special "Random" value (-64) for panning?
-    param_signed(&[0x1C], 0x01, "PART PANPOT", 0x00..=0x7F, 0x40),
-    // TODO: MIDI note number list for these two? (share with JS?)
-    param_unsigned(&[0x1D], 0x01, "KEY RANGE LOW", 0x00..=0x7F),
-    param_unsigned(&[0x1E], 0x01, "KEY RANGE HIGH", 0x00..=0x7F),
-    // TODO: MIDI controller number list for these two? (share with JS?)
-    param_unsigned(&[0x1F], 0x01, "CC1 CONTROLLER NUMBER", 0x00..=0x5F),
-    param_unsigned(&[0x20], 0x01, "CC2 CONTROLLER NUMBER", 0x00..=0x5F),
-    param_unsigned(&[0x21], 0x01, "CHORUS SEND LEVEL", 0x00..=0x7F),
-    param_unsigned(&[0x22], 0x01, "REVERB SEND LEVEL", 0x00..=0x7F),
+    param_other(
+        &[0x17],
+        0x02,
+        "PITCH OFFSET FINE",
+        0x00..=0x0F,
+        Some(&[0x08, 0x00]),
+    ),
+    param_unsigned(&[0x19], 0x01, "PART LEVEL", 0x00..=0x7F, Some(&[0x64])),
+    param_unsigned(
+        &[0x1A],
+        0x01,
+        "VELOCITY SENSE DEPTH",
+        0x00..=0x7F,
+        Some(&[0x40]),
+    ),
+    param_unsigned(
+        &[0x1B],
+        0x01,
+        "VELOCITY SENSE OFFSET",
+        0x00..=0x7F,
+        Some(&[0x40]),
+    ),
+    param_signed_special(
+        &[0x1C],
+        0x01,
+        "PART PANPOT",
+        0x00..=0x7F,
+        0x40,
+        &[(&[0x00], "Random")],
+        Some(&[0x40]),
+    ),
+    param_note(
+        &[0x1D],
+        "KEY RANGE LOW",
+        0x00..=0x7F,
+        ROLAND_OCTAVES,
+        Some(&[0x00]),
+    ),
+    param_note(
+        &[0x1E],
+        "KEY RANGE HIGH",
+        0x00..=0x7F,
+        ROLAND_OCTAVES,
+        Some(&[0x7F]),
+    ),
+    param_controller(&[0x1F], "CC1 CONTROLLER NUMBER", 0x00..=0x5F, Some(&[0x10])),
+    param_controller(&[0x20], "CC2 CONTROLLER NUMBER", 0x00..=0x5F, Some(&[0x11])),
+    param_unsigned(
+        &[0x21],
+        0x01,
+        "CHORUS SEND LEVEL",
+        0x00..=0x7F,
+        Some(&[0x00]),
+    ),
+    param_unsigned(
+        &[0x22],
+        0x01,
+        "REVERB SEND LEVEL",
+        0x00..=0x7F,
+        Some(&[0x28]),
+    ),
     // SC-55 manual does not mention this, but SC-55mkII does. Probably added
     // with the General MIDI support? (GM mode disables bank select receive.)
-    param_bool(&[0x23], "Rx. BANK SELECT [SC-55mkII+]"),
+    param_bool(&[0x23], "Rx. BANK SELECT [SC-55mkII+]", Some(&[0x01])),
     param_signed(
         &[0x30],
         0x01,
         "TONE MODIFY 1, Vibrato rate",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x31],
@@ -346,6 +531,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 2, Vibrato depth",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x32],
@@ -353,6 +539,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 3, TVF cutoff freq.",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x33],
@@ -360,6 +547,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 4, TVF resonance",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x34],
@@ -367,6 +555,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 5, TVF & TVA Env. attack",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x35],
@@ -374,6 +563,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 6, TVF & TVA Env. decay",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x36],
@@ -381,6 +571,7 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 7, TVF & TVA Env. release",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     param_signed(
         &[0x37],
@@ -388,9 +579,16 @@ const GS_PAM_PATCH: ParameterAddressMap = &[
         "TONE MODIFY 8, Vibrato delay",
         0x0E..=0x72,
         0x40,
+        Some(&[0x40]),
     ),
     // TODO: Proper type/range for SCALE TUNING (special)
-    param_other(&[0x40], 0x0C, "SCALE TUNING", 0x00..=0x0F),
+    param_other(
+        &[0x40],
+        0x0C,
+        "SCALE TUNING",
+        0x00..=0x0F,
+        Some(&[0x40; 12]),
+    ),
 ];
 
 const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
@@ -404,6 +602,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x01],
@@ -413,6 +612,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x02],
@@ -422,6 +622,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x03],
@@ -431,6 +632,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x04],
@@ -440,6 +642,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x0A]),
     ),
     param_range(
         &[0x05],
@@ -449,6 +652,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x06],
@@ -458,6 +662,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x07],
@@ -467,6 +672,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x08],
@@ -476,6 +682,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x09],
@@ -485,6 +692,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x0A],
@@ -494,6 +702,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x10],
@@ -503,6 +712,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x42]),
     ),
     param_range(
         &[0x11],
@@ -512,6 +722,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x12],
@@ -521,6 +732,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x13],
@@ -530,6 +742,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x14],
@@ -539,6 +752,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x15],
@@ -548,6 +762,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x16],
@@ -557,6 +772,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x17],
@@ -566,6 +782,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x18],
@@ -575,6 +792,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x19],
@@ -584,6 +802,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x1A],
@@ -593,6 +812,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x20],
@@ -602,6 +822,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x21],
@@ -611,6 +832,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x22],
@@ -620,6 +842,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x23],
@@ -629,6 +852,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x24],
@@ -638,6 +862,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x25],
@@ -647,6 +872,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x26],
@@ -656,6 +882,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x27],
@@ -665,6 +892,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x28],
@@ -674,6 +902,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x29],
@@ -683,6 +912,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x2A],
@@ -692,6 +922,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x30],
@@ -701,6 +932,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x31],
@@ -710,6 +942,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x32],
@@ -719,6 +952,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x33],
@@ -728,6 +962,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x34],
@@ -737,6 +972,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x35],
@@ -746,6 +982,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x36],
@@ -755,6 +992,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x37],
@@ -764,6 +1002,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x38],
@@ -773,6 +1012,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x39],
@@ -782,6 +1022,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x3A],
@@ -791,6 +1032,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x40],
@@ -800,6 +1042,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x41],
@@ -809,6 +1052,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x42],
@@ -818,6 +1062,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x43],
@@ -827,6 +1072,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x44],
@@ -836,6 +1082,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x45],
@@ -845,6 +1092,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x46],
@@ -854,6 +1102,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x47],
@@ -863,6 +1112,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x48],
@@ -872,6 +1122,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x49],
@@ -881,6 +1132,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x4A],
@@ -890,6 +1142,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x50],
@@ -899,6 +1152,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -24.0..=24.0,
         "semitones",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x51],
@@ -908,6 +1162,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x52],
@@ -917,6 +1172,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x53],
@@ -926,6 +1182,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x54],
@@ -935,6 +1192,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x55],
@@ -944,6 +1202,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x56],
@@ -953,6 +1212,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x57],
@@ -962,6 +1222,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        Some(&[0x40]),
     ),
     param_range(
         &[0x58],
@@ -971,6 +1232,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x59],
@@ -980,6 +1242,7 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=2400.0,
         "cents",
+        Some(&[0x00]),
     ),
     param_range(
         &[0x5A],
@@ -989,5 +1252,6 @@ const GS_PAM_PATCH_CONTROLLERS: ParameterAddressMap = &[
         0x00,
         0.0..=100.0,
         "%",
+        Some(&[0x00]),
     ),
 ];
@@ -18,6 +18,9 @@ pub const SC_55: ModelInfo = ModelInfo {
     model_id: &[0x45],
     name: "Roland SC-55/SC-155",
     default_device_id: 0x10,
+    device_id_configurable: true,
     address_size: 3,
     address_block_map: &[], // TODO
+    macro_map: &[],
+    default_overrides: &[],
 };
@@ -19,8 +19,11 @@ pub const SC_7: ModelInfo = ModelInfo {
     model_id: &[0x56],
     name: "Roland SC-7",
     default_device_id: 0x10, // non-configurable
+    device_id_configurable: false,
     address_size: 3,
     address_block_map: SC_7_ABM,
+    macro_map: &[],
+    default_overrides: &[],
 };
 
 const SC_7_ABM: AddressBlockMap = &[
@@ -63,16 +66,17 @@ const SC_7_PAM_SYSTEM: ParameterAddressMap = &[
             (&[0x06], "Delay"),
             (&[0x07], "Panning Delay"),
         ],
+        None,
     ),
-    param_unsigned(&[0x01], 0x01, "REVERB LEVEL", 0x00..=0x7F),
-    param_unsigned(&[0x02], 0x01, "REVERB (DELAY) TIME", 0x00..=0x7F),
-    param_unsigned(&[0x03], 0x01, "DELAY TIME", 0x00..=0x7F),
-    param_unsigned(&[0x04], 0x01, "DELAY FEEDBACK", 0x00..=0x7F),
-    param_unsigned(&[0x05], 0x01, "CHORUS LEVEL", 0x00..=0x7F),
-    param_unsigned(&[0x06], 0x01, "CHORUS FEEDBACK", 0x00..=0x7F),
-    param_unsigned(&[0x07], 0x01, "CHORUS DELAY", 0x00..=0x7F),
-    param_unsigned(&[0x08], 0x01, "CHORUS RATE", 0x00..=0x7F),
-    param_unsigned(&[0x09], 0x01, "CHORUS DEPTH", 0x00..=0x7F),
+    param_unsigned(&[0x01], 0x01, "REVERB LEVEL", 0x00..=0x7F, None),
+    param_unsigned(&[0x02], 0x01, "REVERB (DELAY) TIME", 0x00..=0x7F, None),
+    param_unsigned(&[0x03], 0x01, "DELAY TIME", 0x00..=0x7F, None),
+    param_unsigned(&[0x04], 0x01, "DELAY FEEDBACK", 0x00..=0x7F, None),
+    param_unsigned(&[0x05], 0x01, "CHORUS LEVEL", 0x00..=0x7F, None),
+    param_unsigned(&[0x06], 0x01, "CHORUS FEEDBACK", 0x00..=0x7F, None),
+    param_unsigned(&[0x07], 0x01, "CHORUS DELAY", 0x00..=0x7F, None),
+    param_unsigned(&[0x08], 0x01, "CHORUS RATE", 0x00..=0x7F, None),
+    param_unsigned(&[0x09], 0x01, "CHORUS DEPTH", 0x00..=0x7F, None),
 ];
 
 const SC_7_PAM_PATCH: ParameterAddressMap = &[
@@ -100,8 +104,9 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
             (&[0x0F], "Channel 16"),
             (&[0x10], "OFF"),
         ],
+        None,
     ),
-    param_bool(&[0x01], "RX. NRPN"),
+    param_bool(&[0x01], "RX. NRPN", None),
     param_range(
         &[0x02],
         0x01,
@@ -110,6 +115,7 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        None,
     ),
     param_range(
         &[0x03],
@@ -119,6 +125,7 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "cents",
+        None,
     ),
     // Unit not specified in SC-7 manual, but the SC-55 has what seems to be the
     // same control (same name, same range, same function) and it says cents.
@@ -130,6 +137,7 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
         0x40,
         -9600.0..=9600.0,
         "cents",
+        None,
     ),
     param_range(
         &[0x05],
@@ -139,6 +147,7 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
         0x40,
         -100.0..=100.0,
         "%",
+        None,
     ),
     param_range(
         &[0x06],
@@ -148,6 +157,7 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
         0x40,
         -10.0..=10.0,
         "Hz",
+        None,
     ),
     param_range(
         &[0x07],
@@ -157,5 +167,6 @@ const SC_7_PAM_PATCH: ParameterAddressMap = &[
         0x00,
         0.0..=600.0,
         "Hz",
+        None,
     ),
 ];
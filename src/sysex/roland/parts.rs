@@ -0,0 +1,212 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Setting a parameter on several parts at once, see [part_blocks] and
+//! [PartSpread].
+
+use super::{generate_dt1, DeviceId, ModelInfo, Parameter, ParameterValueDescription};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Split a block name like "Patch parameters, Part 3 (controllers)" into the
+/// text around the part number and the part number.
+fn split_part_block_name(name: &str) -> Option<(&str, u8, &str)> {
+    let (head, rest) = name.split_once(", Part ")?;
+    let digits_len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let part = rest[..digits_len].parse().ok()?;
+    Some((head, part, &rest[digits_len..]))
+}
+
+/// The part numbers and address prefixes of a per-part address block for each
+/// part, in part order. See [part_blocks].
+pub type PartBlocks = Vec<(u8, &'static [u8])>;
+
+/// If `block_name` is the name of a per-part address block of `model_info`,
+/// e.g. "Patch parameters, Part 3 (controllers)", find the same block for
+/// every part.
+pub fn part_blocks(model_info: &ModelInfo, block_name: &str) -> Option<PartBlocks> {
+    let (head, _, tail) = split_part_block_name(block_name)?;
+    let mut blocks: Vec<_> = model_info
+        .address_block_map
+        .iter()
+        .filter_map(|&(prefix, name, _)| match split_part_block_name(name) {
+            Some((head2, part, tail2)) if (head2, tail2) == (head, tail) => Some((part, prefix)),
+            _ => None,
+        })
+        .collect();
+    blocks.sort_by_key(|&(part, _)| part);
+    Some(blocks)
+}
+
+/// The values a [PartSpread] steps through for `param`: the valid values (see
+/// [Parameter::valid_values]), except for the special values of a numeric
+/// parameter, e.g. Random for PART PANPOT, which aren't part of its range.
+pub fn spread_values(param: &Parameter) -> Vec<u8> {
+    param
+        .valid_values()
+        .filter(|&value| {
+            !matches!(param.description, ParameterValueDescription::Numeric { .. })
+                || param.value_name(&[value]).is_none()
+        })
+        .collect()
+}
+
+/// How the value of a parameter changes from one part to the next, when
+/// setting it on several parts at once. Steps are counted in the values given
+/// by [spread_values], so this works for enumerations too.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PartSpread {
+    /// Every part gets the same value.
+    Same,
+    /// The values are spread out evenly from the first part's value to this
+    /// value for the last part, e.g. to fan out the PART PANPOT.
+    Evenly { to: u8 },
+    /// Each part's value is this many steps on from the previous part's,
+    /// stopping at the ends of the range.
+    Offset(i8),
+}
+impl PartSpread {
+    /// The values for `count` parts, starting from `first`, which must be a
+    /// valid value for `param`. If it's a special value (see [spread_values]),
+    /// there's nothing to step from, so every part gets it.
+    pub fn values(self, param: &Parameter, first: u8, count: usize) -> Vec<u8> {
+        let valid = spread_values(param);
+        let Some(first_idx) = valid.iter().position(|&value| value == first) else {
+            return vec![first; count];
+        };
+        let first_idx = first_idx as f64;
+        let last_idx = (valid.len() - 1) as f64;
+        (0..count)
+            .map(|part_idx| {
+                let idx = match self {
+                    PartSpread::Same => first_idx,
+                    PartSpread::Evenly { to } => {
+                        let to_idx = valid.iter().position(|&value| value == to).unwrap() as f64;
+                        let progress = if count > 1 {
+                            part_idx as f64 / (count - 1) as f64
+                        } else {
+                            0.0
+                        };
+                        (first_idx + (to_idx - first_idx) * progress).round()
+                    }
+                    PartSpread::Offset(step) => {
+                        (first_idx + f64::from(step) * part_idx as f64).clamp(0.0, last_idx)
+                    }
+                };
+                valid[idx as usize]
+            })
+            .collect()
+    }
+}
+impl Display for PartSpread {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            PartSpread::Same => write!(f, "Same value on every part"),
+            PartSpread::Evenly { to } => write!(f, "Spread evenly to {:02X}h", to),
+            PartSpread::Offset(step) if step < 0 => write!(f, "−{} per part", -step),
+            PartSpread::Offset(step) => write!(f, "+{} per part", step),
+        }
+    }
+}
+
+/// Write a DT1 SysEx to `out` for each of `parts` (see [part_blocks]), setting
+/// the single-byte parameter `param` at `address_suffix` in each part's block,
+/// with the values given by `spread` starting from `first`.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_dt1_for_parts(
+    model_info: &ModelInfo,
+    device_id: DeviceId,
+    parts: &[(u8, &[u8])],
+    address_suffix: &[u8],
+    param: &Parameter,
+    first: u8,
+    spread: PartSpread,
+    out: &mut Vec<Vec<u8>>,
+) {
+    let values = spread.values(param, first, parts.len());
+    for (&(_, prefix), value) in parts.iter().zip(values) {
+        let address = [prefix, address_suffix].concat();
+        let mut sysex = Vec::new();
+        generate_dt1(model_info, device_id, &address, &[value], &mut sysex);
+        out.push(sysex);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parts() {
+    use super::{look_up_parameter, MODELS};
+
+    let gs = MODELS
+        .iter()
+        .find(|model| model.name == "Roland GS")
+        .unwrap();
+
+    let parts = part_blocks(gs, "Patch parameters, Part 3 (controllers)").unwrap();
+    assert_eq!(parts.len(), 16);
+    assert_eq!(parts[0], (1, &[0x40, 0x21][..]));
+    assert_eq!(parts[9], (10, &[0x40, 0x20][..]));
+    assert!(part_blocks(gs, "Patch parameters, Patch common").is_none());
+
+    // Fanning out the PART PANPOT from L63 to R63 over 16 parts.
+    let (_, Some(panpot)) = look_up_parameter(gs, &[0x40, 0x11, 0x1C]) else {
+        unreachable!();
+    };
+    let parts = part_blocks(gs, "Patch parameters, Part 1").unwrap();
+    let mut sysexes = Vec::new();
+    let spread = PartSpread::Evenly { to: 127 };
+    generate_dt1_for_parts(gs, 0x10, &parts, &[0x1C], panpot, 1, spread, &mut sysexes);
+    assert_eq!(sysexes.len(), 16);
+    assert_eq!(
+        sysexes[0],
+        &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x1C, 0x01, 0x12, 0xF7]
+    );
+    let values: Vec<_> = sysexes.iter().map(|sysex| sysex[8]).collect();
+    assert_eq!(values[..3], [1, 9, 18]);
+    assert_eq!(values[15], 127);
+
+    // Offsets stop at the end of the range, before the Random value.
+    assert_eq!(
+        PartSpread::Offset(-30).values(panpot, 64, 4),
+        &[64, 34, 4, 1]
+    );
+    assert_eq!(PartSpread::Offset(1).values(panpot, 0, 2), &[0, 0]);
+    assert_eq!(PartSpread::Same.values(panpot, 64, 2), &[64, 64]);
+
+    // The same through the menus.
+    use crate::ui::MenuItemResult;
+    let mut menu = super::generate_sysex();
+    for label in [
+        "42h — Roland GS",
+        "10h",
+        "Several parts — Patch parameters, Parts 1–16",
+        "All parts",
+        "1Ch — PART PANPOT",
+        "01h",
+        "Spread evenly to 7Fh",
+    ] {
+        let item_idx = (0..menu.items_count())
+            .find(|&item_idx| {
+                let mut item_label = String::new();
+                menu.item_label(item_idx, &mut item_label).unwrap();
+                item_label.starts_with(label)
+            })
+            .unwrap();
+        menu = match menu.item_descend(item_idx) {
+            MenuItemResult::Submenu(submenu) => submenu,
+            MenuItemResult::Command(generator) => {
+                assert_eq!(generator.generate_all(), sysexes);
+                let mut bytes = Vec::new();
+                generator.generate(&mut bytes);
+                assert_eq!(bytes, sysexes[0]);
+                return;
+            }
+        };
+    }
+    unreachable!();
+}
@@ -0,0 +1,939 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! A small textual language for writing down SysExes, so that a synth setup
+//! can live in a plain text file. Each non-empty line is one parameter
+//! assignment, for example:
+//!
+//! ```text
+//! # Comments start with a hash sign.
+//! gs system "MASTER VOLUME" = 100
+//! gs common "REVERB MACRO" = "Hall 2"
+//! gs part 3 "REVERB SEND LEVEL" = 40
+//! gs part 3 controllers "MOD LFO1 PITCH DEPTH" = 0Ah
+//! sc-7 system "CHORUS RATE" = 0x20
+//! ```
+//!
+//! The parts of a line are:
+//!
+//! - The model, e.g. `gs` or `sc-7`. This is the model name from [MODELS]
+//!   without the "Roland " prefix, ignoring case.
+//! - The address block: `system`, `common`, `part <n>` or
+//!   `part <n> controllers`, or the full block name in double quotes.
+//! - The parameter name in double quotes, as in the parameter map.
+//! - `=` followed by the value. A decimal number is the value as displayed by
+//!   SoundPalette (so it may be negative), a hexadecimal number (`20h` or
+//!   `0x20`) is the raw data byte, and a string in double quotes is the name
+//!   of an enumerated value. A note name (e.g. `C4` or `F#3`) can be used for
+//!   a note number. For parameters whose values map to a unit, a
+//!   number followed by a unit (e.g. `-1200 cents` or `+3 semitones`) picks the
+//!   nearest value, see [Parameter::value_for_unit].
+//!
+//! Names are resolved through [MODELS], the [super::roland::AddressBlockMap]
+//! and the [super::roland::ParameterAddressMap], and each line becomes one DT1
+//! SysEx. The lines are sent in order, so a parameter set by a macro (e.g.
+//! REVERB LEVEL, which REVERB MACRO sets) has to be set after the macro, and
+//! there is a warning if it isn't.
+//!
+//! There are also some less friendly forms, which can represent any SysEx
+//! exactly. These are used by [describe_canonically], so that SysExes can be
+//! turned into text, edited or compared, and turned back into the same bytes:
+//!
+//! ```text
+//! # Device ID other than the model's default.
+//! gs device 11h system "MASTER VOLUME" = 127
+//! # Address and data given as raw bytes, e.g. for an unknown parameter.
+//! gs address 40h 00h 7Eh = 01h 02h
+//! # A specific (wrong) checksum rather than the correct one.
+//! gs system "MASTER VOLUME" = 127 checksum 00h
+//! # Any other SysEx, byte-for-byte, from F0h to F7h.
+//! raw F0h 7Eh 7Fh 09h 01h F7h
+//! ```
+//!
+//! The same kind of lines, without values, can also pick parameters to set to
+//! random values, see [compile_randomizer].
+
+use super::roland::{
+    generate_dt1, look_up_macro, look_up_parameter, next_address, DeviceId, ModelInfo, Parameter,
+    ParameterAddressMap, ParameterValueDescription, ParsedRolandSysExBody,
+    ParsedRolandSysExCommand, UnitValue, CM_ID_DT1, MF_ID_ROLAND, MODELS,
+};
+use super::{parse_sysex, MaybeParsed, ParseFailure, ParsedSysEx, ParsedSysExBody};
+use crate::midi::{format_bytes, parse_note_name};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+mod random;
+pub use random::compile_randomizer;
+
+/// An error found on a particular line (counting from 1) of the text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TextError {
+    pub line: usize,
+    pub message: String,
+}
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Quoted(&'a str),
+    Equals,
+}
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Quoted(string) => write!(f, "\"{}\"", string),
+            Token::Equals => write!(f, "="),
+        }
+    }
+}
+
+/// Split a line into tokens, stopping at a comment.
+fn tokenize(line: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '#' {
+            break;
+        } else if c == '=' {
+            tokens.push(Token::Equals);
+            rest = &rest[1..];
+        } else if c == '"' {
+            let Some((quoted, after)) = rest[1..].split_once('"') else {
+                return Err("Missing closing double quote".into());
+            };
+            tokens.push(Token::Quoted(quoted));
+            rest = after;
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '"' || c == '#')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Word(&rest[..end]));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Short names for a model that can be used in the text, e.g. `"GS"` for
+/// "Roland GS" and both `"SC-55"` and `"SC-155"` for "Roland SC-55/SC-155".
+/// The first one is the preferred one.
+pub fn model_short_names(model_info: &ModelInfo) -> impl Iterator<Item = &'static str> {
+    let name = model_info.name;
+    name.strip_prefix("Roland ").unwrap_or(name).split('/')
+}
+
+fn find_model(name: &str) -> Result<&'static ModelInfo, String> {
+    MODELS
+        .iter()
+        .copied()
+        .find(|model_info| model_short_names(model_info).any(|n| n.eq_ignore_ascii_case(name)))
+        .ok_or_else(|| format!("Unknown model {:?}", name))
+}
+
+/// Parse the address block part of a line, returning the block's address
+/// prefix, name, and parameters.
+fn parse_block<'a>(
+    model_info: &'static ModelInfo,
+    tokens: &mut &'a [Token<'a>],
+) -> Result<(&'static [u8], &'static str, ParameterAddressMap), String> {
+    let matches_name = |expected: &dyn Fn(&str) -> bool| {
+        let mut candidates = model_info
+            .address_block_map
+            .iter()
+            .filter(|&&(_, name, pam)| expected(name) && !pam.is_empty());
+        match (candidates.next(), candidates.next()) {
+            (Some(&block), None) => Ok(block),
+            (None, _) => Err(()),
+            // Ambiguous, e.g. there could be several "System parameters"
+            // blocks in some model.
+            (Some(_), Some(_)) => Err(()),
+        }
+    };
+
+    let (block, description) = match *tokens {
+        [Token::Quoted(name), ref rest @ ..] => {
+            *tokens = rest;
+            (
+                matches_name(&|block_name| block_name.eq_ignore_ascii_case(name)),
+                format!("\"{}\"", name),
+            )
+        }
+        [Token::Word(word), ref rest @ ..] if word.eq_ignore_ascii_case("system") => {
+            *tokens = rest;
+            (
+                matches_name(&|block_name| block_name.starts_with("System parameters")),
+                "system".to_string(),
+            )
+        }
+        [Token::Word(word), ref rest @ ..] if word.eq_ignore_ascii_case("common") => {
+            *tokens = rest;
+            (
+                matches_name(&|block_name| block_name == "Patch parameters, Patch common"),
+                "common".to_string(),
+            )
+        }
+        [Token::Word(word), Token::Word(part), ref rest @ ..]
+            if word.eq_ignore_ascii_case("part") =>
+        {
+            let part: u8 = part
+                .parse()
+                .map_err(|_| format!("{:?} is not a valid part number", part))?;
+            let controllers =
+                matches!(rest, [Token::Word(word), ..] if word.eq_ignore_ascii_case("controllers"));
+            *tokens = if controllers { &rest[1..] } else { rest };
+            let expected = if controllers {
+                format!("Patch parameters, Part {} (controllers)", part)
+            } else {
+                format!("Patch parameters, Part {}", part)
+            };
+            (
+                matches_name(&|block_name| block_name == expected),
+                if controllers {
+                    format!("part {} controllers", part)
+                } else {
+                    format!("part {}", part)
+                },
+            )
+        }
+        [ref token, ..] => return Err(format!("Expected an address block, found {}", token)),
+        [] => return Err("Expected an address block".into()),
+    };
+
+    let (prefix, name, pam) = block.map_err(|()| {
+        format!(
+            "{} has no address block {} with known parameters",
+            model_info.name, description
+        )
+    })?;
+    Ok((prefix, name, pam))
+}
+
+/// Strip a "[...]" hint from the end of a parameter name, so e.g.
+/// `"Rx. BANK SELECT"` can be used for "Rx. BANK SELECT [SC-55mkII+]".
+fn parameter_name_without_hint(name: &str) -> &str {
+    match name.split_once(" [") {
+        Some((name, hint)) if hint.ends_with(']') => name,
+        _ => name,
+    }
+}
+
+fn find_parameter(
+    pam: ParameterAddressMap,
+    name: &str,
+) -> Option<&'static (&'static [u8], Parameter)> {
+    pam.iter()
+        .find(|(_, param)| param.name.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            pam.iter().find(|(_, param)| {
+                parameter_name_without_hint(param.name).eq_ignore_ascii_case(name)
+            })
+        })
+}
+
+/// Parse a raw hexadecimal data byte in `20h` or `0x20` style.
+fn parse_hex_byte(word: &str) -> Option<u8> {
+    let digits = word
+        .strip_suffix('h')
+        .or_else(|| word.strip_suffix('H'))
+        .or_else(|| word.strip_prefix("0x"))
+        .or_else(|| word.strip_prefix("0X"))?;
+    if digits.is_empty() || digits.len() > 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u8::from_str_radix(digits, 16).ok()
+}
+
+/// Parse the value part of a line and validate it against the parameter. If
+/// the value was given in a unit, the details are put in `unit_value`.
+fn parse_value(
+    param: &Parameter,
+    tokens: &[Token],
+    unit_value: &mut Option<UnitValue>,
+) -> Result<u8, String> {
+    let value = match *tokens {
+        [Token::Word(amount), Token::Word(unit)] if amount.parse::<f32>().is_ok() => {
+            let amount: f32 = amount.parse().unwrap();
+            let chosen = param.value_for_unit(amount, unit).map_err(|err| {
+                format!("Can't set {} to {} {}: {}", param.name, amount, unit, err)
+            })?;
+            *unit_value = Some(chosen);
+            chosen.value
+        }
+        [Token::Quoted(name)] => {
+            let values = match param.description {
+                ParameterValueDescription::Enum(values) => values,
+                ParameterValueDescription::Numeric { special_values, .. }
+                    if !special_values.is_empty() =>
+                {
+                    special_values
+                }
+                _ => return Err(format!("{} does not have named values", param.name)),
+            };
+            let Some(&(&[value], _)) = values
+                .iter()
+                .find(|&&(_, value_name)| value_name.eq_ignore_ascii_case(name))
+            else {
+                return Err(format!("{:?} is not a value of {}", name, param.name));
+            };
+            value
+        }
+        [Token::Word(word)] => {
+            if let Some(value) = parse_hex_byte(word) {
+                value
+            } else if let Ok(number) = word.parse::<i16>() {
+                let (zero_offset, special_values) = match param.description {
+                    ParameterValueDescription::Numeric {
+                        zero_offset,
+                        special_values,
+                        ..
+                    } => (zero_offset, special_values),
+                    _ => (0, &[][..]),
+                };
+                // Special values like "Random" are only set by name, so the
+                // numbers they'd otherwise have are out of range.
+                let is_numeric = |value: &u8| {
+                    param.range.contains(value)
+                        && !special_values
+                            .iter()
+                            .any(|&(special, _)| special == [*value])
+                };
+                let value = number + zero_offset as i16;
+                u8::try_from(value).ok().filter(is_numeric).ok_or_else(|| {
+                    let mut numeric = param.range.clone().filter(is_numeric);
+                    let min = numeric.next().unwrap_or(*param.range.start());
+                    let max = numeric.next_back().unwrap_or(min);
+                    format!(
+                        "{} is out of range for {} ({} to {})",
+                        number,
+                        param.name,
+                        min as i16 - zero_offset as i16,
+                        max as i16 - zero_offset as i16,
+                    )
+                })?
+            } else if let Some(key) = match param.description {
+                ParameterValueDescription::Note(convention) => parse_note_name(word, convention),
+                _ => None,
+            } {
+                key
+            } else {
+                return Err(format!("{:?} is not a valid value", word));
+            }
+        }
+        [] => return Err("Expected a value after \"=\"".into()),
+        [_, ref extra, ..] => return Err(format!("Unexpected {} after the value", extra)),
+        [ref token] => return Err(format!("{} is not a valid value", token)),
+    };
+
+    if !param.range.contains(&value) {
+        return Err(format!(
+            "{:02X}h is out of range for {} ({:02X}h to {:02X}h)",
+            value,
+            param.name,
+            param.range.start(),
+            param.range.end()
+        ));
+    }
+    if !param.value_is_valid(value) {
+        return Err(format!(
+            "{:02X}h is not a valid value of {}",
+            value, param.name
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Parse a sequence of hex bytes (see [parse_hex_byte]), which must all be
+/// valid MIDI data bytes unless `allow_status_bytes` is set.
+fn parse_hex_bytes(tokens: &[Token], allow_status_bytes: bool) -> Result<Vec<u8>, String> {
+    tokens
+        .iter()
+        .map(|token| match *token {
+            Token::Word(word) => match parse_hex_byte(word) {
+                Some(byte) if byte <= 0x7F || allow_status_bytes => Ok(byte),
+                Some(byte) => Err(format!("{:02X}h is not a data byte (> 7Fh)", byte)),
+                None => Err(format!("{:?} is not a hex byte", word)),
+            },
+            ref token => Err(format!("Expected a hex byte, found {}", token)),
+        })
+        .collect()
+}
+
+/// Compile a single line. Returns [false] if the line is blank. If the value
+/// was given in a unit, the parameter and the details are put in `unit_value`.
+fn compile_line(
+    line: &str,
+    out: &mut Vec<u8>,
+    unit_value: &mut Option<(&'static Parameter, UnitValue)>,
+) -> Result<bool, String> {
+    let tokens = tokenize(line)?;
+    let mut tokens = &tokens[..];
+
+    let model_info = match tokens {
+        [] => return Ok(false),
+        [Token::Word("raw"), ref rest @ ..] => {
+            let bytes = parse_hex_bytes(rest, /* allow_status_bytes: */ true)?;
+            // The rest isn't checked, so that a malformed SysEx can still be
+            // written down exactly.
+            match bytes[..] {
+                [0xF0, .., 0xF7] => (),
+                [0xF0, ..] => return Err(ParseFailure::MissingEndOfExclusive.to_string()),
+                _ => return Err(ParseFailure::NotSysEx.to_string()),
+            }
+            out.extend_from_slice(&bytes);
+            return Ok(true);
+        }
+        [Token::Word(model), ref rest @ ..] => {
+            tokens = rest;
+            find_model(model)?
+        }
+        [ref token, ..] => return Err(format!("Expected a model name, found {}", token)),
+    };
+
+    let device_id = match tokens {
+        [Token::Word("device"), Token::Word(device_id), ref rest @ ..] => {
+            tokens = rest;
+            parse_hex_byte(device_id)
+                .filter(|&device_id| device_id <= 0x7F)
+                .ok_or_else(|| format!("{:?} is not a valid device ID", device_id))?
+        }
+        _ => model_info.default_device_id,
+    };
+
+    // The checksum override, if present, is at the very end.
+    let checksum = match tokens {
+        [ref rest @ .., Token::Word("checksum"), Token::Word(value)] => {
+            tokens = rest;
+            Some(
+                parse_hex_byte(value)
+                    .filter(|&value| value <= 0x7F)
+                    .ok_or_else(|| format!("{:?} is not a valid checksum", value))?,
+            )
+        }
+        _ => None,
+    };
+
+    let (address, data) = if let [Token::Word("address"), ref rest @ ..] = tokens {
+        let Some(equals_idx) = rest.iter().position(|token| *token == Token::Equals) else {
+            return Err("Expected \"=\" after the address".into());
+        };
+        let address = parse_hex_bytes(&rest[..equals_idx], false)?;
+        let data = parse_hex_bytes(&rest[equals_idx + 1..], false)?;
+        if address.len() != model_info.address_size as usize {
+            return Err(format!(
+                "{} addresses are {} bytes long",
+                model_info.name, model_info.address_size
+            ));
+        }
+        (address, data)
+    } else {
+        let (prefix, block_name, pam) = parse_block(model_info, &mut tokens)?;
+
+        let (suffix, param) = match tokens {
+            [Token::Quoted(name), ref rest @ ..] => {
+                tokens = rest;
+                find_parameter(pam, name)
+                    .ok_or_else(|| format!("{} has no parameter {:?}", block_name, name))?
+            }
+            [ref token, ..] => return Err(format!("Expected a parameter name, found {}", token)),
+            [] => return Err("Expected a parameter name".into()),
+        };
+
+        if param.size != 1 || matches!(param.description, ParameterValueDescription::Other) {
+            return Err(format!(
+                "Setting {} is not supported yet, use \"address\" instead",
+                param.name
+            ));
+        }
+
+        let [Token::Equals, ref value_tokens @ ..] = tokens else {
+            return Err("Expected \"=\" after the parameter name".into());
+        };
+        let mut param_unit_value = None;
+        let value = parse_value(param, value_tokens, &mut param_unit_value)?;
+        *unit_value = param_unit_value.map(|unit_value| (param, unit_value));
+
+        let mut address = Vec::with_capacity(prefix.len() + suffix.len());
+        address.extend_from_slice(prefix);
+        address.extend_from_slice(suffix);
+        (address, vec![value])
+    };
+
+    generate_dt1(model_info, device_id, &address, &data, out);
+    if let Some(checksum) = checksum {
+        // Replace the correct checksum, which is just before the F7h.
+        let checksum_idx = out.len() - 2;
+        out[checksum_idx] = checksum;
+    }
+    Ok(true)
+}
+
+/// The SysExes [compile_text] produced, and the warnings for them.
+pub type CompiledText = (Vec<Vec<u8>>, Vec<TextError>);
+
+/// Compile a whole text into a list of SysExes, one per assignment, in the
+/// same order as in the text. If there are errors, all of them are returned.
+///
+/// Along with the SysExes, warnings are returned for assignments that are
+/// valid but can't have any effect, because a macro parameter on a later line
+/// sets the same parameter back to a preset value (see [macro_reset_warnings]).
+pub fn compile_text(text: &str) -> Result<CompiledText, Vec<TextError>> {
+    let mut sysexes = Vec::new();
+    let mut errors = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let mut sysex = Vec::new();
+        match compile_line(line, &mut sysex, &mut None) {
+            Ok(true) => sysexes.push((line_idx + 1, sysex)),
+            Ok(false) => (),
+            Err(message) => errors.push(TextError {
+                line: line_idx + 1,
+                message,
+            }),
+        }
+    }
+    if errors.is_empty() {
+        let warnings = macro_reset_warnings(&sysexes);
+        Ok((
+            sysexes.into_iter().map(|(_, sysex)| sysex).collect(),
+            warnings,
+        ))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Find the DT1s among `sysexes` (pairs of a line number and a SysEx) that set
+/// a parameter which a macro parameter on a later line then sets back to a
+/// preset value, e.g. REVERB LEVEL followed by REVERB MACRO. Such a tweak only
+/// sticks if it comes after the macro, like
+/// [super::roland::generate_macro_with_tweaks] does.
+fn macro_reset_warnings(sysexes: &[(usize, Vec<u8>)]) -> Vec<TextError> {
+    let param_name = |model_info: &ModelInfo, address: &[u8]| {
+        look_up_parameter(model_info, address)
+            .1
+            .map_or("(unknown)", |param| param.name)
+    };
+
+    // The line that last set each address, for each model and device ID.
+    type Tweak = (&'static ModelInfo, DeviceId, Vec<u8>, usize);
+    let mut tweaks: Vec<Tweak> = Vec::new();
+    let mut warnings = Vec::new();
+    for &(line, ref sysex) in sysexes {
+        let Ok(ParsedSysEx {
+            manufacturer_id: MF_ID_ROLAND,
+            content:
+                MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                    device_id,
+                    model_id,
+                    command:
+                        MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 { address, data, .. }),
+                    ..
+                })),
+        }) = parse_sysex(sysex)
+        else {
+            continue;
+        };
+        let Some(&model_info) = MODELS.iter().find(|model| model.model_id == model_id) else {
+            continue;
+        };
+        let tweak_idx = |tweaks: &[Tweak], address: &[u8]| {
+            tweaks
+                .iter()
+                .position(|(model_info2, device_id2, address2, _)| {
+                    std::ptr::eq(*model_info2, model_info)
+                        && *device_id2 == device_id
+                        && address2 == address
+                })
+        };
+
+        let mut address = address.to_vec();
+        for &byte in data {
+            if let Some(implied_values) = look_up_macro(model_info, &address)
+                .and_then(|macro_param| macro_param.implied_values(byte))
+            {
+                for (implied_address, _) in implied_values {
+                    let Some(idx) = tweak_idx(&tweaks, implied_address) else {
+                        continue;
+                    };
+                    let (_, _, _, tweak_line) = tweaks.remove(idx);
+                    warnings.push(TextError {
+                        line: tweak_line,
+                        message: format!(
+                            "{} is reset by {} on line {}, set it after the macro instead",
+                            param_name(model_info, implied_address),
+                            param_name(model_info, &address),
+                            line
+                        ),
+                    });
+                }
+            }
+
+            if let Some(idx) = tweak_idx(&tweaks, &address) {
+                tweaks.remove(idx);
+            }
+            tweaks.push((model_info, device_id, address.clone(), line));
+            next_address(&mut address);
+        }
+    }
+    warnings.sort_by_key(|warning| warning.line);
+    warnings
+}
+
+/// Compile a single assignment whose value is given in a unit, e.g.
+/// `gs part 1 controllers "MOD TVF CUT OFF CONTROL" = -1200 cents`, and return
+/// the SysEx along with the parameter and the value that was picked for it,
+/// which says how far off it is.
+pub fn compile_unit_assignment(
+    line: &str,
+) -> Result<(Vec<u8>, &'static Parameter, UnitValue), String> {
+    let mut sysex = Vec::new();
+    let mut unit_value = None;
+    if !compile_line(line, &mut sysex, &mut unit_value)? {
+        return Err("Expected an assignment".into());
+    }
+    let Some((param, unit_value)) = unit_value else {
+        return Err("Expected a value with a unit, e.g. \"-1200 cents\"".into());
+    };
+    Ok((sysex, param, unit_value))
+}
+
+/// Find the shortest way to refer to an address block in the text, checking
+/// that it would be resolved to the right block by [parse_block].
+fn block_reference(model_info: &'static ModelInfo, prefix: &[u8], block_name: &str) -> String {
+    let candidates = [
+        if block_name.starts_with("System parameters") {
+            Some("system".to_string())
+        } else {
+            None
+        },
+        if block_name == "Patch parameters, Patch common" {
+            Some("common".to_string())
+        } else {
+            None
+        },
+        block_name
+            .strip_prefix("Patch parameters, Part ")
+            .map(|part| match part.strip_suffix(" (controllers)") {
+                Some(part) => format!("part {} controllers", part),
+                None => format!("part {}", part),
+            }),
+    ];
+    for candidate in candidates.into_iter().flatten() {
+        let Ok(tokens) = tokenize(&candidate) else {
+            continue;
+        };
+        let mut tokens = &tokens[..];
+        if let Ok((prefix2, _, _)) = parse_block(model_info, &mut tokens) {
+            if prefix2 == prefix && tokens.is_empty() {
+                return candidate;
+            }
+        }
+    }
+    format!("\"{}\"", block_name)
+}
+
+/// Write the canonical text form of a SysEx, as a single line without a line
+/// break. [compile_text] turns this back into exactly the same bytes, even if
+/// the SysEx is invalid in some way, e.g. has a wrong checksum or an unknown
+/// parameter. Anything that isn't a Roland DT1 for a known model is written
+/// as a `raw` line.
+pub fn describe_canonically(sysex_bytes: &[u8], write_to: &mut impl std::fmt::Write) -> FmtResult {
+    let raw =
+        |write_to: &mut dyn std::fmt::Write| write!(write_to, "raw {}", format_bytes(sysex_bytes));
+
+    let Ok(ParsedSysEx {
+        manufacturer_id: MF_ID_ROLAND,
+        content:
+            MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                device_id,
+                model_id,
+                command_id: CM_ID_DT1,
+                command:
+                    MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                        address,
+                        data,
+                        valid_checksum,
+                        block_name_and_prefix_size,
+                        param_info,
+                        invalid_size,
+                        implied_values: _,
+                        block_write: _,
+                    }),
+                ..
+            })),
+    }) = parse_sysex(sysex_bytes)
+    else {
+        return raw(write_to);
+    };
+    let Some(&model_info) = MODELS.iter().find(|model| model.model_id == model_id) else {
+        return raw(write_to);
+    };
+
+    write!(
+        write_to,
+        "{}",
+        model_short_names(model_info)
+            .next()
+            .unwrap()
+            .to_ascii_lowercase()
+    )?;
+    if device_id != model_info.default_device_id {
+        write!(write_to, " device {:02X}h", device_id)?;
+    }
+
+    // The friendly form is only possible if the parameter is one that can be
+    // set by name and the value is valid for it.
+    let named = match (block_name_and_prefix_size, param_info, data) {
+        (Some((block_name, prefix_size)), Some(param), &[value])
+            if !invalid_size
+                && param.value_is_valid(value)
+                && !matches!(param.description, ParameterValueDescription::Other) =>
+        {
+            Some((&address[..prefix_size as usize], block_name, param, value))
+        }
+        _ => None,
+    };
+
+    if let Some((prefix, block_name, param, value)) = named {
+        write!(
+            write_to,
+            " {} \"{}\" = ",
+            block_reference(model_info, prefix, block_name),
+            param.name
+        )?;
+        if let Some(name) = param.value_name(&[value]) {
+            write!(write_to, "\"{}\"", name)?;
+        } else {
+            match param.description {
+                ParameterValueDescription::Numeric { zero_offset, .. } => {
+                    write!(write_to, "{}", value as i16 - zero_offset as i16)?
+                }
+                ParameterValueDescription::Note(_) | ParameterValueDescription::Controller => {
+                    write!(write_to, "{}", value)?
+                }
+                // Valid enum values always have a name.
+                ParameterValueDescription::Enum(_) | ParameterValueDescription::Other => {
+                    unreachable!()
+                }
+            }
+        }
+    } else {
+        write!(write_to, " address {} =", format_bytes(address))?;
+        if !data.is_empty() {
+            write!(write_to, " {}", format_bytes(data))?;
+        }
+    }
+
+    if !valid_checksum {
+        let checksum = sysex_bytes[sysex_bytes.len() - 2];
+        write!(write_to, " checksum {:02X}h", checksum)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn test_canonical_round_trip() {
+    use crate::state::GS_RESET;
+
+    let sysexes: &[&[u8]] = &[
+        // GS MASTER VOLUME = 127
+        &[
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x04, 0x7F, 0x3D, 0xF7,
+        ],
+        // Same again but with the wrong checksum and device ID
+        &[
+            0xF0, 0x41, 0x11, 0x42, 0x12, 0x40, 0x00, 0x04, 0x7F, 0x00, 0xF7,
+        ],
+        // GS REVERB MACRO, an enum value
+        &[
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x01, 0x30, 0x04, 0x0B, 0xF7,
+        ],
+        // GS PART PANPOT for part 3, special value and negative value
+        &[
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x13, 0x1C, 0x00, 0x11, 0xF7,
+        ],
+        &[
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x13, 0x1C, 0x01, 0x10, 0xF7,
+        ],
+        GS_RESET,
+        // Unknown GS parameter, with two data bytes
+        &[
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x7E, 0x01, 0x02, 0x3F, 0xF7,
+        ],
+        // Out of range value
+        &[
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x05, 0x00, 0x3B, 0xF7,
+        ],
+        // GM System On
+        &[0xF0, 0x7E, 0x7F, 0x09, 0x01, 0xF7],
+        // Not even a valid SysEx
+        &[0xF0, 0x41, 0x90, 0xF7],
+    ];
+
+    let mut text = String::new();
+    for sysex in sysexes {
+        describe_canonically(sysex, &mut text).unwrap();
+        text.push('\n');
+    }
+    assert_eq!(
+        text,
+        "\
+gs system \"MASTER VOLUME\" = 127
+gs device 11h system \"MASTER VOLUME\" = 127 checksum 00h
+gs common \"REVERB MACRO\" = \"Hall 2\"
+gs part 3 \"PART PANPOT\" = \"Random\"
+gs part 3 \"PART PANPOT\" = -63
+gs system \"MODE SET\" = \"GS Reset\"
+gs address 40h 00h 7Eh = 01h 02h
+gs address 40h 00h 05h = 00h
+raw F0h 7Eh 7Fh 09h 01h F7h
+raw F0h 41h 90h F7h
+"
+    );
+    assert_eq!(
+        compile_text(&text).unwrap(),
+        (sysexes.iter().map(|s| s.to_vec()).collect(), vec![])
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_compile_text() {
+    let (sysexes, warnings) = compile_text(
+        "\
+# Reverb setup
+gs system \"MASTER VOLUME\" = 127
+gs part 3 \"REVERB SEND LEVEL\" = 40 # the part's reverb
+gs part 3 \"PART PANPOT\" = \"Random\"
+gs common \"REVERB MACRO\" = \"Hall 2\"
+gs part 10 \"Rx. BANK SELECT\" = 0
+gs part 1 \"KEY RANGE LOW\" = C4
+
+sc-7 system \"CHORUS RATE\" = 0x20
+",
+    )
+    .unwrap();
+    assert_eq!(
+        sysexes,
+        &[
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x04, 0x7F, 0x3D, 0xF7][..],
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x13, 0x22, 0x28, 0x63, 0xF7],
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x13, 0x1C, 0x00, 0x11, 0xF7],
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x01, 0x30, 0x04, 0x0B, 0xF7],
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x10, 0x23, 0x00, 0x0D, 0xF7],
+            &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x1D, 0x3C, 0x56, 0xF7],
+            &[0xF0, 0x41, 0x10, 0x56, 0x12, 0x00, 0x00, 0x08, 0x20, 0x58, 0xF7],
+        ]
+    );
+    assert!(warnings.is_empty());
+
+    // A macro sets REVERB LEVEL and REVERB TIME back to its own values, so
+    // setting them before it has no effect. Other devices aren't affected.
+    let (sysexes, warnings) = compile_text(
+        "\
+gs common \"REVERB LEVEL\" = 100
+gs device 11h common \"REVERB TIME\" = 100
+gs common \"REVERB TIME\" = 90
+gs common \"REVERB TIME\" = 100
+gs common \"REVERB MACRO\" = \"Hall 2\"
+gs common \"REVERB LEVEL\" = 100
+gs common \"CHORUS MACRO\" = \"Chorus 1\"
+",
+    )
+    .unwrap();
+    assert_eq!(sysexes.len(), 7);
+    assert_eq!(
+        warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+        &[
+            "Line 1: REVERB LEVEL is reset by REVERB MACRO on line 5, set it after the macro instead",
+            "Line 4: REVERB TIME is reset by REVERB MACRO on line 5, set it after the macro instead",
+        ]
+    );
+
+    let errors = compile_text(
+        "\
+xg system \"MASTER VOLUME\" = 127
+gs part 17 \"PART LEVEL\" = 100
+gs system \"MASTER VOLUME\" = 128
+gs system \"MASTER KEY-SHIFT\" = 00h
+gs system \"MASTER VOLUNE\" = 127
+gs common \"REVERB MACRO\" = \"Hall 3\"
+gs part 3 \"PART PANPOT\" = -64
+raw 90h 3Ch 64h
+raw F0h 41h 10h
+",
+    )
+    .unwrap_err();
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        &[
+            "Line 1: Unknown model \"xg\"",
+            "Line 2: Roland GS has no address block part 17 with known parameters",
+            "Line 3: 128 is out of range for MASTER VOLUME (0 to 127)",
+            "Line 4: 00h is out of range for MASTER KEY-SHIFT (28h to 58h)",
+            "Line 5: System parameters has no parameter \"MASTER VOLUNE\"",
+            "Line 6: \"Hall 3\" is not a value of REVERB MACRO",
+            "Line 7: -64 is out of range for PART PANPOT (-63 to 63)",
+            "Line 8: Not a SysEx (doesn't start with F0h)",
+            "Line 9: Incomplete SysEx (doesn't end with F7h)",
+        ]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_compile_unit_assignment() {
+    let (sysex, param, unit_value) =
+        compile_unit_assignment("gs part 1 controllers \"MOD TVF CUT OFF CONTROL\" = -1200 cents")
+            .unwrap();
+    assert_eq!(param.name, "MOD TVF CUT OFF CONTROL");
+    assert_eq!(unit_value.value, 0x38);
+    assert_eq!(
+        unit_value.to_string(),
+        "38h (≈ -1209 cents, off by -9 cents)"
+    );
+    assert_eq!(
+        sysex,
+        &[0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x21, 0x01, 0x38, 0x66, 0xF7]
+    );
+
+    // Semitones can be used for a parameter in cents.
+    let (_, _, unit_value) =
+        compile_unit_assignment("gs part 1 controllers \"MOD TVF CUT OFF CONTROL\" = +3 semitones")
+            .unwrap();
+    assert_eq!(unit_value.value, 0x42);
+    assert_eq!(unit_value.unit, "semitones");
+
+    // Exact mappings have no error.
+    let (_, _, unit_value) =
+        compile_unit_assignment("gs system \"MASTER KEY-SHIFT\" = -12 semitones").unwrap();
+    assert_eq!(unit_value.value, 0x34);
+    assert_eq!(unit_value.error, 0.0);
+
+    assert_eq!(
+        compile_unit_assignment("gs part 1 controllers \"MOD TVF CUT OFF CONTROL\" = 2 Hz")
+            .unwrap_err(),
+        "Can't set MOD TVF CUT OFF CONTROL to 2 Hz: \"Hz\" can't be converted to cents"
+    );
+    assert_eq!(
+        compile_unit_assignment("gs system \"MASTER KEY-SHIFT\" = +25 semitones").unwrap_err(),
+        "Can't set MASTER KEY-SHIFT to 25 semitones: out of range (-24 to 24 semitones)"
+    );
+    assert_eq!(
+        compile_unit_assignment("gs common \"REVERB TIME\" = 50 %").unwrap_err(),
+        "Can't set REVERB TIME to 50 %: values are not in any unit"
+    );
+}
@@ -0,0 +1,334 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Picking random values for a set of parameters, for exploring sounds, see
+//! [compile_randomizer]. The parameters are picked with the same kind of
+//! lines as in the setup text, but without a value, or with a constraint
+//! instead:
+//!
+//! ```text
+//! # Every parameter in a block.
+//! gs common
+//! # But leave this one alone.
+//! gs common "CHORUS MACRO" keep
+//! # A hand-picked parameter, with a range.
+//! gs part 3 "REVERB SEND LEVEL" = 60..100
+//! # "*" in a name matches anything, so this is every level in the part.
+//! gs part 4 "*LEVEL" = 60..100
+//! # Any value but these.
+//! gs common "REVERB MACRO" except "Delay" "Panning Delay"
+//! ```
+//!
+//! A later line for the same parameter replaces the constraint from an earlier
+//! one. The values in a constraint are written as in the setup text. Only
+//! single-byte parameters that the setup text can set are picked.
+
+use super::{find_model, find_parameter, parse_block, parse_hex_byte, parse_value, tokenize};
+use super::{TextError, Token};
+use crate::sysex::roland::{
+    generate_dt1, look_up_macro, DeviceId, Parameter, ParameterValueDescription, MODELS,
+};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// The SplitMix64 generator: small, fast, and the same everywhere, so a seed
+/// always gives the same values.
+struct SplitMix64(u64);
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to but not including `n`.
+    fn below(&mut self, n: usize) -> usize {
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+}
+
+/// What to do with a parameter, see [compile_randomizer].
+#[derive(Debug)]
+struct Choice {
+    param: &'static Parameter,
+    /// The line that last changed this, for errors.
+    line: usize,
+    range: Option<RangeInclusive<u8>>,
+    except: Vec<u8>,
+    keep: bool,
+}
+
+/// The constraint at the end of a line.
+enum Constraint<'a> {
+    None,
+    Keep,
+    Range(&'a str),
+    Except(&'a [Token<'a>]),
+}
+
+/// Where a picked parameter is: the index of its model in [MODELS], the
+/// device ID, and the full address.
+type ChoiceKey = (usize, DeviceId, Vec<u8>);
+
+/// Whether `name` matches `pattern`, ignoring case, where `*` in the pattern
+/// matches any sequence of characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_uppercase();
+    let name = name.to_ascii_uppercase();
+    let mut pieces = pattern.split('*');
+    let first = pieces.next().unwrap();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let pieces: Vec<_> = pieces.collect();
+    let Some((last, middle)) = pieces.split_last() else {
+        return rest.is_empty();
+    };
+    for piece in middle {
+        let Some(idx) = rest.find(piece) else {
+            return false;
+        };
+        rest = &rest[idx + piece.len()..];
+    }
+    rest.ends_with(last)
+}
+
+/// Whether the setup text can set `param`, see [super::compile_line].
+fn is_supported(param: &Parameter) -> bool {
+    param.size == 1 && !matches!(param.description, ParameterValueDescription::Other)
+}
+
+fn compile_line(
+    line: &str,
+    line_number: usize,
+    choices: &mut BTreeMap<ChoiceKey, Choice>,
+) -> Result<(), String> {
+    let tokens = tokenize(line)?;
+    let mut tokens = &tokens[..];
+
+    let model_info = match tokens {
+        [] => return Ok(()),
+        [Token::Word(model), ref rest @ ..] => {
+            tokens = rest;
+            find_model(model)?
+        }
+        [ref token, ..] => return Err(format!("Expected a model name, found {}", token)),
+    };
+    let model_idx = MODELS
+        .iter()
+        .position(|model| model.name == model_info.name)
+        .unwrap();
+
+    let device_id = match tokens {
+        [Token::Word("device"), Token::Word(device_id), ref rest @ ..] => {
+            tokens = rest;
+            parse_hex_byte(device_id)
+                .filter(|&device_id| device_id <= 0x7F)
+                .ok_or_else(|| format!("{:?} is not a valid device ID", device_id))?
+        }
+        _ => model_info.default_device_id,
+    };
+
+    let (prefix, block_name, pam) = parse_block(model_info, &mut tokens)?;
+
+    let params: Vec<&(&[u8], Parameter)> = match tokens {
+        [Token::Quoted(name), ref rest @ ..] if name.contains('*') => {
+            tokens = rest;
+            let params: Vec<_> = pam
+                .iter()
+                .filter(|(_, param)| matches_pattern(name, param.name) && is_supported(param))
+                .collect();
+            if params.is_empty() {
+                return Err(format!(
+                    "{} has no parameters matching {:?}",
+                    block_name, name
+                ));
+            }
+            params
+        }
+        [Token::Quoted(name), ref rest @ ..] => {
+            tokens = rest;
+            let entry = find_parameter(pam, name)
+                .ok_or_else(|| format!("{} has no parameter {:?}", block_name, name))?;
+            if !is_supported(&entry.1) {
+                return Err(format!("Randomizing {} is not supported yet", entry.1.name));
+            }
+            vec![entry]
+        }
+        _ => pam
+            .iter()
+            .filter(|(_, param)| is_supported(param))
+            .collect(),
+    };
+
+    let constraint = match tokens {
+        [] => Constraint::None,
+        [Token::Word("keep")] => Constraint::Keep,
+        [Token::Equals, Token::Word(range)] => Constraint::Range(range),
+        [Token::Word("except"), ref values @ ..] if !values.is_empty() => {
+            Constraint::Except(values)
+        }
+        [ref token, ..] => {
+            return Err(format!(
+                "Expected \"= <min>..<max>\", \"except\" or \"keep\", found {}",
+                token
+            ))
+        }
+    };
+
+    for (suffix, param) in params {
+        let address = [prefix, suffix].concat();
+        let choice = choices
+            .entry((model_idx, device_id, address))
+            .or_insert(Choice {
+                param,
+                line: line_number,
+                range: None,
+                except: Vec::new(),
+                keep: false,
+            });
+        match constraint {
+            Constraint::None => continue,
+            Constraint::Keep => choice.keep = true,
+            Constraint::Range(range) => {
+                let (min, max) = range.split_once("..").unwrap_or((range, range));
+                let min = parse_value(param, &[Token::Word(min)], &mut None)?;
+                let max = parse_value(param, &[Token::Word(max)], &mut None)?;
+                choice.range = Some(min..=max);
+                choice.except.clear();
+                choice.keep = false;
+            }
+            Constraint::Except(values) => {
+                choice.except = values
+                    .iter()
+                    .map(|value| parse_value(param, std::slice::from_ref(value), &mut None))
+                    .collect::<Result<_, _>>()?;
+                choice.range = None;
+                choice.keep = false;
+            }
+        }
+        choice.line = line_number;
+    }
+    Ok(())
+}
+
+/// Compile a text picking parameters and constraints (see the
+/// [module documentation](self)) into a DT1 for each parameter, with a value
+/// picked at random from the valid values that meet the constraints. The same
+/// `seed` always gives the same values. Macro parameters (see
+/// [crate::sysex::roland::MacroParameter]) come first, so that they don't undo
+/// the other parameters, and then the rest in address order. If there are
+/// errors, all of them are returned.
+pub fn compile_randomizer(text: &str, seed: u64) -> Result<Vec<Vec<u8>>, Vec<TextError>> {
+    let mut choices = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        if let Err(message) = compile_line(line, line_idx + 1, &mut choices) {
+            errors.push(TextError {
+                line: line_idx + 1,
+                message,
+            });
+        }
+    }
+
+    let (macros, others): (Vec<_>, Vec<_>) =
+        choices
+            .iter()
+            .partition(|&(&(model_idx, _, ref address), _)| {
+                look_up_macro(MODELS[model_idx], address).is_some()
+            });
+
+    let mut rng = SplitMix64(seed);
+    let mut sysexes = Vec::new();
+    for ((model_idx, device_id, address), choice) in macros.into_iter().chain(others) {
+        if choice.keep {
+            continue;
+        }
+        let values: Vec<u8> = choice
+            .param
+            .valid_values()
+            .filter(|value| {
+                choice
+                    .range
+                    .as_ref()
+                    .is_none_or(|range| range.contains(value))
+            })
+            .filter(|value| !choice.except.contains(value))
+            .collect();
+        if values.is_empty() {
+            errors.push(TextError {
+                line: choice.line,
+                message: format!("No values of {} are left to pick from", choice.param.name),
+            });
+            continue;
+        }
+        let value = values[rng.below(values.len())];
+        let mut sysex = Vec::new();
+        generate_dt1(
+            MODELS[*model_idx],
+            *device_id,
+            address,
+            &[value],
+            &mut sysex,
+        );
+        sysexes.push(sysex);
+    }
+
+    if errors.is_empty() {
+        Ok(sysexes)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_compile_randomizer() {
+    use crate::sysex::roland::look_up_parameter;
+
+    let text = "\
+gs common
+gs common \"CHORUS MACRO\" keep
+gs common \"REVERB MACRO\" except \"Room 1\" \"Room 2\" \"Room 3\" \"Hall 1\"
+gs part 3 \"*SEND LEVEL\" = 60..64
+";
+    let sysexes = compile_randomizer(text, 1).unwrap();
+    assert_eq!(compile_randomizer(text, 1).unwrap(), sysexes);
+    assert_ne!(compile_randomizer(text, 2).unwrap(), sysexes);
+
+    let gs = crate::state::gs_model();
+    let picked: Vec<_> = sysexes
+        .iter()
+        .map(|sysex| {
+            let address = &sysex[5..8];
+            let (_, Some(param)) = look_up_parameter(gs, address) else {
+                unreachable!();
+            };
+            (param.name, sysex[8])
+        })
+        .collect();
+
+    // The reverb macro comes first, and the chorus macro is left alone.
+    assert_eq!(picked[0].0, "REVERB MACRO");
+    assert!(picked[0].1 >= 0x04);
+    assert!(!picked.iter().any(|&(name, _)| name == "CHORUS MACRO"));
+    // VOICE RESERVE can't be set by the setup text, so it isn't picked.
+    assert_eq!(picked.len(), 1 + 13 + 2);
+    for &(name, value) in &picked[14..] {
+        assert!(name.ends_with("SEND LEVEL"));
+        assert!((60..=64).contains(&value));
+    }
+
+    let errors = compile_randomizer("gs part 3 \"*SEND LEVEL\" = 60..50", 1).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].to_string(),
+        "Line 1: No values of CHORUS SEND LEVEL are left to pick from"
+    );
+}
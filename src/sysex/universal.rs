@@ -11,8 +11,8 @@
 //! The main reference here was the _MIDI 1.0 Detailed Specification_.
 
 use super::{
-    ManufacturerId, StaticSysExGenerator, SysExGenerator, SysExGeneratorMenuTrait,
-    MF_ID_UNIVERSAL_NON_REAL_TIME,
+    ByteSpanKind, ByteSpans, ManufacturerId, StaticSysExGenerator, SysExAnnotator, SysExGenerator,
+    SysExGeneratorMenuTrait, MF_ID_UNIVERSAL_NON_REAL_TIME,
 };
 use crate::midi::format_bytes;
 use crate::ui::{Menu, MenuItemResult};
@@ -50,6 +50,7 @@ pub type SubId2 = u8;
 // General MIDI ones.
 pub const SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON: SubId2 = 0x01;
 pub const SI2_NRT_GM_GENERAL_MIDI_SYSTEM_OFF: SubId2 = 0x02;
+pub const SI2_NRT_GM_GENERAL_MIDI_2_SYSTEM_ON: SubId2 = 0x03;
 
 #[derive(Debug)]
 pub struct ParsedUniversalSysExBody<'a> {
@@ -74,37 +75,101 @@ impl Display for ParsedUniversalSysExBody<'_> {
         } else {
             write!(f, "Device {:02X}h, ", device_id)?;
         }
-        match (real_time, sub_id1) {
-            (false, SI1_NRT_SAMPLE_DUMP_HEADER) => write!(f, "Sample Dump Header")?,
-            (false, SI1_NRT_SAMPLE_DATA_PACKET) => write!(f, "Sample Data Packet")?,
-            (false, SI1_NRT_SAMPLE_DUMP_REQUEST) => write!(f, "Sample Dump Request")?,
-            (false, SI1_NRT_MIDI_TIME_CODE) => write!(f, "MIDI Time Code")?,
-            (false, SI1_NRT_SAMPLE_DUMP_EXTENSIONS) => write!(f, "Sample Dump Extensions")?,
-            (false, SI1_NRT_GENERAL_INFORMATION) => write!(f, "General Information")?,
-            (false, SI1_NRT_FILE_DUMP) => write!(f, "File Dump")?,
-            (false, SI1_NRT_MIDI_TUNING_STANDARD) => write!(f, "MIDI Tuning Standard")?,
-            (false, SI1_NRT_GENERAL_MIDI) => write!(f, "General MIDI")?,
-            (false, SI1_NRT_END_OF_FILE) => write!(f, "End Of File")?,
-            (false, SI1_NRT_WAIT) => write!(f, "Wait")?,
-            (false, SI1_NRT_CANCEL) => write!(f, "Cancel")?,
-            (false, SI1_NRT_NAK) => write!(f, "NAK")?,
-            (false, SI1_NRT_ACK) => write!(f, "ACK")?,
-            (false, _) => write!(f, "Sub-ID#1 (unknown) {:02X}h", sub_id1)?,
-            // We don't have constants for the real-time ones so we can't
-            // meaningfully say they're unknown.
-            (true, _) => write!(f, "Sub-ID#1 {:02X}h", sub_id1)?,
-        }
-        match (real_time, sub_id1, sub_id2) {
-            (false, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON) => {
-                write!(f, ", General MIDI System On")?
+        write!(
+            f,
+            "{}, {}",
+            sub_id1_name(real_time, sub_id1),
+            sub_id2_name(real_time, sub_id1, sub_id2)
+        )?;
+        write!(f, ": {}", format_bytes(data))?;
+        Ok(())
+    }
+}
+
+fn sub_id1_name(real_time: bool, sub_id1: SubId1) -> impl Display {
+    struct SubId1Name(bool, SubId1);
+    impl Display for SubId1Name {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            let &SubId1Name(real_time, sub_id1) = self;
+            match (real_time, sub_id1) {
+                (false, SI1_NRT_SAMPLE_DUMP_HEADER) => write!(f, "Sample Dump Header"),
+                (false, SI1_NRT_SAMPLE_DATA_PACKET) => write!(f, "Sample Data Packet"),
+                (false, SI1_NRT_SAMPLE_DUMP_REQUEST) => write!(f, "Sample Dump Request"),
+                (false, SI1_NRT_MIDI_TIME_CODE) => write!(f, "MIDI Time Code"),
+                (false, SI1_NRT_SAMPLE_DUMP_EXTENSIONS) => write!(f, "Sample Dump Extensions"),
+                (false, SI1_NRT_GENERAL_INFORMATION) => write!(f, "General Information"),
+                (false, SI1_NRT_FILE_DUMP) => write!(f, "File Dump"),
+                (false, SI1_NRT_MIDI_TUNING_STANDARD) => write!(f, "MIDI Tuning Standard"),
+                (false, SI1_NRT_GENERAL_MIDI) => write!(f, "General MIDI"),
+                (false, SI1_NRT_END_OF_FILE) => write!(f, "End Of File"),
+                (false, SI1_NRT_WAIT) => write!(f, "Wait"),
+                (false, SI1_NRT_CANCEL) => write!(f, "Cancel"),
+                (false, SI1_NRT_NAK) => write!(f, "NAK"),
+                (false, SI1_NRT_ACK) => write!(f, "ACK"),
+                (false, _) => write!(f, "Sub-ID#1 (unknown) {:02X}h", sub_id1),
+                // We don't have constants for the real-time ones so we can't
+                // meaningfully say they're unknown.
+                (true, _) => write!(f, "Sub-ID#1 {:02X}h", sub_id1),
             }
-            (false, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_SYSTEM_OFF) => {
-                write!(f, ", General MIDI System Off")?
+        }
+    }
+    SubId1Name(real_time, sub_id1)
+}
+
+fn sub_id2_name(real_time: bool, sub_id1: SubId1, sub_id2: SubId2) -> impl Display {
+    struct SubId2Name(bool, SubId1, SubId2);
+    impl Display for SubId2Name {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            let &SubId2Name(real_time, sub_id1, sub_id2) = self;
+            match (real_time, sub_id1, sub_id2) {
+                (false, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_SYSTEM_ON) => {
+                    write!(f, "General MIDI System On")
+                }
+                (false, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_SYSTEM_OFF) => {
+                    write!(f, "General MIDI System Off")
+                }
+                (false, SI1_NRT_GENERAL_MIDI, SI2_NRT_GM_GENERAL_MIDI_2_SYSTEM_ON) => {
+                    write!(f, "General MIDI 2 System On")
+                }
+                _ => write!(f, "Sub-ID#2 {:02X}h", sub_id2),
             }
-            _ => write!(f, ", Sub-ID#2 {:02X}h", sub_id2)?,
         }
-        write!(f, ": {}", format_bytes(data))?;
-        Ok(())
+    }
+    SubId2Name(real_time, sub_id1, sub_id2)
+}
+
+impl SysExAnnotator for ParsedUniversalSysExBody<'_> {
+    fn annotate(&self, spans: &mut ByteSpans) {
+        let &ParsedUniversalSysExBody {
+            real_time,
+            device_id,
+            sub_id1,
+            sub_id2,
+            data,
+        } = self;
+
+        if device_id == DV_ID_BROADCAST {
+            spans.push(1, ByteSpanKind::DeviceId, format_args!("Broadcast"));
+        } else {
+            spans.push(
+                1,
+                ByteSpanKind::DeviceId,
+                format_args!("{:02X}h", device_id),
+            );
+        }
+        spans.push(
+            1,
+            ByteSpanKind::SubId,
+            format_args!("{}", sub_id1_name(real_time, sub_id1)),
+        );
+        spans.push(
+            1,
+            ByteSpanKind::SubId,
+            format_args!("{}", sub_id2_name(real_time, sub_id1, sub_id2)),
+        );
+        for &data_byte in data {
+            spans.push(1, ByteSpanKind::Data, format_args!("{:02X}h", data_byte));
+        }
     }
 }
 
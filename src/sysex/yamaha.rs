@@ -0,0 +1,397 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Yamaha SysEx parsing. Only the XG "Parameter change" message is understood,
+//! and only a subset of the XG parameters are known: the ones that have
+//! Roland GS equivalents (see [crate::transform::convert_gs_to_xg]).
+//!
+//! The reference here was Yamaha's _XG Specifications_.
+
+use super::{
+    ByteSpanKind, ByteSpans, ManufacturerId, StaticSysExGenerator, SysExAnnotator, SysExGenerator,
+    SysExGeneratorMenuTrait,
+};
+use crate::midi::format_bytes;
+use crate::ui::{Menu, MenuItemResult};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+pub const MF_ID_YAMAHA: ManufacturerId = 0x43;
+
+/// XG calls this the "device number". It's the low nibble of the byte after
+/// the manufacturer ID, and `0h` by default.
+pub type DeviceNumber = u8;
+
+/// The high nibble of the byte after the manufacturer ID.
+const COMMAND_PARAMETER_CHANGE: u8 = 0x10;
+
+pub const MODEL_ID_XG: u8 = 0x4C;
+
+/// "XG System On", for device number `0h`. Like a GS reset, the synth needs
+/// some time to process this.
+pub const XG_SYSTEM_ON: &[u8] = &[
+    0xF0,
+    MF_ID_YAMAHA,
+    COMMAND_PARAMETER_CHANGE,
+    MODEL_ID_XG,
+    0x00,
+    0x00,
+    0x7E,
+    0x00,
+    0xF7,
+];
+
+/// XG reverb types (REVERB TYPE MSB and LSB) and their names.
+pub const XG_REVERB_TYPES: &[([u8; 2], &str)] = &[
+    ([0x00, 0x00], "NO EFFECT"),
+    ([0x01, 0x00], "HALL1"),
+    ([0x01, 0x01], "HALL2"),
+    ([0x02, 0x00], "ROOM1"),
+    ([0x02, 0x01], "ROOM2"),
+    ([0x02, 0x02], "ROOM3"),
+    ([0x03, 0x00], "STAGE1"),
+    ([0x03, 0x01], "STAGE2"),
+    ([0x04, 0x00], "PLATE"),
+    ([0x10, 0x00], "WHITE ROOM"),
+    ([0x11, 0x00], "TUNNEL"),
+    ([0x12, 0x00], "CANYON"),
+    ([0x13, 0x00], "BASEMENT"),
+];
+
+/// XG chorus types (CHORUS TYPE MSB and LSB) and their names.
+pub const XG_CHORUS_TYPES: &[([u8; 2], &str)] = &[
+    ([0x00, 0x00], "NO EFFECT"),
+    ([0x41, 0x00], "CHORUS1"),
+    ([0x41, 0x01], "CHORUS2"),
+    ([0x41, 0x02], "CHORUS3"),
+    ([0x41, 0x08], "CHORUS4"),
+    ([0x42, 0x00], "CELESTE1"),
+    ([0x42, 0x01], "CELESTE2"),
+    ([0x42, 0x02], "CELESTE3"),
+    ([0x42, 0x08], "CELESTE4"),
+    ([0x43, 0x00], "FLANGER1"),
+    ([0x43, 0x01], "FLANGER2"),
+    ([0x43, 0x08], "FLANGER3"),
+];
+
+/// Low byte of the address and size of the parameter, and its name.
+type XgParameterMap = &'static [(u8, u8, &'static str)];
+
+const XG_SYSTEM: XgParameterMap = &[
+    (0x00, 0x04, "MASTER TUNE"),
+    (0x04, 0x01, "MASTER VOLUME"),
+    (0x05, 0x01, "MASTER ATTENUATOR"),
+    (0x06, 0x01, "TRANSPOSE"),
+    (0x7D, 0x01, "DRUM SETUP RESET"),
+    (0x7E, 0x01, "XG SYSTEM ON"),
+    (0x7F, 0x01, "ALL PARAMETER RESET"),
+];
+
+const XG_EFFECT_1: XgParameterMap = &[
+    (0x00, 0x02, "REVERB TYPE"),
+    (0x0C, 0x01, "REVERB RETURN"),
+    (0x0D, 0x01, "REVERB PAN"),
+    (0x20, 0x02, "CHORUS TYPE"),
+    (0x2C, 0x01, "CHORUS RETURN"),
+    (0x2D, 0x01, "CHORUS PAN"),
+    (0x2E, 0x01, "SEND CHORUS TO REVERB"),
+];
+
+const XG_MULTI_PART: XgParameterMap = &[
+    (0x01, 0x01, "BANK SELECT MSB"),
+    (0x02, 0x01, "BANK SELECT LSB"),
+    (0x03, 0x01, "PROGRAM NUMBER"),
+    (0x04, 0x01, "Rcv CHANNEL"),
+    (0x05, 0x01, "MONO/POLY MODE"),
+    (0x07, 0x01, "PART MODE"),
+    (0x08, 0x01, "NOTE SHIFT"),
+    (0x09, 0x02, "DETUNE"),
+    (0x0B, 0x01, "VOLUME"),
+    (0x0C, 0x01, "VELOCITY SENSE DEPTH"),
+    (0x0D, 0x01, "VELOCITY SENSE OFFSET"),
+    (0x0E, 0x01, "PAN"),
+    (0x0F, 0x01, "NOTE LIMIT LOW"),
+    (0x10, 0x01, "NOTE LIMIT HIGH"),
+    (0x12, 0x01, "CHORUS SEND"),
+    (0x13, 0x01, "REVERB SEND"),
+    (0x15, 0x01, "VIBRATO RATE"),
+    (0x16, 0x01, "VIBRATO DEPTH"),
+    (0x17, 0x01, "VIBRATO DELAY"),
+    (0x18, 0x01, "FILTER CUTOFF FREQUENCY"),
+    (0x19, 0x01, "FILTER RESONANCE"),
+    (0x1A, 0x01, "EG ATTACK TIME"),
+    (0x1B, 0x01, "EG DECAY TIME"),
+    (0x1C, 0x01, "EG RELEASE TIME"),
+    (0x1D, 0x01, "MW PITCH CONTROL"),
+    (0x1E, 0x01, "MW FILTER CONTROL"),
+    (0x1F, 0x01, "MW AMPLITUDE CONTROL"),
+    (0x20, 0x01, "MW LFO PMOD DEPTH"),
+    (0x21, 0x01, "MW LFO FMOD DEPTH"),
+    (0x22, 0x01, "MW LFO AMOD DEPTH"),
+    (0x23, 0x01, "BEND PITCH CONTROL"),
+    (0x24, 0x01, "BEND FILTER CONTROL"),
+    (0x25, 0x01, "BEND AMPLITUDE CONTROL"),
+    (0x26, 0x01, "BEND LFO PMOD DEPTH"),
+    (0x27, 0x01, "BEND LFO FMOD DEPTH"),
+    (0x28, 0x01, "BEND LFO AMOD DEPTH"),
+    (0x30, 0x01, "Rcv PITCH BEND"),
+    (0x31, 0x01, "Rcv CH AFTER TOUCH (CAT)"),
+    (0x32, 0x01, "Rcv PROGRAM CHANGE"),
+    (0x33, 0x01, "Rcv CONTROL CHANGE"),
+    (0x34, 0x01, "Rcv POLY AFTER TOUCH (PAT)"),
+    (0x35, 0x01, "Rcv NOTE MESSAGE"),
+    (0x36, 0x01, "Rcv RPN"),
+    (0x37, 0x01, "Rcv NRPN"),
+    (0x38, 0x01, "Rcv MODULATION"),
+    (0x39, 0x01, "Rcv VOLUME"),
+    (0x3A, 0x01, "Rcv PAN"),
+    (0x3B, 0x01, "Rcv EXPRESSION"),
+    (0x3C, 0x01, "Rcv HOLD1"),
+    (0x3D, 0x01, "Rcv PORTAMENTO"),
+    (0x3E, 0x01, "Rcv SOSTENUTO"),
+    (0x3F, 0x01, "Rcv SOFT PEDAL"),
+    (0x40, 0x01, "Rcv BANK SELECT"),
+    (0x41, 0x01, "SCALE TUNING C"),
+    (0x42, 0x01, "SCALE TUNING C#"),
+    (0x43, 0x01, "SCALE TUNING D"),
+    (0x44, 0x01, "SCALE TUNING D#"),
+    (0x45, 0x01, "SCALE TUNING E"),
+    (0x46, 0x01, "SCALE TUNING F"),
+    (0x47, 0x01, "SCALE TUNING F#"),
+    (0x48, 0x01, "SCALE TUNING G"),
+    (0x49, 0x01, "SCALE TUNING G#"),
+    (0x4A, 0x01, "SCALE TUNING A"),
+    (0x4B, 0x01, "SCALE TUNING A#"),
+    (0x4C, 0x01, "SCALE TUNING B"),
+    (0x4D, 0x01, "CAT PITCH CONTROL"),
+    (0x4E, 0x01, "CAT FILTER CONTROL"),
+    (0x4F, 0x01, "CAT AMPLITUDE CONTROL"),
+    (0x50, 0x01, "CAT LFO PMOD DEPTH"),
+    (0x51, 0x01, "CAT LFO FMOD DEPTH"),
+    (0x52, 0x01, "CAT LFO AMOD DEPTH"),
+    (0x53, 0x01, "PAT PITCH CONTROL"),
+    (0x54, 0x01, "PAT FILTER CONTROL"),
+    (0x55, 0x01, "PAT AMPLITUDE CONTROL"),
+    (0x56, 0x01, "PAT LFO PMOD DEPTH"),
+    (0x57, 0x01, "PAT LFO FMOD DEPTH"),
+    (0x58, 0x01, "PAT LFO AMOD DEPTH"),
+    (0x59, 0x01, "AC1 CONTROLLER NUMBER"),
+    (0x5A, 0x01, "AC1 PITCH CONTROL"),
+    (0x5B, 0x01, "AC1 FILTER CONTROL"),
+    (0x5C, 0x01, "AC1 AMPLITUDE CONTROL"),
+    (0x5D, 0x01, "AC1 LFO PMOD DEPTH"),
+    (0x5E, 0x01, "AC1 LFO FMOD DEPTH"),
+    (0x5F, 0x01, "AC1 LFO AMOD DEPTH"),
+    (0x60, 0x01, "AC2 CONTROLLER NUMBER"),
+    (0x61, 0x01, "AC2 PITCH CONTROL"),
+    (0x62, 0x01, "AC2 FILTER CONTROL"),
+    (0x63, 0x01, "AC2 AMPLITUDE CONTROL"),
+    (0x64, 0x01, "AC2 LFO PMOD DEPTH"),
+    (0x65, 0x01, "AC2 LFO FMOD DEPTH"),
+    (0x66, 0x01, "AC2 LFO AMOD DEPTH"),
+];
+
+/// Name of the parameter block `address` is in, and the name and size of the
+/// parameter, if known. The XG multi part blocks are numbered by part, and the
+/// parts are numbered from 1 here, like Roland's are.
+pub fn look_up_xg_parameter(address: [u8; 3]) -> Option<(String, &'static str, u8)> {
+    let (block_name, map) = match address {
+        [0x00, 0x00, _] => ("System".to_string(), XG_SYSTEM),
+        [0x02, 0x01, _] => ("Effect 1".to_string(), XG_EFFECT_1),
+        [0x08, part @ 0x00..=0x0F, _] => (format!("Multi part, Part {}", part + 1), XG_MULTI_PART),
+        _ => return None,
+    };
+    map.iter()
+        .find(|&&(lsb, _, _)| lsb == address[2])
+        .map(|&(_, size, name)| (block_name, name, size))
+}
+
+/// Write an XG parameter change SysEx setting the parameter at `address` to
+/// `data` to `out`.
+pub fn generate_xg_parameter_change(
+    device_number: DeviceNumber,
+    address: [u8; 3],
+    data: &[u8],
+    out: &mut Vec<u8>,
+) {
+    assert!(device_number <= 0x0F);
+    out.extend_from_slice(&[
+        0xF0,
+        MF_ID_YAMAHA,
+        COMMAND_PARAMETER_CHANGE | device_number,
+        MODEL_ID_XG,
+    ]);
+    out.extend_from_slice(&address);
+    out.extend_from_slice(data);
+    out.push(0xF7);
+}
+
+#[derive(Debug)]
+pub enum ParsedYamahaSysExBody<'a> {
+    /// XG "Parameter change". The data isn't validated.
+    XgParameterChange {
+        device_number: DeviceNumber,
+        address: [u8; 3],
+        data: &'a [u8],
+    },
+}
+impl Display for ParsedYamahaSysExBody<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let &ParsedYamahaSysExBody::XgParameterChange {
+            device_number,
+            address,
+            data,
+        } = self;
+        write!(
+            f,
+            "Device number {:X}h, XG: Parameter change: {} => {}",
+            device_number,
+            describe_address(address),
+            format_bytes(data)
+        )?;
+        if let Some(name) = describe_effect_type(address, data) {
+            write!(f, " [{}]", name)?;
+        }
+        Ok(())
+    }
+}
+
+fn describe_address(address: [u8; 3]) -> impl Display {
+    struct AddressDescription([u8; 3]);
+    impl Display for AddressDescription {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            match look_up_xg_parameter(self.0) {
+                Some((block_name, name, _)) => write!(f, "{} § {}", block_name, name),
+                None => write!(f, "(unknown) {}", format_bytes(&self.0)),
+            }
+        }
+    }
+    AddressDescription(address)
+}
+
+fn describe_effect_type(address: [u8; 3], data: &[u8]) -> Option<&'static str> {
+    let types = match address {
+        [0x02, 0x01, 0x00] => XG_REVERB_TYPES,
+        [0x02, 0x01, 0x20] => XG_CHORUS_TYPES,
+        _ => return None,
+    };
+    types
+        .iter()
+        .find(|&&(effect_type, _)| effect_type == data)
+        .map(|&(_, name)| name)
+}
+
+impl SysExGenerator for ParsedYamahaSysExBody<'_> {
+    fn generate(&self, out: &mut Vec<u8>) {
+        let &ParsedYamahaSysExBody::XgParameterChange {
+            device_number,
+            address,
+            data,
+        } = self;
+        out.extend_from_slice(&[COMMAND_PARAMETER_CHANGE | device_number, MODEL_ID_XG]);
+        out.extend_from_slice(&address);
+        out.extend_from_slice(data);
+    }
+}
+
+impl SysExAnnotator for ParsedYamahaSysExBody<'_> {
+    fn annotate(&self, spans: &mut ByteSpans) {
+        let &ParsedYamahaSysExBody::XgParameterChange {
+            device_number,
+            address,
+            data,
+        } = self;
+        spans.push(
+            1,
+            ByteSpanKind::DeviceId,
+            format_args!("Parameter change, device number {:X}h", device_number),
+        );
+        spans.push(1, ByteSpanKind::ModelId, format_args!("XG"));
+        spans.push(
+            address.len(),
+            ByteSpanKind::Address,
+            format_args!("{}", describe_address(address)),
+        );
+        let effect_type = describe_effect_type(address, data);
+        for &data_byte in data {
+            match effect_type {
+                Some(name) => spans.push(
+                    1,
+                    ByteSpanKind::Data,
+                    format_args!("{:02X}h ({})", data_byte, name),
+                ),
+                None => spans.push(1, ByteSpanKind::Data, format_args!("{:02X}h", data_byte)),
+            }
+        }
+    }
+}
+
+#[allow(clippy::result_unit_err)] // not much explanation can be given really
+pub fn parse_sysex_body(body: &[u8]) -> Result<ParsedYamahaSysExBody<'_>, ()> {
+    match *body {
+        [command_and_device_number, MODEL_ID_XG, a, b, c, ref data @ ..]
+            if command_and_device_number & 0xF0 == COMMAND_PARAMETER_CHANGE =>
+        {
+            Ok(ParsedYamahaSysExBody::XgParameterChange {
+                device_number: command_and_device_number & 0x0F,
+                address: [a, b, c],
+                data,
+            })
+        }
+        _ => Err(()),
+    }
+}
+
+pub(super) fn generate_sysex() -> Box<SysExGeneratorMenuTrait> {
+    struct SysExGeneratorMenu;
+
+    #[allow(clippy::type_complexity)]
+    const SYSEX_GENERATORS: &[(&str, fn() -> Box<dyn SysExGenerator>)] =
+        &[("4Ch — XG System On (@ 0h)", || {
+            Box::new(StaticSysExGenerator(XG_SYSTEM_ON))
+        })];
+
+    impl Menu<Box<dyn SysExGenerator>> for SysExGeneratorMenu {
+        fn items_count(&self) -> usize {
+            SYSEX_GENERATORS.len()
+        }
+        fn item_label(&self, item_idx: usize, write_to: &mut dyn std::fmt::Write) -> FmtResult {
+            write!(write_to, "{}", SYSEX_GENERATORS[item_idx].0)
+        }
+        fn item_descend(&self, item_idx: usize) -> MenuItemResult<Box<dyn SysExGenerator>> {
+            MenuItemResult::Command(SYSEX_GENERATORS[item_idx].1())
+        }
+    }
+
+    Box::new(SysExGeneratorMenu)
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_xg_parameter_change() {
+    use super::parse_sysex;
+
+    assert_eq!(
+        parse_sysex(XG_SYSTEM_ON).unwrap().to_string(),
+        "Yamaha: Device number 0h, XG: Parameter change: System § XG SYSTEM ON => 00h"
+    );
+
+    let mut sysex = Vec::new();
+    generate_xg_parameter_change(0x01, [0x02, 0x01, 0x00], &[0x04, 0x00], &mut sysex);
+    assert_eq!(
+        sysex,
+        &[0xF0, 0x43, 0x11, 0x4C, 0x02, 0x01, 0x00, 0x04, 0x00, 0xF7]
+    );
+    let parsed = parse_sysex(&sysex).unwrap();
+    assert_eq!(
+        parsed.to_string(),
+        "Yamaha: Device number 1h, XG: Parameter change: Effect 1 § REVERB TYPE => 04h 00h [PLATE]"
+    );
+    let mut regenerated = Vec::new();
+    parsed.generate(&mut regenerated);
+    assert_eq!(regenerated, sysex);
+    assert_eq!(parsed.byte_spans().len(), 8);
+}
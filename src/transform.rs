@@ -0,0 +1,1106 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Operations that rewrite the SysExes in a [MidiData] in bulk.
+
+use crate::lint::{device_ids, lint_sysexes, Problem, RESET_TIME_MS};
+use crate::midi::{format_bytes, AbsoluteTime, ChannelMessageKind, MidiData, TimeConverter};
+use crate::nrpn::{
+    gs_channel_default_part, gs_dt1_to_parameter_number, gs_part_default_channel,
+    parameter_number_messages, parameter_number_to_gs_dt1, CC_DATA_DECREMENT, CC_DATA_ENTRY_LSB,
+    CC_DATA_ENTRY_MSB, CC_DATA_INCREMENT, CC_NRPN_LSB, CC_RPN_MSB, PARAMETER_NUMBER_NULL,
+};
+use crate::state::{dt1_writes, gs_model, gs_part_address, parse_reset, SynthState};
+use crate::sysex::roland::{
+    self, generate_dt1, next_address, DeviceId, ModelInfo, ParsedRolandSysExBody,
+    ParsedRolandSysExCommand, MODELS,
+};
+use crate::sysex::{parse_sysex, MaybeParsed, ParsedSysEx, ParsedSysExBody};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+mod parts;
+mod ramp;
+mod xg;
+pub use parts::copy_parts;
+pub use ramp::{insert_ramp, Curve, Ramp};
+pub use xg::convert_gs_to_xg;
+
+/// What [retarget_device_id] changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RetargetReport {
+    /// Number of SysExes whose device ID was changed.
+    pub changed: usize,
+    /// Number of SysExes left alone because they're for a model whose device
+    /// ID can't be changed (see [ModelInfo::device_id_configurable]).
+    pub skipped: usize,
+}
+
+/// Whether `sysex` is a Roland SysEx for a model whose device ID can't be
+/// changed, e.g. the SC-7.
+fn has_fixed_device_id(sysex: &[u8]) -> bool {
+    let Ok(ParsedSysEx {
+        content:
+            MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+                model_id, ..
+            })),
+        ..
+    }) = parse_sysex(sysex)
+    else {
+        return false;
+    };
+    MODELS
+        .iter()
+        .any(|model| model.model_id == model_id && !model.device_id_configurable)
+}
+
+/// Change the device ID of every Roland SysEx in `data` to `device_id`, e.g.
+/// to make a song written for one Sound Canvas play on a second unit. Other
+/// SysExes, including universal ones, are left alone, and so are those for
+/// models whose device ID can't be changed. Returns an error if `device_id`
+/// isn't valid.
+pub fn retarget_device_id(
+    data: &mut MidiData,
+    device_id: DeviceId,
+) -> Result<RetargetReport, String> {
+    let mut report = RetargetReport::default();
+    for (_, bytes) in &mut data.other_events {
+        let old_device_id = bytes.get(2).copied();
+        if old_device_id == Some(device_id) {
+            continue;
+        }
+        if has_fixed_device_id(bytes) {
+            report.skipped += 1;
+        } else if roland::set_device_id(bytes, device_id)? {
+            report.changed += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// A checksum fixed by [repair_checksums].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChecksumRepair {
+    /// Index in [MidiData::other_events].
+    pub index: usize,
+    pub time: AbsoluteTime,
+    pub wrong: u8,
+    pub correct: u8,
+}
+
+/// Fix the checksum of every Roland SysEx in `data` that has a wrong one (see
+/// [roland::fix_checksum]), which the synth would otherwise silently ignore.
+/// SysExes for other manufacturers are left alone.
+pub fn repair_checksums(data: &mut MidiData) -> Vec<ChecksumRepair> {
+    data.other_events
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(index, (time, bytes))| {
+            let (wrong, correct) = roland::fix_checksum(bytes)?;
+            Some(ChecksumRepair {
+                index,
+                time: *time,
+                wrong,
+                correct,
+            })
+        })
+        .collect()
+}
+
+/// Time to leave after any other SysEx, so the synth isn't flooded.
+const PROCESSING_TIME_MS: f64 = 20.0;
+
+/// What [optimize_sysexes] changed.
+#[derive(Debug, Default)]
+pub struct OptimizeReport {
+    pub checksums_fixed: usize,
+    /// Time, bytes and reason for each SysEx that was dropped.
+    pub dropped: Vec<(AbsoluteTime, Vec<u8>, Problem)>,
+    /// Number of DT1s that were merged into the one before.
+    pub merged: usize,
+    /// Number of SysExes whose time changed to make room for processing.
+    pub moved: usize,
+    /// Number of ticks the first notes and everything after them were
+    /// delayed by, to make room for the setup.
+    pub lead_in: AbsoluteTime,
+}
+impl Display for OptimizeReport {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(
+            f,
+            "Fixed the checksum of {} SysEx(es).",
+            self.checksums_fixed
+        )?;
+        writeln!(f, "Dropped {} SysEx(es).", self.dropped.len())?;
+        for (time, bytes, problem) in &self.dropped {
+            writeln!(f, "- {}: {}: {}", time, format_bytes(bytes), problem)?;
+        }
+        writeln!(f, "Merged {} DT1(s) into the one before.", self.merged)?;
+        writeln!(
+            f,
+            "Moved {} SysEx(es) to leave time for processing.",
+            self.moved
+        )?;
+        writeln!(
+            f,
+            "Delayed the first notes by {} tick(s) to make room for the setup.",
+            self.lead_in
+        )
+    }
+}
+
+/// A DT1 for a model in [MODELS], as far as [optimize_sysexes] cares.
+fn parse_dt1(sysex: &[u8]) -> Option<(&'static ModelInfo, DeviceId, &[u8], &[u8])> {
+    if parse_reset(sysex).is_some() {
+        return None;
+    }
+    let MaybeParsed::Parsed(ParsedSysExBody::Roland(ParsedRolandSysExBody::TypeIV {
+        device_id,
+        model_id,
+        command:
+            MaybeParsed::Parsed(ParsedRolandSysExCommand::DT1 {
+                address,
+                data,
+                valid_checksum: true,
+                invalid_size: false,
+                ..
+            }),
+        ..
+    })) = parse_sysex(sysex).ok()?.content
+    else {
+        return None;
+    };
+    let model_info = MODELS.iter().find(|model| model.model_id == model_id)?;
+    Some((model_info, device_id, address, data))
+}
+
+/// Improve the SysExes in `data`, in this order:
+///
+/// 1. Fix the checksums of Roland DT1s (see [repair_checksums]).
+/// 2. Drop SysExes that are redundant, or overwritten or wiped by a reset
+///    before any notes play (see [lint_sysexes]), if replaying `data` (see
+///    [SynthState::apply_events]) confirms that no synth ends up any
+///    different without them.
+/// 3. Merge each DT1 into the one before, if it continues where that one left
+///    off and no channel messages come in between.
+/// 4. Space out the SysExes, leaving time for the synth to finish a reset or
+///    process a SysEx. In the setup (everything up to the first note), the
+///    channel messages wait for the SysExes too, and everything after is
+///    delayed to match, so the setup isn't still going on under the first
+///    notes. Later SysExes are only moved relative to each other, and never
+///    past a channel message they came before, since that could change how a
+///    note sounds.
+pub fn optimize_sysexes(data: &mut MidiData) -> OptimizeReport {
+    let mut report = OptimizeReport {
+        checksums_fixed: repair_checksums(data).len(),
+        ..Default::default()
+    };
+
+    // Each drop is checked with the state model before it's made: with and
+    // without the SysEx, every synth must be in the same state once the
+    // SysEx's values are back in effect, overwritten or wiped.
+    let device_ids = device_ids(data);
+    let mut drop = vec![false; data.other_events.len()];
+    for lint in lint_sysexes(data) {
+        let until = match lint.problem {
+            Problem::Redundant => lint.time,
+            Problem::Overwritten { time } | Problem::WipedByReset { time } => time,
+            _ => continue,
+        };
+        let states_until = |data: &MidiData| -> Vec<SynthState> {
+            device_ids
+                .iter()
+                .flat_map(|&device_id| [SynthState::new(device_id), SynthState::unknown(device_id)])
+                .map(|mut state| {
+                    state.apply_events(data, until, |_, _| ());
+                    state
+                })
+                .collect()
+        };
+        let before = states_until(data);
+        // An empty SysEx is ignored, see SynthState::apply_sysex().
+        let bytes = std::mem::take(&mut data.other_events[lint.index].1);
+        if states_until(data) == before {
+            drop[lint.index] = true;
+            report
+                .dropped
+                .push((data.other_events[lint.index].0, bytes, lint.problem));
+        } else {
+            data.other_events[lint.index].1 = bytes;
+        }
+    }
+    let mut other_events: Vec<_> = std::mem::take(&mut data.other_events)
+        .into_iter()
+        .zip(drop)
+        .filter(|&(_, drop)| !drop)
+        .map(|(event, _)| event)
+        .collect();
+    other_events.sort_by_key(|&(time, _)| time);
+
+    let channel_message_between = |from: AbsoluteTime, until: AbsoluteTime| {
+        data.channel_messages
+            .iter()
+            .any(|&(time, _)| from <= time && time < until)
+    };
+    let mut merged_events: Vec<(AbsoluteTime, Vec<u8>)> = Vec::new();
+    for (time, bytes) in other_events {
+        let merged = parse_dt1(&bytes).and_then(|(model_info, device_id, address, dt1_data)| {
+            let &mut (last_time, ref mut last_bytes) = merged_events.last_mut()?;
+            let (last_model_info, last_device_id, last_address, last_data) = parse_dt1(last_bytes)?;
+            let mut end = last_address.to_vec();
+            for _ in last_data {
+                next_address(&mut end);
+            }
+            if last_model_info.model_id != model_info.model_id
+                || last_device_id != device_id
+                || end != address
+                || channel_message_between(last_time, time)
+            {
+                return None;
+            }
+            let mut merged = Vec::new();
+            generate_dt1(
+                model_info,
+                device_id,
+                last_address,
+                &[last_data, dt1_data].concat(),
+                &mut merged,
+            );
+            *last_bytes = merged;
+            Some(())
+        });
+        if merged.is_some() {
+            report.merged += 1;
+        } else {
+            merged_events.push((time, bytes));
+        }
+    }
+    data.other_events = merged_events;
+
+    let time_converter = TimeConverter::new(data);
+    let first_note = data
+        .channel_messages
+        .iter()
+        .filter(|(_, message)| {
+            matches!(message.kind, ChannelMessageKind::NoteOn { velocity, .. } if velocity != 0)
+        })
+        .map(|&(time, _)| time)
+        .min();
+    // SysExes and meta events come before channel messages at the same time,
+    // see crate::state::SynthState::replay.
+    let mut order: Vec<(AbsoluteTime, bool, usize)> = data
+        .other_events
+        .iter()
+        .enumerate()
+        .map(|(index, &(time, _))| (time, false, index))
+        .chain(
+            data.channel_messages
+                .iter()
+                .enumerate()
+                .map(|(index, &(time, _))| (time, true, index)),
+        )
+        .collect();
+    order.sort_by_key(|&(time, is_channel_message, _)| (time, is_channel_message));
+    // For each event, the time of the next channel message from it on.
+    let mut next_channel_message = vec![None; order.len()];
+    for i in (0..order.len()).rev() {
+        next_channel_message[i] = match order[i] {
+            (time, true, _) => Some(time),
+            _ => next_channel_message.get(i + 1).copied().flatten(),
+        };
+    }
+    let mut shift = 0;
+    // When the synth will be ready for the next SysEx.
+    let mut ready = 0;
+    for (i, (time, is_channel_message, index)) in order.into_iter().enumerate() {
+        let in_setup = first_note.is_none_or(|first_note| time <= first_note);
+        let mut new_time = time + shift;
+        if is_channel_message {
+            if in_setup {
+                new_time = new_time.max(ready);
+                shift = new_time - time;
+            }
+            data.channel_messages[index].0 = new_time;
+            continue;
+        }
+
+        let bytes = &data.other_events[index].1;
+        if bytes.first() != Some(&0xF0) {
+            data.other_events[index].0 = new_time;
+            continue;
+        }
+        new_time = new_time.max(ready);
+        if in_setup {
+            shift = new_time - time;
+        } else if let Some(next_time) = next_channel_message[i] {
+            // Outside the setup, channel messages aren't delayed, so this
+            // SysEx has to make do with less time instead.
+            new_time = new_time.min(next_time + shift);
+        }
+        if new_time != time {
+            report.moved += 1;
+        }
+        let gap = if parse_reset(bytes).is_some() {
+            RESET_TIME_MS
+        } else {
+            PROCESSING_TIME_MS
+        };
+        ready = new_time + time_converter.ms_to_ticks(time, gap);
+        data.other_events[index].0 = new_time;
+    }
+    if first_note.is_some() {
+        report.lead_in = shift;
+    }
+    data.other_events.sort_by_key(|&(time, _)| time);
+    data.channel_messages.sort_by_key(|&(time, _)| time);
+
+    report
+}
+
+/// For each channel message in `data`, whether it's a parameter number select
+/// (see [crate::nrpn]) that some Data Entry after it depends on, ignoring the
+/// messages marked in `removed`. A select is only superseded by a later one for
+/// the same control on the same channel. `order` is the time order.
+fn parameter_number_selects_in_use(
+    data: &MidiData,
+    order: &[usize],
+    removed: &[bool],
+) -> Vec<bool> {
+    let mut in_use = vec![false; data.channel_messages.len()];
+    let mut last_selects = [[None; 4]; 16];
+    for &index in order {
+        let (_, ref message) = data.channel_messages[index];
+        let ChannelMessageKind::ControlChange { control, .. } = message.kind else {
+            continue;
+        };
+        if removed[index] {
+            continue;
+        }
+        let last_selects = &mut last_selects[message.channel as usize];
+        match control {
+            CC_NRPN_LSB..=CC_RPN_MSB => {
+                last_selects[(control - CC_NRPN_LSB) as usize] = Some(index)
+            }
+            CC_DATA_ENTRY_MSB | CC_DATA_ENTRY_LSB | CC_DATA_INCREMENT | CC_DATA_DECREMENT => {
+                for &select_index in last_selects.iter().flatten() {
+                    in_use[select_index] = true;
+                }
+            }
+            _ => (),
+        }
+    }
+    in_use
+}
+
+/// Replace each Data Entry in `data` for an RPN or NRPN that has an equivalent
+/// GS part parameter (see [crate::nrpn::parameter_number_to_gs_dt1]) with a DT1
+/// for `device_id`, assuming each part receives on its default channel. The
+/// parameter number selects that are no longer needed are removed too. Returns
+/// the number of Data Entries replaced.
+pub fn convert_nrpns_to_sysex(data: &mut MidiData, device_id: DeviceId) -> usize {
+    let mut order: Vec<usize> = (0..data.channel_messages.len()).collect();
+    order.sort_by_key(|&index| data.channel_messages[index].0);
+
+    let mut removed = vec![false; data.channel_messages.len()];
+    let in_use_before = parameter_number_selects_in_use(data, &order, &removed);
+
+    let mut converted = 0;
+    let mut numbers = [PARAMETER_NUMBER_NULL; 16];
+    // Whether the last Data Entry MSB on each channel was replaced.
+    let mut replaced = [false; 16];
+    for &index in &order {
+        let (time, ref message) = data.channel_messages[index];
+        let channel = message.channel;
+        let ChannelMessageKind::ControlChange { control, value } = message.kind else {
+            continue;
+        };
+        let number = &mut numbers[channel as usize];
+        if number.select(control, value) {
+            continue;
+        }
+        match control {
+            CC_DATA_ENTRY_MSB => {
+                let part_idx = gs_channel_default_part(channel);
+                let dt1 = parameter_number_to_gs_dt1(*number, value, part_idx);
+                replaced[channel as usize] = dt1.is_some();
+                if let Some((address, dt1_data)) = dt1 {
+                    let mut sysex = Vec::new();
+                    generate_dt1(gs_model(), device_id, &address, &[dt1_data], &mut sysex);
+                    data.other_events.push((time, sysex));
+                    removed[index] = true;
+                    converted += 1;
+                }
+            }
+            // GS ignores the LSB for all of these.
+            CC_DATA_ENTRY_LSB if replaced[channel as usize] => removed[index] = true,
+            // Reset All Controllers
+            121 => *number = PARAMETER_NUMBER_NULL,
+            _ => (),
+        }
+    }
+
+    let in_use_after = parameter_number_selects_in_use(data, &order, &removed);
+    for index in 0..removed.len() {
+        removed[index] |= in_use_before[index] && !in_use_after[index];
+    }
+    let mut removed = removed.into_iter();
+    data.channel_messages.retain(|_| !removed.next().unwrap());
+    data.other_events.sort_by_key(|&(time, _)| time);
+
+    converted
+}
+
+/// Replace each single-byte GS DT1 in `data` for a part parameter that has an
+/// equivalent RPN or NRPN (see [crate::nrpn::gs_dt1_to_parameter_number]) with
+/// control changes on the part's default channel. The device ID is ignored.
+/// Returns the number of DT1s replaced.
+pub fn convert_sysex_to_nrpns(data: &mut MidiData) -> usize {
+    let mut converted = 0;
+    let mut messages = Vec::new();
+    data.other_events.retain(|(time, bytes)| {
+        let Some((model_info, _, address, &[value])) = parse_dt1(bytes) else {
+            return true;
+        };
+        if model_info.model_id != gs_model().model_id {
+            return true;
+        }
+        let Some((part_idx, number, value)) = gs_dt1_to_parameter_number(address, value) else {
+            return true;
+        };
+        let channel = gs_part_default_channel(part_idx);
+        messages.extend(
+            parameter_number_messages(channel, number, value)
+                .into_iter()
+                .map(|message| (*time, message)),
+        );
+        converted += 1;
+        false
+    });
+    data.channel_messages.extend(messages);
+    data.channel_messages.sort_by_key(|&(time, _)| time);
+    converted
+}
+
+/// SC-7 system parameters (address `00h 00h xx`) and the equivalent GS patch
+/// common parameters (address `40h 01h yy`). The values are the same.
+const SC_7_GS_SYSTEM: &[(u8, u8)] = &[
+    (0x00, 0x31), // REVERB CHARACTER
+    (0x01, 0x33), // REVERB LEVEL
+    (0x02, 0x34), // REVERB (DELAY) TIME
+    (0x04, 0x35), // (REVERB) DELAY FEEDBACK
+    (0x05, 0x3A), // CHORUS LEVEL
+    (0x06, 0x3B), // CHORUS FEEDBACK
+    (0x07, 0x3C), // CHORUS DELAY
+    (0x08, 0x3D), // CHORUS RATE
+    (0x09, 0x3E), // CHORUS DEPTH
+];
+/// SC-7 part parameters (address `01h 0ph xx`) and the equivalent GS part
+/// parameters: whether they're in the controllers block and the address within
+/// the block (see [crate::state::gs_part_address]). The values are the same.
+const SC_7_GS_PART: &[(u8, bool, u8)] = &[
+    (0x00, false, 0x02), // RX. CHANNEL
+    (0x01, false, 0x0A), // RX. NRPN
+    (0x02, true, 0x03),  // MOD LFO(1) RATE CONTROL
+    (0x03, true, 0x04),  // MOD LFO(1) PITCH DEPTH
+    (0x04, true, 0x21),  // CAF TVF CUT OFF CONTROL
+    (0x05, true, 0x22),  // CAF AMPLITUDE CONTROL
+    (0x06, true, 0x23),  // CAF LFO(1) RATE CONTROL
+    (0x07, true, 0x24),  // CAF LFO(1) PITCH DEPTH
+];
+
+fn sc_7_to_gs_address(address: &[u8]) -> Option<[u8; 3]> {
+    match *address {
+        [0x00, 0x00, sc_7] => SC_7_GS_SYSTEM
+            .iter()
+            .find(|&&(sc_7_2, _)| sc_7_2 == sc_7)
+            .map(|&(_, gs)| [0x40, 0x01, gs]),
+        [0x01, part_idx @ 0x00..=0x0F, sc_7] => SC_7_GS_PART
+            .iter()
+            .find(|&&(sc_7_2, _, _)| sc_7_2 == sc_7)
+            .map(|&(_, controllers, gs)| gs_part_address(part_idx, controllers, gs)),
+        _ => None,
+    }
+}
+
+fn gs_to_sc_7_address(address: &[u8]) -> Option<[u8; 3]> {
+    match *address {
+        [0x40, 0x01, gs] => SC_7_GS_SYSTEM
+            .iter()
+            .find(|&&(_, gs2)| gs2 == gs)
+            .map(|&(sc_7, _)| [0x00, 0x00, sc_7]),
+        [0x40, block @ 0x10..=0x2F, gs] => SC_7_GS_PART
+            .iter()
+            .find(|&&(_, controllers, gs2)| controllers == (block >= 0x20) && gs2 == gs)
+            .map(|&(sc_7, _, _)| [0x01, block & 0x0F, sc_7]),
+        _ => None,
+    }
+}
+
+/// A parameter mentioned in a [TranslationReport].
+#[derive(Debug)]
+pub struct ReportedParameter {
+    pub time: AbsoluteTime,
+    pub model_info: &'static ModelInfo,
+    pub address: Vec<u8>,
+}
+impl Display for ReportedParameter {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}: {}: ", self.time, self.model_info.name)?;
+        match roland::look_up_parameter(self.model_info, &self.address) {
+            (Some((block_name, _)), Some(param)) => write!(f, "{} § {}", block_name, param.name),
+            _ => write!(f, "{}", format_bytes(&self.address)),
+        }
+    }
+}
+
+/// What [translate_gs_to_sc_7], [translate_sc_7_to_gs] or [convert_gs_to_xg]
+/// did.
+#[derive(Debug, Default)]
+pub struct TranslationReport {
+    /// Number of DT1s that were replaced.
+    pub translated: usize,
+    /// Parameters with no equivalent. The DT1s they are in are left alone.
+    pub untranslated: Vec<ReportedParameter>,
+    /// Parameters that were translated to the nearest equivalent, which isn't
+    /// quite the same, or that were only implied by a macro parameter and were
+    /// left out for lack of one.
+    pub approximated: Vec<ReportedParameter>,
+}
+impl Display for TranslationReport {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(f, "Translated {} DT1(s).", self.translated)?;
+        writeln!(
+            f,
+            "{} parameter(s) could not be translated:",
+            self.untranslated.len()
+        )?;
+        for untranslated in &self.untranslated {
+            writeln!(f, "- {}", untranslated)?;
+        }
+        if !self.approximated.is_empty() {
+            writeln!(
+                f,
+                "{} parameter(s) were translated to the nearest equivalent, or left out of a macro:",
+                self.approximated.len()
+            )?;
+            for approximated in &self.approximated {
+                writeln!(f, "- {}", approximated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Translate each DT1 in `data` for the model `from` to DT1s for the model
+/// `to`, using `translate_address`. A DT1 that sets a macro parameter (see
+/// [roland::MacroParameter]) is translated as the parameters that the macro
+/// sets, leaving out and reporting as approximated those with no equivalent.
+/// If anything else in a DT1 can't be translated, it's reported and the DT1 is
+/// left alone.
+fn translate_dt1s(
+    data: &mut MidiData,
+    from: &'static ModelInfo,
+    to: &'static ModelInfo,
+    device_id: Option<DeviceId>,
+    translate_address: fn(&[u8]) -> Option<[u8; 3]>,
+) -> TranslationReport {
+    let mut report = TranslationReport::default();
+    let mut other_events = Vec::with_capacity(data.other_events.len());
+    for (time, bytes) in std::mem::take(&mut data.other_events) {
+        let Some((model_info, from_device_id, address, dt1_data)) = parse_dt1(&bytes) else {
+            other_events.push((time, bytes));
+            continue;
+        };
+        if model_info.model_id != from.model_id {
+            other_events.push((time, bytes));
+            continue;
+        }
+
+        let mut translated: Vec<([u8; 3], u8)> = Vec::new();
+        let mut untranslated: Vec<ReportedParameter> = Vec::new();
+        let mut approximated: Vec<ReportedParameter> = Vec::new();
+        // The address range of the last untranslated parameter, so that a
+        // multi-byte parameter is only reported once.
+        let mut last_untranslated: Option<(Vec<u8>, Vec<u8>)> = None;
+        // How many of the writes to come are implied by a macro.
+        let mut implied_count = 0usize;
+        for (address, value) in dt1_writes(from, address, dt1_data) {
+            let implied = implied_count > 0;
+            implied_count = implied_count.saturating_sub(1);
+            if let Some(to_address) = translate_address(&address) {
+                translated.push((to_address, value));
+                continue;
+            }
+            if let Some(implied_values) = roland::look_up_macro(from, &address)
+                .and_then(|macro_param| macro_param.implied_values(value))
+            {
+                implied_count = implied_values.count();
+                continue;
+            }
+            // A macro's preset for a parameter with no equivalent can be left
+            // out without losing the rest of the macro.
+            if implied {
+                approximated.push(ReportedParameter {
+                    time,
+                    model_info: from,
+                    address,
+                });
+                continue;
+            }
+            if let Some((start, end)) = &last_untranslated {
+                if *start <= address && address < *end {
+                    continue;
+                }
+            }
+            let mut end = address.clone();
+            let size = match roland::look_up_parameter(from, &address) {
+                (_, Some(param)) => param.size,
+                _ => 1,
+            };
+            for _ in 0..size {
+                next_address(&mut end);
+            }
+            last_untranslated = Some((address.clone(), end));
+            untranslated.push(ReportedParameter {
+                time,
+                model_info: from,
+                address,
+            });
+        }
+        if !untranslated.is_empty() || translated.is_empty() {
+            report.untranslated.extend(untranslated);
+            other_events.push((time, bytes));
+            continue;
+        }
+        report.approximated.extend(approximated);
+
+        // Coalesce the translations where the addresses are contiguous.
+        let mut runs: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        for (address, value) in translated {
+            if let Some((run_address, run_data)) = runs.last_mut() {
+                let mut end = run_address.clone();
+                for _ in 0..run_data.len() {
+                    next_address(&mut end);
+                }
+                if end == address {
+                    run_data.push(value);
+                    continue;
+                }
+            }
+            runs.push((address.to_vec(), vec![value]));
+        }
+        for (address, run_data) in runs {
+            let mut sysex = Vec::new();
+            generate_dt1(
+                to,
+                device_id.unwrap_or(from_device_id),
+                &address,
+                &run_data,
+                &mut sysex,
+            );
+            other_events.push((time, sysex));
+        }
+        report.translated += 1;
+    }
+    data.other_events = other_events;
+    report
+}
+
+fn model_info_for(model_id: &[u8]) -> &'static ModelInfo {
+    MODELS
+        .iter()
+        .find(|model| model.model_id == model_id)
+        .unwrap()
+}
+
+/// Translate the GS DT1s in `data` to the SC-7's own parameters, where there is
+/// an equivalent (see [translate_dt1s]). The SC-7's device ID isn't
+/// configurable, so its default is used. Other SysExes, including the GS reset,
+/// are left alone, since the SC-7 accepts some GS messages.
+pub fn translate_gs_to_sc_7(data: &mut MidiData) -> TranslationReport {
+    let sc_7 = model_info_for(&[0x56]);
+    translate_dt1s(
+        data,
+        gs_model(),
+        sc_7,
+        Some(sc_7.default_device_id),
+        gs_to_sc_7_address,
+    )
+}
+
+/// Translate the SC-7 DT1s in `data` to GS parameters, where there is an
+/// equivalent (see [translate_dt1s]). The device ID is kept.
+pub fn translate_sc_7_to_gs(data: &mut MidiData) -> TranslationReport {
+    translate_dt1s(
+        data,
+        model_info_for(&[0x56]),
+        gs_model(),
+        None,
+        sc_7_to_gs_address,
+    )
+}
+
+#[cfg(test)]
+#[test]
+fn test_retarget_device_id() {
+    use crate::midi::Division;
+    use crate::state::GS_RESET;
+    use crate::sysex::roland::dt1;
+
+    let gm_on = vec![0xF0, 0x7E, 0x7F, 0x09, 0x01, 0xF7];
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        other_events: vec![(0, GS_RESET.to_vec()), (0, gm_on.clone())],
+    };
+
+    assert_eq!(
+        retarget_device_id(&mut data, 0x11),
+        Ok(RetargetReport {
+            changed: 1,
+            skipped: 0
+        })
+    );
+    assert_eq!(data.other_events[0].1[2], 0x11);
+    assert_eq!(data.other_events[1].1, gm_on);
+    // Already targeted, so nothing changes.
+    assert_eq!(
+        retarget_device_id(&mut data, 0x11),
+        Ok(RetargetReport::default())
+    );
+
+    // The SC-7's device ID is fixed.
+    let sc_7_sysex = dt1(model_info_for(&[0x56]), 0x10, &[0x00, 0x00, 0x00], &[0x04]);
+    data.other_events.push((0, sc_7_sysex.clone()));
+    assert_eq!(
+        retarget_device_id(&mut data, 0x12),
+        Ok(RetargetReport {
+            changed: 1,
+            skipped: 1
+        })
+    );
+    assert_eq!(data.other_events[2].1, sc_7_sysex);
+
+    assert!(retarget_device_id(&mut data, 0x80).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_repair_checksums() {
+    use crate::midi::Division;
+
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        other_events: vec![
+            // GS reset with a wrong checksum
+            (
+                0,
+                vec![
+                    0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x00, 0xF7,
+                ],
+            ),
+            // Not Roland
+            (
+                10,
+                vec![0xF0, 0x43, 0x10, 0x4C, 0x00, 0x00, 0x7E, 0x00, 0xF7],
+            ),
+            // Part 1 PART LEVEL = 90
+            (
+                20,
+                vec![
+                    0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x19, 0x5A, 0x3C, 0xF7,
+                ],
+            ),
+        ],
+    };
+
+    assert_eq!(
+        repair_checksums(&mut data),
+        &[ChecksumRepair {
+            index: 0,
+            time: 0,
+            wrong: 0x00,
+            correct: 0x41
+        }]
+    );
+    assert_eq!(data.other_events[0].1[9], 0x41);
+    assert!(repair_checksums(&mut data).is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_optimize_sysexes() {
+    use crate::midi::{ChannelMessage, Division};
+    use crate::state::GS_RESET;
+    use crate::sysex::roland::dt1;
+
+    // Part 1 CHORUS SEND LEVEL = 16, REVERB SEND LEVEL = 100 (with a bad
+    // checksum), PART LEVEL = 90 and then 80
+    let chorus_send = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x21, 0x10, 0x7E, 0xF7,
+    ];
+    let reverb_send = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x22, 0x64, 0x00, 0xF7,
+    ];
+    let part_level_90 = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x19, 0x5A, 0x3C, 0xF7,
+    ];
+    let part_level_80 = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x19, 0x50, 0x46, 0xF7,
+    ];
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            (
+                0,
+                ChannelMessage {
+                    channel: 0,
+                    kind: ChannelMessageKind::ProgramChange(4),
+                },
+            ),
+            (
+                0,
+                ChannelMessage {
+                    channel: 0,
+                    kind: ChannelMessageKind::NoteOn {
+                        key: 60,
+                        velocity: 100,
+                    },
+                },
+            ),
+        ],
+        other_events: vec![
+            (0, GS_RESET.to_vec()),
+            (0, chorus_send),
+            (0, reverb_send),
+            (0, part_level_90.clone()),
+            (0, part_level_80.clone()),
+        ],
+    };
+
+    let report = optimize_sysexes(&mut data);
+    assert_eq!(report.checksums_fixed, 1);
+    assert_eq!(report.dropped.len(), 1);
+    assert_eq!(report.dropped[0].1, part_level_90);
+    assert_eq!(report.merged, 1);
+    assert_eq!(report.moved, 2);
+    // 50ms after the reset, and 20ms after each DT1, rounded up to ticks.
+    assert_eq!(report.lead_in, 12 + 5 + 5);
+    assert_eq!(
+        data.other_events,
+        &[
+            (0, GS_RESET.to_vec()),
+            (
+                12,
+                vec![0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x11, 0x21, 0x10, 0x64, 0x1A, 0xF7]
+            ),
+            (17, part_level_80),
+        ]
+    );
+    assert!(data.channel_messages.iter().all(|&(time, _)| time == 22));
+    // The merged DT1 sets both parameters, which the linter is fine with.
+    assert!(lint_sysexes(&data).is_empty());
+
+    // Nothing is dropped that the synth would miss: PART LEVEL was changed by
+    // CC#7, the second REVERB SEND LEVEL is for another unit, and Part 2
+    // receives the program change on channel 1 while its Rx. CHANNEL is
+    // changed, even though that's overwritten later.
+    let gs = gs_model();
+    let mut gs_reset_11 = GS_RESET.to_vec();
+    gs_reset_11[2] = 0x11;
+    let message = |kind| ChannelMessage { channel: 0, kind };
+    let sysexes = vec![
+        (0, GS_RESET.to_vec()),
+        (20, gs_reset_11),
+        (40, dt1(gs, 0x10, &[0x40, 0x11, 0x19], &[100])),
+        (50, dt1(gs, 0x10, &[0x40, 0x11, 0x22], &[100])),
+        (60, dt1(gs, 0x11, &[0x40, 0x11, 0x22], &[100])),
+        (70, dt1(gs, 0x10, &[0x40, 0x12, 0x02], &[0])),
+        (90, dt1(gs, 0x10, &[0x40, 0x12, 0x02], &[1])),
+    ];
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            (
+                30,
+                message(ChannelMessageKind::ControlChange {
+                    control: 7,
+                    value: 50,
+                }),
+            ),
+            (80, message(ChannelMessageKind::ProgramChange(4))),
+            (
+                100,
+                message(ChannelMessageKind::NoteOn {
+                    key: 60,
+                    velocity: 100,
+                }),
+            ),
+        ],
+        other_events: sysexes.clone(),
+    };
+    // The linter only looks at what the SysExes write, so it misses that.
+    assert_eq!(
+        lint_sysexes(&data)[0].problem,
+        Problem::Overwritten { time: 90 }
+    );
+    let report = optimize_sysexes(&mut data);
+    assert!(report.dropped.is_empty());
+    assert_eq!(data.other_events.len(), sysexes.len());
+
+    // After the first note, a DT1 isn't moved past a note it came before,
+    // even if that leaves the synth less time to process it.
+    let note_on = || {
+        message(ChannelMessageKind::NoteOn {
+            key: 60,
+            velocity: 100,
+        })
+    };
+    let part_level = dt1(gs, 0x10, &[0x40, 0x11, 0x19], &[100]);
+    let reverb_send = dt1(gs, 0x10, &[0x40, 0x11, 0x22], &[100]);
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![(0, note_on()), (100, note_on())],
+        other_events: vec![(100, part_level.clone()), (100, reverb_send.clone())],
+    };
+    let report = optimize_sysexes(&mut data);
+    assert_eq!(report.moved, 0);
+    assert_eq!(data.other_events, &[(100, part_level), (100, reverb_send)]);
+}
+
+#[cfg(test)]
+#[test]
+fn test_convert_nrpns() {
+    use crate::midi::{cc, Division};
+
+    // Part 2 TVF cutoff freq. = 50h, and a drum NRPN that has no equivalent
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            (0, cc(1, 99, 0x01)),
+            (0, cc(1, 98, 0x20)),
+            (0, cc(1, 6, 0x50)),
+            (0, cc(1, 38, 0x00)),
+            (10, cc(9, 99, 0x1A)),
+            (10, cc(9, 98, 36)),
+            (10, cc(9, 6, 0x60)),
+        ],
+        other_events: Vec::new(),
+    };
+    let cutoff = vec![
+        0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x12, 0x32, 0x50, 0x2C, 0xF7,
+    ];
+
+    assert_eq!(convert_nrpns_to_sysex(&mut data, 0x10), 1);
+    assert_eq!(data.other_events, &[(0, cutoff)]);
+    assert_eq!(data.channel_messages.len(), 3);
+    assert!(data
+        .channel_messages
+        .iter()
+        .all(|(_, message)| message.channel == 9));
+
+    assert_eq!(convert_sysex_to_nrpns(&mut data), 1);
+    assert!(data.other_events.is_empty());
+    assert_eq!(data.channel_messages.len(), 8);
+    let controls: Vec<_> = data
+        .channel_messages
+        .iter()
+        .filter(|(_, message)| message.channel == 1)
+        .map(|(_, message)| match message.kind {
+            ChannelMessageKind::ControlChange { control, value } => (control, value),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        controls,
+        &[(99, 0x01), (98, 0x20), (6, 0x50), (101, 0x7F), (100, 0x7F)]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_translate_sc_7() {
+    use crate::midi::Division;
+    use crate::sysex::roland::dt1;
+
+    let gs = gs_model();
+    let sc_7 = model_info_for(&[0x56]);
+    // Every equivalent has the same kind of name.
+    for (sc_7_address, gs_address) in SC_7_GS_SYSTEM
+        .iter()
+        .map(|&(sc_7, _)| [0x00, 0x00, sc_7])
+        .chain(SC_7_GS_PART.iter().map(|&(sc_7, _, _)| [0x01, 0x03, sc_7]))
+        .map(|address| (address, sc_7_to_gs_address(&address).unwrap()))
+    {
+        assert_eq!(gs_to_sc_7_address(&gs_address), Some(sc_7_address));
+        let (_, Some(sc_7_param)) = roland::look_up_parameter(sc_7, &sc_7_address) else {
+            panic!();
+        };
+        let (_, Some(gs_param)) = roland::look_up_parameter(gs, &gs_address) else {
+            panic!();
+        };
+        let last_word = |name: &str| name.rsplit(' ').next().unwrap().to_owned();
+        assert_eq!(last_word(sc_7_param.name), last_word(gs_param.name));
+    }
+
+    // REVERB MACRO = Plate, Part 1 TONE NUMBER = 00h 04h, Part 1 Rx. NRPN = OFF,
+    // CHORUS MACRO = Chorus 3
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        other_events: vec![
+            (0, dt1(gs, 0x10, &[0x40, 0x01, 0x30], &[0x05])),
+            (10, dt1(gs, 0x10, &[0x40, 0x11, 0x00], &[0x00, 0x04])),
+            (20, dt1(gs, 0x10, &[0x40, 0x11, 0x0A], &[0x00])),
+            (30, dt1(gs, 0x10, &[0x40, 0x01, 0x38], &[0x02])),
+        ],
+    };
+
+    let report = translate_gs_to_sc_7(&mut data);
+    assert_eq!(report.translated, 3);
+    let to_strings = |parameters: &[ReportedParameter]| -> Vec<String> {
+        parameters
+            .iter()
+            .map(|parameter| parameter.to_string())
+            .collect()
+    };
+    assert_eq!(
+        to_strings(&report.untranslated),
+        &["10: Roland GS: Patch parameters, Part 1 § TONE NUMBER"]
+    );
+    // The macros' presets are translated, except for the parameters the SC-7
+    // doesn't have.
+    assert_eq!(
+        to_strings(&report.approximated),
+        &[
+            "0: Roland GS: Patch parameters, Patch common § REVERB PRE-LPF",
+            "30: Roland GS: Patch parameters, Patch common § CHORUS PRE-LPF",
+            "30: Roland GS: Patch parameters, Patch common § CHORUS SEND LEVEL TO REVERB",
+        ]
+    );
+    assert_eq!(
+        data.other_events,
+        &[
+            (0, dt1(sc_7, 0x10, &[0x00, 0x00, 0x00], &[5, 64, 88])),
+            (0, dt1(sc_7, 0x10, &[0x00, 0x00, 0x04], &[0])),
+            (10, dt1(gs, 0x10, &[0x40, 0x11, 0x00], &[0x00, 0x04])),
+            (20, dt1(sc_7, 0x10, &[0x01, 0x01, 0x01], &[0x00])),
+            (
+                30,
+                dt1(sc_7, 0x10, &[0x00, 0x00, 0x05], &[64, 8, 80, 3, 19])
+            ),
+        ]
+    );
+
+    let report = translate_sc_7_to_gs(&mut data);
+    assert_eq!(report.translated, 4);
+    assert!(report.untranslated.is_empty());
+    assert_eq!(
+        data.other_events[4],
+        (20, dt1(gs, 0x10, &[0x40, 0x11, 0x0A], &[0x00]))
+    );
+}
@@ -0,0 +1,320 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Copying and swapping the settings of GS parts, see [copy_parts].
+
+use super::PROCESSING_TIME_MS;
+use crate::midi::{AbsoluteTime, ChannelMessage, ChannelMessageKind, MidiData, TimeConverter};
+use crate::nrpn::gs_channel_default_part;
+use crate::state::{
+    generate_setup, gs_model, gs_part_address, SynthState, GS_RX_CHANNEL, GS_TONE_NUMBER,
+};
+use crate::sysex::roland::DeviceId;
+
+/// The position of a GS part's block (see [gs_part_address]) for the part
+/// number (1 to 16) as shown on the synth.
+fn gs_part_idx(part: u8) -> u8 {
+    // Each part receives on the channel with its number by default.
+    gs_channel_default_part(part - 1)
+}
+
+fn tone_number(state: &SynthState, part_idx: u8) -> Option<Vec<u8>> {
+    let address = gs_part_address(part_idx, false, GS_TONE_NUMBER);
+    state.get(gs_model(), &address, 2)
+}
+
+/// Copy the settings of GS parts onto other parts, as they are at `time` in
+/// `data` (see [SynthState::replay]), for the synth with the device ID
+/// `device_id`. `moves` are pairs of part numbers (1 to 16): the part to copy
+/// from and the part to copy to. All the parts are read before any is
+/// written, so `[(2, 4), (4, 2)]` swaps Parts 2 and 4.
+///
+/// Everything in the part's blocks is copied, i.e. the tone, levels, sends,
+/// TONE MODIFY, Rx. switches and controller assignments, except the
+/// Rx. CHANNEL, so each part still receives on its own channel. Settings whose
+/// data isn't known are left alone.
+///
+/// The messages are inserted from `time` on: DT1s for the settings that
+/// change, coalesced and spaced out as by [generate_setup], and then a bank
+/// select and program change for the tone if the part would receive them and
+/// no other part would, or else a DT1 for the TONE NUMBER. The bank select is
+/// put back afterwards, so later program changes on that channel still get
+/// the bank they would have. Returns the number of messages inserted.
+pub fn copy_parts(
+    data: &mut MidiData,
+    device_id: DeviceId,
+    time: AbsoluteTime,
+    moves: &[(u8, u8)],
+) -> Result<usize, String> {
+    for &(from, to) in moves {
+        for part in [from, to] {
+            if !(1..=16).contains(&part) {
+                return Err(format!("There is no Part {}", part));
+            }
+        }
+    }
+    for (i, &(_, to)) in moves.iter().enumerate() {
+        if moves[..i].iter().any(|&(_, to2)| to2 == to) {
+            return Err(format!("Part {} can't be copied to twice", to));
+        }
+    }
+
+    let gs = gs_model();
+    let state = SynthState::replay(data, device_id, time);
+    let mut target = state.clone();
+    for &(from, to) in moves {
+        let (from_idx, to_idx) = (gs_part_idx(from), gs_part_idx(to));
+        for controllers in [false, true] {
+            let prefix = &gs_part_address(from_idx, controllers, 0)[..2];
+            let (_, _, pam) = gs
+                .address_block_map
+                .iter()
+                .find(|&&(prefix2, _, _)| prefix2 == prefix)
+                .unwrap();
+            for &(suffix, ref param) in pam.iter() {
+                if !controllers && suffix == [GS_RX_CHANNEL] {
+                    continue;
+                }
+                let from_address = gs_part_address(from_idx, controllers, suffix[0]);
+                let to_address = gs_part_address(to_idx, controllers, suffix[0]);
+                if let Some(data) = state.get(gs, &from_address, param.size as usize) {
+                    target.set(gs, &to_address, &data);
+                }
+            }
+        }
+    }
+
+    // Try to send each new tone as a bank select and program change instead,
+    // checking with the state model that they would land on the right part
+    // and no other.
+    let mut sysex_target = target.clone();
+    let mut tone_messages = Vec::new();
+    for &(_, to) in moves {
+        let to_idx = gs_part_idx(to);
+        let (Some(tone), Some(old_tone)) =
+            (tone_number(&target, to_idx), tone_number(&state, to_idx))
+        else {
+            continue;
+        };
+        let rx_channel = gs_part_address(to_idx, false, GS_RX_CHANNEL);
+        let Some(channel) = target
+            .get(gs, &rx_channel, 1)
+            .map(|data| data[0])
+            .filter(|&channel| channel < 16)
+        else {
+            continue;
+        };
+        if tone == old_tone {
+            continue;
+        }
+
+        let mut candidate = sysex_target.clone();
+        candidate.set(
+            gs,
+            &gs_part_address(to_idx, false, GS_TONE_NUMBER),
+            &old_tone,
+        );
+        let old_bank_select = state.bank_select(channel);
+        let mut messages = vec![
+            ChannelMessageKind::ControlChange {
+                control: 0,
+                value: tone[0],
+            },
+            ChannelMessageKind::ProgramChange(tone[1]),
+        ];
+        if old_bank_select != tone[0] {
+            messages.push(ChannelMessageKind::ControlChange {
+                control: 0,
+                value: old_bank_select,
+            });
+        }
+        let messages: Vec<_> = messages
+            .into_iter()
+            .map(|kind| ChannelMessage { channel, kind })
+            .collect();
+        let mut trial = candidate.clone();
+        for message in tone_messages.iter().chain(&messages) {
+            trial.apply_channel_message(message);
+        }
+        if (0..16).all(|part_idx| tone_number(&trial, part_idx) == tone_number(&target, part_idx)) {
+            sysex_target = candidate;
+            tone_messages.extend(messages);
+        }
+    }
+
+    // The setup is timed for its own collection, so its spacing has to be
+    // converted to the file's ticks, at the tempo at `time`. The program
+    // changes come after the last DT1 has been processed, since they depend on
+    // the Rx. switches it may set.
+    let setup = generate_setup(&sysex_target, Some(&state));
+    let setup_converter = TimeConverter::new(&setup);
+    let converter = TimeConverter::new(data);
+    let at_ms = |ms| time + converter.ms_to_ticks(time, ms);
+    let tone_time = match setup.other_events.last() {
+        Some(&(last_time, _)) => at_ms(setup_converter.to_ms(last_time) + PROCESSING_TIME_MS),
+        None => time,
+    };
+    let count = setup.other_events.len() + tone_messages.len();
+    data.other_events.extend(
+        setup
+            .other_events
+            .into_iter()
+            .map(|(setup_time, sysex)| (at_ms(setup_converter.to_ms(setup_time)), sysex)),
+    );
+    data.other_events.sort_by_key(|&(time, _)| time);
+    data.channel_messages.extend(
+        tone_messages
+            .into_iter()
+            .map(|message| (tone_time, message)),
+    );
+    data.channel_messages.sort_by_key(|&(time, _)| time);
+    Ok(count)
+}
+
+#[cfg(test)]
+#[test]
+fn test_copy_parts() {
+    use crate::midi::Division;
+    use crate::sysex::roland::dt1;
+
+    let gs = gs_model();
+    let program = |channel, bank, program| {
+        [
+            ChannelMessageKind::ControlChange {
+                control: 0,
+                value: bank,
+            },
+            ChannelMessageKind::ProgramChange(program),
+        ]
+        .map(|kind| (0, ChannelMessage { channel, kind }))
+    };
+
+    // Part 2 has Piano 2 (bank 8, program 1), a PART LEVEL of 90 and a
+    // MOD PITCH CONTROL of +12 semitones. Part 4 has Organ 1 (program 16)
+    // and a PART PANPOT of L32.
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(480),
+        channel_messages: [program(1, 8, 1), program(3, 0, 16)]
+            .into_iter()
+            .flatten()
+            .collect(),
+        other_events: vec![
+            (0, dt1(gs, 0x10, &gs_part_address(2, false, 0x19), &[90])),
+            (0, dt1(gs, 0x10, &gs_part_address(2, true, 0x00), &[0x4C])),
+            (0, dt1(gs, 0x10, &gs_part_address(4, false, 0x1C), &[32])),
+        ],
+    };
+
+    // At 120bpm and 480 ticks per quarter note, the DT1s are 48 ticks (50ms)
+    // apart, and the program changes come 20ms after the last one.
+    assert_eq!(copy_parts(&mut data, 0x10, 480, &[(2, 4), (4, 2)]), Ok(10));
+    let inserted: Vec<_> = data.other_events[3..]
+        .iter()
+        .map(|(time, sysex)| (*time, sysex.clone()))
+        .collect();
+    assert_eq!(
+        inserted,
+        &[
+            (
+                480,
+                dt1(
+                    gs,
+                    0x10,
+                    &gs_part_address(2, false, 0x19),
+                    &[100, 0x40, 0x40, 32]
+                )
+            ),
+            (
+                528,
+                dt1(
+                    gs,
+                    0x10,
+                    &gs_part_address(4, false, 0x19),
+                    &[90, 0x40, 0x40, 0x40]
+                )
+            ),
+            (576, dt1(gs, 0x10, &gs_part_address(2, true, 0x00), &[0x40])),
+            (624, dt1(gs, 0x10, &gs_part_address(4, true, 0x00), &[0x4C])),
+        ]
+    );
+    // Each bank select is put back after the program change.
+    let bank_select = |channel, value| ChannelMessage {
+        channel,
+        kind: ChannelMessageKind::ControlChange { control: 0, value },
+    };
+    let [_, (_, organ)] = program(1, 0, 16);
+    assert_eq!(
+        data.channel_messages[4..],
+        program(3, 8, 1)
+            .into_iter()
+            .map(|(_, message)| message)
+            .chain([
+                bank_select(3, 0),
+                bank_select(1, 0),
+                organ,
+                bank_select(1, 8)
+            ])
+            .map(|message| (644, message))
+            .collect::<Vec<_>>()
+    );
+
+    // The swap took: the state after is as it was before, with the parts
+    // the other way round.
+    let before = SynthState::replay(&data, 0x10, 0);
+    let after = SynthState::replay(&data, 0x10, 644);
+    for (a, b) in [(2, 4), (4, 2)] {
+        for controllers in [false, true] {
+            for suffix in 0x00..0x4C {
+                if !controllers && suffix == GS_RX_CHANNEL {
+                    continue;
+                }
+                assert_eq!(
+                    after.get(gs, &gs_part_address(a, controllers, suffix), 1),
+                    before.get(gs, &gs_part_address(b, controllers, suffix), 1)
+                );
+            }
+        }
+    }
+
+    // A later program change on Part 4's channel without a bank select still
+    // gets bank 0, as it would have without the copy.
+    data.channel_messages.push((
+        2000,
+        ChannelMessage {
+            channel: 3,
+            kind: ChannelMessageKind::ProgramChange(20),
+        },
+    ));
+    let later = SynthState::replay(&data, 0x10, 2000);
+    assert_eq!(tone_number(&later, 4), Some(vec![0x00, 20]));
+    data.channel_messages.pop();
+
+    // If Part 5, with another tone, also receives on channel 4, a program
+    // change would change its tone too, so a DT1 is used.
+    data.channel_messages.extend(program(4, 0, 20));
+    data.other_events.push((
+        700,
+        dt1(gs, 0x10, &gs_part_address(5, false, GS_RX_CHANNEL), &[3]),
+    ));
+    let count = data.other_events.len();
+    let channel_messages_count = data.channel_messages.len();
+    assert_eq!(copy_parts(&mut data, 0x10, 960, &[(1, 4)]), Ok(2));
+    assert_eq!(data.channel_messages.len(), channel_messages_count);
+    // The TONE NUMBER and PART LEVEL are in one DT1, which re-sends the
+    // settings in between.
+    assert_eq!(&data.other_events[count].1[5..8], &[0x40, 0x14, 0x00]);
+    let after = SynthState::replay(&data, 0x10, 960);
+    assert_eq!(tone_number(&after, 4), Some(vec![0x00, 0x00]));
+    assert_eq!(tone_number(&after, 5), Some(vec![0x00, 20]));
+    assert_eq!(
+        after.get(gs, &gs_part_address(4, false, 0x19), 1),
+        Some(vec![100])
+    );
+
+    assert!(copy_parts(&mut data, 0x10, 0, &[(0, 4)]).is_err());
+    assert!(copy_parts(&mut data, 0x10, 0, &[(1, 4), (2, 4)]).is_err());
+}
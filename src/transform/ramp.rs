@@ -0,0 +1,228 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Sweeping a parameter from one value to another with a series of DT1s, see
+//! [insert_ramp].
+
+use super::{parse_dt1, PROCESSING_TIME_MS};
+use crate::midi::{AbsoluteTime, MidiData, TimeConverter};
+use crate::sysex::roland::{generate_dt1, look_up_parameter, DeviceId, ModelInfo};
+
+/// MIDI runs at 31250 baud, and each byte takes 10 bits.
+const MIDI_BYTES_PER_MS: f64 = 3.125;
+
+/// The most of the MIDI bandwidth a ramp may use, so that notes and other
+/// messages can still get through on time.
+const MAX_BANDWIDTH_SHARE: f64 = 0.25;
+
+/// How the value moves between the start and end of a [Ramp].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// Equal steps in equal times.
+    Linear,
+    /// Equal ratios in equal times, which sounds more even for parameters
+    /// like levels and rates. Slow at the low end and fast at the high end.
+    Exponential,
+}
+
+/// A sweep of a single-byte parameter from one data value to another, see
+/// [insert_ramp].
+#[derive(Debug)]
+pub struct Ramp {
+    pub model_info: &'static ModelInfo,
+    pub device_id: DeviceId,
+    /// Full address of the parameter.
+    pub address: Vec<u8>,
+    pub from: u8,
+    pub to: u8,
+    /// Time of the first DT1, which has the data `from`.
+    pub start: AbsoluteTime,
+    /// Time of the last DT1, which has the data `to`.
+    pub end: AbsoluteTime,
+    pub curve: Curve,
+    /// Maximum number of DT1s per second.
+    pub rate: f64,
+}
+impl Ramp {
+    /// Make a ramp between the values in two DT1s for the same single-byte
+    /// parameter, e.g. ones compiled from text (see [crate::sysex::text]).
+    pub fn from_dt1s(
+        from_sysex: &[u8],
+        to_sysex: &[u8],
+        start: AbsoluteTime,
+        end: AbsoluteTime,
+        curve: Curve,
+        rate: f64,
+    ) -> Result<Ramp, String> {
+        let (Some(from_dt1), Some(to_dt1)) = (parse_dt1(from_sysex), parse_dt1(to_sysex)) else {
+            return Err("Both ends of a ramp must be DT1s for a known model".into());
+        };
+        let (
+            (model_info, device_id, address, &[from]),
+            (to_model_info, to_device_id, to_address, &[to]),
+        ) = (from_dt1, to_dt1)
+        else {
+            return Err("Only single-byte parameters can be ramped".into());
+        };
+        if (to_model_info.model_id, to_device_id, to_address)
+            != (model_info.model_id, device_id, address)
+        {
+            return Err("Both ends of a ramp must be for the same parameter".into());
+        }
+        let (_, Some(param)) = look_up_parameter(model_info, address) else {
+            return Err("Only known parameters can be ramped".into());
+        };
+        if param.size != 1 {
+            return Err("Only single-byte parameters can be ramped".into());
+        }
+        if end <= start {
+            return Err("A ramp must end after it starts".into());
+        }
+        if rate.is_nan() || rate <= 0.0 {
+            return Err("The rate must be more than 0 DT1s per second".into());
+        }
+        Ok(Ramp {
+            model_info,
+            device_id,
+            address: address.to_vec(),
+            from,
+            to,
+            start,
+            end,
+            curve,
+            rate,
+        })
+    }
+
+    /// The data at `progress` (0 to 1) through the ramp.
+    fn value_at(&self, progress: f64) -> u8 {
+        let (from, to) = (f64::from(self.from), f64::from(self.to));
+        let value = match self.curve {
+            Curve::Linear => from + (to - from) * progress,
+            // Offset by one so that 0 can be an end.
+            Curve::Exponential => (from + 1.0) * ((to + 1.0) / (from + 1.0)).powf(progress) - 1.0,
+        };
+        value.round() as u8
+    }
+}
+
+/// Insert DT1s into `data` that take the parameter of `ramp` from its start
+/// value to its end value, evenly spaced in real time between its start and
+/// end times (following the tempo changes). DT1s are sent no more often than
+/// the ramp's rate allows, than the synth can process them, or than would use
+/// too much of the MIDI bandwidth, and a DT1 that wouldn't change the value is
+/// left out. Returns the number of DT1s inserted.
+pub fn insert_ramp(data: &mut MidiData, ramp: &Ramp) -> usize {
+    let dt1 = |value| {
+        let mut sysex = Vec::new();
+        generate_dt1(
+            ramp.model_info,
+            ramp.device_id,
+            &ramp.address,
+            &[value],
+            &mut sysex,
+        );
+        sysex
+    };
+
+    let converter = TimeConverter::new(data);
+    let start_ms = converter.to_ms(ramp.start);
+    let total_ms = converter.to_ms(ramp.end) - start_ms;
+    let min_interval_ms = (1000.0 / ramp.rate)
+        .max(PROCESSING_TIME_MS)
+        .max(dt1(ramp.from).len() as f64 / MIDI_BYTES_PER_MS / MAX_BANDWIDTH_SHARE);
+    let steps = ((total_ms / min_interval_ms).floor() as u32).max(1);
+
+    let mut sysexes = Vec::new();
+    let mut time = ramp.start;
+    let mut last_value = None;
+    for step in 0..=steps {
+        if step != 0 {
+            let target_ms = start_ms + total_ms * f64::from(step) / f64::from(steps);
+            let ms = target_ms - converter.to_ms(time);
+            time = (time + converter.ms_to_ticks(time, ms)).min(ramp.end);
+        }
+        let value = ramp.value_at(f64::from(step) / f64::from(steps));
+        if last_value != Some(value) {
+            sysexes.push((time, dt1(value)));
+            last_value = Some(value);
+        }
+    }
+
+    let count = sysexes.len();
+    data.other_events.extend(sysexes);
+    data.other_events.sort_by_key(|&(time, _)| time);
+    count
+}
+
+#[cfg(test)]
+#[test]
+fn test_insert_ramp() {
+    use crate::midi::Division;
+    use crate::state::gs_model;
+    use crate::sysex::roland::dt1;
+
+    let gs = gs_model();
+    // Part 5 CHORUS SEND LEVEL
+    let address = [0x40, 0x15, 0x21];
+    let chorus_send = |value| dt1(gs, 0x10, &address, &[value]);
+
+    // A bar of 4/4 at 120bpm is 2 seconds, which is 100 DT1s at most.
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(480),
+        channel_messages: Vec::new(),
+        other_events: Vec::new(),
+    };
+    let ramp = Ramp::from_dt1s(
+        &chorus_send(0),
+        &chorus_send(127),
+        0,
+        1920,
+        Curve::Linear,
+        1000.0,
+    )
+    .unwrap();
+    assert_eq!(insert_ramp(&mut data, &ramp), 101);
+    assert_eq!(data.other_events[0], (0, chorus_send(0)));
+    assert_eq!(data.other_events[1].0, 20);
+    assert_eq!(data.other_events[50], (960, chorus_send(64)));
+    assert_eq!(data.other_events[100], (1920, chorus_send(127)));
+
+    // At 4 DT1s per second there are fewer steps, and an exponential curve
+    // has smaller ones at the start.
+    data.other_events.clear();
+    let ramp = Ramp {
+        curve: Curve::Exponential,
+        rate: 4.0,
+        ..ramp
+    };
+    assert_eq!(insert_ramp(&mut data, &ramp), 9);
+    let values: Vec<_> = data
+        .other_events
+        .iter()
+        .map(|(time, sysex)| (*time, sysex[8]))
+        .collect();
+    assert_eq!(
+        values,
+        &[
+            (0, 0),
+            (240, 1),
+            (480, 2),
+            (720, 5),
+            (960, 10),
+            (1200, 20),
+            (1440, 37),
+            (1680, 69),
+            (1920, 127)
+        ]
+    );
+
+    // A ramp can't be between two parameters.
+    let mut other = Vec::new();
+    generate_dt1(gs, 0x10, &[0x40, 0x15, 0x22], &[0], &mut other);
+    assert!(Ramp::from_dt1s(&other, &chorus_send(127), 0, 1920, Curve::Linear, 20.0).is_err());
+}
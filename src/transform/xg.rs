@@ -0,0 +1,723 @@
+/*
+ * Part of SoundPalette by hikari_no_yume.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Converting Roland GS setups to Yamaha XG, see [convert_gs_to_xg].
+
+use super::{parse_dt1, ReportedParameter, TranslationReport};
+use crate::midi::{cc, AbsoluteTime, ChannelMessage, ChannelMessageKind, MidiData};
+use crate::nrpn::gs_part_default_channel;
+use crate::state::{
+    dt1_writes, gs_model, gs_part_address, parse_reset, Reset, SynthState, GS_RX_CHANNEL,
+    GS_TONE_NUMBER,
+};
+use crate::sysex::roland::{look_up_macro, look_up_parameter, next_address, DeviceId};
+use crate::sysex::yamaha::{
+    generate_xg_parameter_change, DeviceNumber, XG_CHORUS_TYPES, XG_REVERB_TYPES,
+};
+use std::collections::BTreeMap;
+
+/// An XG parameter change to send: the address and the data.
+type XgWrite = ([u8; 3], Vec<u8>);
+
+/// How the value of a GS parameter becomes the value of an XG one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Conversion {
+    /// The same value has the same meaning.
+    Same,
+    /// The same value, but the XG parameter has a different scale or a
+    /// slightly different meaning.
+    Similar,
+    /// REVERB CHARACTER to REVERB TYPE.
+    ReverbType,
+    /// CHORUS MACRO to CHORUS TYPE.
+    ChorusType,
+    /// Rx. CHANNEL to Rcv CHANNEL, which has a different value for "off".
+    RxChannel,
+    /// TONE NUMBER to BANK SELECT MSB, BANK SELECT LSB and PROGRAM NUMBER.
+    Tone,
+    /// USE FOR RHYTHM PART to PART MODE. The tone is converted again too,
+    /// because XG drum kits are in their own bank.
+    PartMode,
+}
+
+/// A GS parameter (address of its first byte, and size) and its XG
+/// equivalent (address of its first byte).
+#[derive(Copy, Clone, Debug)]
+struct Equivalent {
+    gs_address: [u8; 3],
+    gs_size: u8,
+    xg_address: [u8; 3],
+    conversion: Conversion,
+}
+
+/// GS patch common parameters and their XG effect 1 equivalents. REVERB MACRO
+/// isn't here: REVERB CHARACTER, which it sets, is what picks the type.
+const GS_XG_PATCH_COMMON: &[(u8, u8, Conversion)] = &[
+    (0x31, 0x00, Conversion::ReverbType),
+    (0x33, 0x0C, Conversion::Similar), // REVERB LEVEL to REVERB RETURN
+    (0x38, 0x20, Conversion::ChorusType),
+    (0x3A, 0x2C, Conversion::Similar), // CHORUS LEVEL to CHORUS RETURN
+    (0x3F, 0x2E, Conversion::Similar), // CHORUS SEND LEVEL TO REVERB
+];
+
+/// GS part parameters and their XG multi part equivalents: GS address within
+/// the part's block, size, XG address within the part's block.
+const GS_XG_PART: &[(u8, u8, u8, Conversion)] = &[
+    (0x00, 2, 0x01, Conversion::Tone),
+    (0x02, 1, 0x04, Conversion::RxChannel),
+    (0x13, 1, 0x05, Conversion::Same), // MONO/POLY MODE
+    (0x15, 1, 0x07, Conversion::PartMode),
+    (0x16, 1, 0x08, Conversion::Same), // PITCH KEY SHIFT to NOTE SHIFT
+    (0x17, 2, 0x09, Conversion::Same), // PITCH OFFSET FINE to DETUNE
+    (0x19, 1, 0x0B, Conversion::Same), // PART LEVEL to VOLUME
+    (0x1A, 1, 0x0C, Conversion::Same), // VELOCITY SENSE DEPTH
+    (0x1B, 1, 0x0D, Conversion::Same), // VELOCITY SENSE OFFSET
+    (0x1C, 1, 0x0E, Conversion::Same), // PART PANPOT to PAN
+    (0x1D, 1, 0x0F, Conversion::Same), // KEY RANGE LOW to NOTE LIMIT LOW
+    (0x1E, 1, 0x10, Conversion::Same), // KEY RANGE HIGH to NOTE LIMIT HIGH
+    (0x1F, 1, 0x59, Conversion::Same), // CC1 to AC1 CONTROLLER NUMBER
+    (0x20, 1, 0x60, Conversion::Same), // CC2 to AC2 CONTROLLER NUMBER
+    (0x21, 1, 0x12, Conversion::Same), // CHORUS SEND LEVEL
+    (0x22, 1, 0x13, Conversion::Same), // REVERB SEND LEVEL
+    (0x23, 1, 0x40, Conversion::Same), // Rx. BANK SELECT
+    // The TONE MODIFY parameters are ±50 on GS, ±64 on XG.
+    (0x30, 1, 0x15, Conversion::Similar), // Vibrato rate
+    (0x31, 1, 0x16, Conversion::Similar), // Vibrato depth
+    (0x32, 1, 0x18, Conversion::Similar), // TVF cutoff freq.
+    (0x33, 1, 0x19, Conversion::Similar), // TVF resonance
+    (0x34, 1, 0x1A, Conversion::Similar), // TVF & TVA Env. attack
+    (0x35, 1, 0x1B, Conversion::Similar), // TVF & TVA Env. decay
+    (0x36, 1, 0x1C, Conversion::Similar), // TVF & TVA Env. release
+    (0x37, 1, 0x17, Conversion::Similar), // Vibrato delay
+];
+
+/// The first address of each controller's group in a GS part's controllers
+/// block (MOD, BEND, CAf, PAf, CC1, CC2), and of the XG equivalent in the
+/// multi part block (MW, BEND, CAT, PAT, AC1, AC2).
+const GS_XG_CONTROLLERS: &[(u8, u8)] = &[
+    (0x00, 0x1D),
+    (0x10, 0x23),
+    (0x20, 0x4D),
+    (0x30, 0x53),
+    (0x40, 0x5A),
+    (0x50, 0x61),
+];
+
+fn xg_equivalent(gs_address: &[u8]) -> Option<Equivalent> {
+    let equivalent = |gs_address, gs_size, xg_address, conversion| {
+        Some(Equivalent {
+            gs_address,
+            gs_size,
+            xg_address,
+            conversion,
+        })
+    };
+    match *gs_address {
+        // MASTER TUNE has the same 4-nibble format on both.
+        [0x40, 0x00, 0x00..=0x03] => {
+            equivalent([0x40, 0x00, 0x00], 4, [0x00, 0x00, 0x00], Conversion::Same)
+        }
+        [0x40, 0x00, 0x04] => {
+            equivalent([0x40, 0x00, 0x04], 1, [0x00, 0x00, 0x04], Conversion::Same)
+        }
+        // MASTER KEY-SHIFT to TRANSPOSE
+        [0x40, 0x00, 0x05] => {
+            equivalent([0x40, 0x00, 0x05], 1, [0x00, 0x00, 0x06], Conversion::Same)
+        }
+        [0x40, 0x01, lsb] => GS_XG_PATCH_COMMON
+            .iter()
+            .find(|&&(gs_lsb, _, _)| gs_lsb == lsb)
+            .and_then(|&(gs_lsb, xg_lsb, conversion)| {
+                equivalent([0x40, 0x01, gs_lsb], 1, [0x02, 0x01, xg_lsb], conversion)
+            }),
+        [0x40, block @ 0x10..=0x1F, lsb] => {
+            let xg_part = gs_part_default_channel(block & 0x0F);
+            if let 0x03..=0x12 = lsb {
+                // The Rx. switches from PITCH BEND to SOFT are in the same
+                // order on both.
+                return equivalent(
+                    [0x40, block, lsb],
+                    1,
+                    [0x08, xg_part, lsb - 0x03 + 0x30],
+                    Conversion::Same,
+                );
+            }
+            if let 0x40..=0x4B = lsb {
+                // SCALE TUNING is a single 12-byte parameter on GS, but twelve
+                // separate ones on XG.
+                return equivalent(
+                    [0x40, block, lsb],
+                    1,
+                    [0x08, xg_part, lsb + 1],
+                    Conversion::Same,
+                );
+            }
+            GS_XG_PART
+                .iter()
+                .find(|&&(gs_lsb, gs_size, _, _)| (gs_lsb..gs_lsb + gs_size).contains(&lsb))
+                .and_then(|&(gs_lsb, gs_size, xg_lsb, conversion)| {
+                    equivalent(
+                        [0x40, block, gs_lsb],
+                        gs_size,
+                        [0x08, xg_part, xg_lsb],
+                        conversion,
+                    )
+                })
+        }
+        [0x40, block @ 0x20..=0x2F, lsb] => {
+            let xg_part = gs_part_default_channel(block & 0x0F);
+            let &(gs_group, xg_group) = GS_XG_CONTROLLERS
+                .iter()
+                .find(|&&(gs_group, _)| (gs_group..gs_group + 0x10).contains(&lsb))?;
+            // XG has no LFO1 RATE CONTROL and no LFO2.
+            let (xg_offset, conversion) = match lsb - gs_group {
+                offset @ 0x00..=0x02 => (offset, Conversion::Same),
+                offset @ 0x04..=0x06 => (offset - 1, Conversion::Similar),
+                _ => return None,
+            };
+            equivalent(
+                [0x40, block, lsb],
+                1,
+                [0x08, xg_part, xg_group + xg_offset],
+                conversion,
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The XG parameter writes equivalent to a GS parameter's current value in
+/// `state`, and whether they are exactly equivalent. [None] if there is no
+/// equivalent for that value.
+fn convert(equivalent: &Equivalent, state: &SynthState) -> Option<(Vec<XgWrite>, bool)> {
+    let gs = gs_model();
+    let value = state.get(gs, &equivalent.gs_address, equivalent.gs_size.into())?;
+    let xg_address = equivalent.xg_address;
+    let write = |data: &[u8]| vec![(xg_address, data.to_vec())];
+    match equivalent.conversion {
+        Conversion::Same => Some((write(&value), true)),
+        Conversion::Similar => Some((write(&value), false)),
+        Conversion::ReverbType => {
+            let (name, exact) = match value[0] {
+                0x00 => ("ROOM1", true),
+                0x01 => ("ROOM2", true),
+                0x02 => ("ROOM3", true),
+                0x03 => ("HALL1", true),
+                0x04 => ("HALL2", true),
+                0x05 => ("PLATE", true),
+                // XG only has delays as variation effects.
+                _ => return None,
+            };
+            Some((write(&xg_effect_type(XG_REVERB_TYPES, name)), exact))
+        }
+        Conversion::ChorusType => {
+            let (name, exact) = match value[0] {
+                0x00 => ("CHORUS1", true),
+                0x01 => ("CHORUS2", true),
+                0x02 => ("CHORUS3", true),
+                0x03 => ("CHORUS4", true),
+                0x04 => ("CELESTE1", false), // Feedback Chorus
+                0x05 => ("FLANGER1", true),
+                _ => return None,
+            };
+            Some((write(&xg_effect_type(XG_CHORUS_TYPES, name)), exact))
+        }
+        Conversion::RxChannel => Some((
+            write(&[match value[0] {
+                0x10 => 0x7F,
+                channel => channel,
+            }]),
+            true,
+        )),
+        Conversion::Tone => convert_tone(xg_address, equivalent.gs_address[1], state),
+        Conversion::PartMode => {
+            let (tone_writes, exact) =
+                convert_tone([0x08, xg_address[1], 0x01], equivalent.gs_address[1], state)?;
+            let mut writes = write(&value);
+            writes.extend(tone_writes);
+            Some((writes, exact))
+        }
+    }
+}
+
+fn xg_effect_type(types: &[([u8; 2], &str)], name: &str) -> [u8; 2] {
+    types
+        .iter()
+        .find(|&&(_, name2)| name2 == name)
+        .map(|&(effect_type, _)| effect_type)
+        .unwrap()
+}
+
+/// Convert the TONE NUMBER of the GS part with the block `gs_block` to XG's
+/// BANK SELECT MSB, BANK SELECT LSB and PROGRAM NUMBER, starting at
+/// `xg_address`.
+///
+/// GS variation tones are in the same banks as XG's variation voices, but
+/// they aren't the same sounds, so those become the capital tone. Most GS
+/// drum sets have an XG drum kit with the same program number.
+fn convert_tone(
+    xg_address: [u8; 3],
+    gs_block: u8,
+    state: &SynthState,
+) -> Option<(Vec<XgWrite>, bool)> {
+    let gs = gs_model();
+    let &[bank, program] = &state.get(gs, &[0x40, gs_block, 0x00], 2)?[..] else {
+        unreachable!();
+    };
+    let rhythm = state.get(gs, &[0x40, gs_block, 0x15], 1)?[0] != 0x00;
+    let (bank_msb, program, exact) = if rhythm {
+        xg_drum_kit(program)
+    } else {
+        (0x00, program, bank == 0x00)
+    };
+    let mut writes = Vec::new();
+    for (offset, value) in [bank_msb, 0x00, program].into_iter().enumerate() {
+        let mut address = xg_address;
+        address[2] += offset as u8;
+        writes.push((address, vec![value]));
+    }
+    Some((writes, exact))
+}
+
+/// The XG drum kit for the GS drum set with the program number `program`: the
+/// bank select MSB, the program number, and whether it's the same kit.
+fn xg_drum_kit(program: u8) -> (u8, u8, bool) {
+    match program {
+        // STANDARD, ROOM, POWER (Rock), ELECTRONIC (Electro), TR-808
+        // (Analog), JAZZ, BRUSH, ORCHESTRA (Classic)
+        0 | 8 | 16 | 24 | 25 | 32 | 40 | 48 => (0x7F, program, true),
+        // SFX is in XG's SFX kit bank.
+        56 => (0x7E, 0, true),
+        _ => (0x7F, 0, false),
+    }
+}
+
+/// Convert the bank selects and program changes in `data`'s channel messages
+/// the way [convert_tone] converts TONE NUMBER, reporting them as approximated
+/// the same way too. The GS parts that receive on each channel are found by
+/// replaying the SysExes, which must not have been converted yet.
+///
+/// On a channel with a rhythm part, Bank Select MSB (CC#0) becomes the XG drum
+/// kit bank, and a program change picks the nearest XG drum kit, which might
+/// mean a different program number and bank. Otherwise, Bank Select MSB becomes
+/// 0, the capital tone. Bank Select LSB (CC#32) picks a variation bank on XG,
+/// but doesn't on GS, so it becomes 0.
+fn convert_bank_selects(data: &mut MidiData, report: &mut TranslationReport) {
+    let gs = gs_model();
+    let mut sysexes: Vec<_> = data.other_events.iter().collect();
+    sysexes.sort_by_key(|&&(time, _)| time);
+    let mut sysexes = sysexes.into_iter().peekable();
+    let mut order: Vec<usize> = (0..data.channel_messages.len()).collect();
+    order.sort_by_key(|&idx| data.channel_messages[idx].0);
+
+    let mut state = SynthState::new(gs.default_device_id);
+    // The index of the Bank Select MSB on each channel that the next program
+    // change will use, if it hasn't been used yet.
+    let mut bank_selects: [Option<usize>; 16] = [None; 16];
+    // The last XG Bank Select MSB value on each channel.
+    let mut xg_bank_msbs: [Option<u8>; 16] = [None; 16];
+    // Bank selects to insert before a program change, and where.
+    let mut insertions: Vec<(usize, (AbsoluteTime, ChannelMessage))> = Vec::new();
+    for idx in order {
+        let time = data.channel_messages[idx].0;
+        // SysExes come first at the same time, like in SynthState::replay.
+        while let Some((_, sysex)) = sysexes.next_if(|&&(sysex_time, _)| sysex_time <= time) {
+            state.apply_sysex(sysex);
+        }
+
+        let channel = data.channel_messages[idx].1.channel;
+        let parts: Vec<u8> = (0..16)
+            .filter(|&part_idx| {
+                let address = gs_part_address(part_idx, false, GS_RX_CHANNEL);
+                state.get(gs, &address, 1) == Some(vec![channel])
+            })
+            .collect();
+        // USE FOR RHYTHM PART
+        let rhythm = parts.iter().any(|&part_idx| {
+            let address = gs_part_address(part_idx, false, 0x15);
+            state
+                .get(gs, &address, 1)
+                .is_some_and(|data| data[0] != 0x00)
+        });
+        let mut approximate = || {
+            for &part_idx in &parts {
+                let address = gs_part_address(part_idx, false, GS_TONE_NUMBER).to_vec();
+                if !report
+                    .approximated
+                    .iter()
+                    .any(|other| other.time == time && other.address == address)
+                {
+                    report.approximated.push(ReportedParameter {
+                        time,
+                        model_info: gs,
+                        address,
+                    });
+                }
+            }
+        };
+
+        state.apply_channel_message(&data.channel_messages[idx].1);
+        match data.channel_messages[idx].1.kind {
+            ChannelMessageKind::ControlChange {
+                control: 0,
+                ref mut value,
+            } => {
+                bank_selects[channel as usize] = Some(idx);
+                if rhythm {
+                    *value = 0x7F;
+                } else if *value != 0x00 {
+                    *value = 0x00;
+                    approximate();
+                }
+                xg_bank_msbs[channel as usize] = Some(*value);
+            }
+            ChannelMessageKind::ControlChange {
+                control: 32,
+                ref mut value,
+            } if *value != 0x00 => {
+                *value = 0x00;
+                approximate();
+            }
+            ChannelMessageKind::ProgramChange(_) if !rhythm => {
+                bank_selects[channel as usize] = None;
+            }
+            ChannelMessageKind::ProgramChange(ref mut program) => {
+                let (bank_msb, xg_program, exact) = xg_drum_kit(*program);
+                *program = xg_program;
+                match bank_selects[channel as usize].take() {
+                    Some(bank_select_idx) => {
+                        data.channel_messages[bank_select_idx].1 = cc(channel, 0, bank_msb)
+                    }
+                    // Part 10 defaults to the drum kit bank on XG.
+                    None if xg_bank_msbs[channel as usize].unwrap_or(0x7F) == bank_msb => (),
+                    None => insertions.push((idx, (time, cc(channel, 0, bank_msb)))),
+                }
+                xg_bank_msbs[channel as usize] = Some(bank_msb);
+                if !exact {
+                    approximate();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for (idx, message) in insertions.into_iter().rev() {
+        data.channel_messages.insert(idx, message);
+    }
+}
+
+/// XG's device number is the same as the low nibble of a Sound Canvas
+/// device ID: the default is `0h`, and unit 2 is `1h`.
+fn xg_device_number(device_id: DeviceId) -> DeviceNumber {
+    match device_id {
+        0x10..=0x1F => device_id & 0x0F,
+        _ => 0x00,
+    }
+}
+
+/// Convert the Roland GS setup in `data` to its Yamaha XG equivalent:
+///
+/// - A GS reset becomes an XG System On.
+/// - REVERB CHARACTER and CHORUS MACRO become the nearest XG reverb and chorus
+///   types.
+/// - Part parameters become the XG multi part parameters for the part with the
+///   same default channel.
+/// - A TONE NUMBER becomes an XG bank select and program number (see
+///   [convert_tone]), and so do bank selects and program changes in the
+///   channel messages (see [convert_bank_selects]).
+///
+/// Each GS DT1 is replaced by XG parameter changes, except when some of its
+/// parameters have no equivalent: then those are reported, and the DT1 is
+/// kept after the XG parameter changes. Parameters set by a macro parameter
+/// (see [crate::sysex::roland::MacroParameter]) that have no equivalent are
+/// left to the XG effect type.
+pub fn convert_gs_to_xg(data: &mut MidiData) -> TranslationReport {
+    let gs = gs_model();
+    let mut report = TranslationReport::default();
+    convert_bank_selects(data, &mut report);
+
+    let mut states: BTreeMap<DeviceId, SynthState> = BTreeMap::new();
+    let mut other_events = Vec::with_capacity(data.other_events.len());
+    for (time, bytes) in std::mem::take(&mut data.other_events) {
+        if let Some((reset, device_id)) = parse_reset(&bytes) {
+            for (&device_id2, state) in states.iter_mut() {
+                if device_id == device_id2 || device_id == 0x7F || device_id2 == 0x7F {
+                    state.reset();
+                }
+            }
+            if reset == Reset::GsReset {
+                let mut sysex = Vec::new();
+                generate_xg_parameter_change(
+                    xg_device_number(device_id),
+                    [0x00, 0x00, 0x7E],
+                    &[0x00],
+                    &mut sysex,
+                );
+                other_events.push((time, sysex));
+                report.translated += 1;
+            } else {
+                other_events.push((time, bytes));
+            }
+            continue;
+        }
+
+        let Some((model_info, device_id, address, dt1_data)) = parse_dt1(&bytes) else {
+            other_events.push((time, bytes));
+            continue;
+        };
+        if model_info.model_id != gs.model_id {
+            other_events.push((time, bytes));
+            continue;
+        }
+
+        let state = states
+            .entry(device_id)
+            .or_insert_with(|| SynthState::new(device_id));
+        state.apply_dt1(gs, address, dt1_data);
+
+        let mut explicit_address = address.to_vec();
+        let mut explicit_end = address.to_vec();
+        for _ in 0..dt1_data.len() {
+            next_address(&mut explicit_end);
+        }
+
+        let mut xg_writes: Vec<XgWrite> = Vec::new();
+        let mut untranslated: Vec<ReportedParameter> = Vec::new();
+        let mut approximated: Vec<ReportedParameter> = Vec::new();
+        // The address range of the last untranslated parameter, so that a
+        // multi-byte parameter is only reported once.
+        let mut last_untranslated: Option<(Vec<u8>, Vec<u8>)> = None;
+        for (address, value) in dt1_writes(gs, address, dt1_data) {
+            // A write outside the DT1's own range was set by the last macro
+            // parameter, which problems are reported as.
+            let implied = !(explicit_address <= address && address < explicit_end);
+            if !implied {
+                explicit_address = address.clone();
+            }
+            let report_address = || ReportedParameter {
+                time,
+                model_info: gs,
+                address: explicit_address.clone(),
+            };
+
+            let Some(equivalent) = xg_equivalent(&address) else {
+                let is_macro = look_up_macro(gs, &address)
+                    .and_then(|macro_param| macro_param.implied_values(value))
+                    .is_some();
+                if implied || is_macro {
+                    continue;
+                }
+                if let Some((start, end)) = &last_untranslated {
+                    if *start <= address && address < *end {
+                        continue;
+                    }
+                }
+                let size = match look_up_parameter(gs, &address) {
+                    (_, Some(param)) => param.size,
+                    _ => 1,
+                };
+                let mut end = address.clone();
+                for _ in 0..size {
+                    next_address(&mut end);
+                }
+                last_untranslated = Some((address, end));
+                untranslated.push(report_address());
+                continue;
+            };
+
+            match convert(&equivalent, state) {
+                Some((writes, exact)) => {
+                    for write in writes {
+                        if !xg_writes.contains(&write) {
+                            xg_writes.push(write);
+                        }
+                    }
+                    let address = equivalent.gs_address.to_vec();
+                    if !exact
+                        && !implied
+                        && !approximated.iter().any(|other| other.address == address)
+                    {
+                        approximated.push(ReportedParameter {
+                            time,
+                            model_info: gs,
+                            address,
+                        });
+                    }
+                }
+                None => {
+                    if !untranslated
+                        .iter()
+                        .any(|other| other.address == explicit_address)
+                    {
+                        untranslated.push(report_address());
+                    }
+                }
+            }
+        }
+
+        let device_number = xg_device_number(device_id);
+        for (xg_address, xg_data) in xg_writes {
+            let mut sysex = Vec::new();
+            generate_xg_parameter_change(device_number, xg_address, &xg_data, &mut sysex);
+            other_events.push((time, sysex));
+        }
+        if untranslated.is_empty() {
+            report.translated += 1;
+        } else {
+            other_events.push((time, bytes));
+        }
+        report.untranslated.extend(untranslated);
+        report.approximated.extend(approximated);
+    }
+    data.other_events = other_events;
+    report.approximated.sort_by_key(|reported| reported.time);
+    report
+}
+
+#[cfg(test)]
+#[test]
+fn test_convert_gs_to_xg() {
+    use crate::midi::Division;
+    use crate::sysex::roland::dt1;
+    use crate::sysex::yamaha::look_up_xg_parameter;
+
+    let gs = gs_model();
+
+    // The equivalents are for parameters that exist on both.
+    for block in [0x00, 0x01, 0x10, 0x20] {
+        for lsb in 0x00..=0x7F {
+            let Some(equivalent) = xg_equivalent(&[0x40, block, lsb]) else {
+                continue;
+            };
+            let mut gs_address = equivalent.gs_address;
+            if block != 0x00 && (0x40..=0x4B).contains(&lsb) {
+                gs_address[2] = 0x40; // SCALE TUNING
+            }
+            assert!(look_up_parameter(gs, &gs_address).1.is_some());
+            assert!(look_up_xg_parameter(equivalent.xg_address).is_some());
+        }
+    }
+
+    let xg = |address: [u8; 3], xg_data: &[u8]| {
+        let mut sysex = Vec::new();
+        generate_xg_parameter_change(0x00, address, xg_data, &mut sysex);
+        sysex
+    };
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        other_events: vec![
+            (0, dt1(gs, 0x10, &[0x40, 0x00, 0x7F], &[0x00])),
+            // REVERB MACRO = Plate
+            (10, dt1(gs, 0x10, &[0x40, 0x01, 0x30], &[0x05])),
+            // CHORUS MACRO = Short Delay
+            (20, dt1(gs, 0x10, &[0x40, 0x01, 0x38], &[0x06])),
+            // Part 1 TONE NUMBER = 08h 04h (a variation), PART LEVEL = 90
+            (30, dt1(gs, 0x10, &[0x40, 0x11, 0x00], &[0x08, 0x04])),
+            (40, dt1(gs, 0x10, &[0x40, 0x11, 0x19], &[90])),
+            // Part 10 TONE NUMBER = 00h 19h (TR-808)
+            (50, dt1(gs, 0x10, &[0x40, 0x10, 0x00], &[0x00, 0x19])),
+            // Part 1 Rx. CHANNEL = OFF, Rx. PITCH BEND = OFF, MASTER PAN = 64
+            (60, dt1(gs, 0x10, &[0x40, 0x11, 0x02], &[0x10, 0x00])),
+            (70, dt1(gs, 0x10, &[0x40, 0x00, 0x06], &[0x40])),
+        ],
+    };
+
+    let report = convert_gs_to_xg(&mut data);
+    assert_eq!(report.translated, 6);
+    let untranslated: Vec<_> = report
+        .untranslated
+        .iter()
+        .map(|reported| reported.to_string())
+        .collect();
+    assert_eq!(
+        untranslated,
+        &[
+            "20: Roland GS: Patch parameters, Patch common § CHORUS MACRO",
+            "70: Roland GS: System parameters § MASTER PAN",
+        ]
+    );
+    let approximated: Vec<_> = report
+        .approximated
+        .iter()
+        .map(|reported| reported.to_string())
+        .collect();
+    assert_eq!(
+        approximated,
+        &["30: Roland GS: Patch parameters, Part 1 § TONE NUMBER"]
+    );
+
+    assert_eq!(
+        data.other_events,
+        &[
+            (0, xg([0x00, 0x00, 0x7E], &[0x00])),
+            (10, xg([0x02, 0x01, 0x00], &[0x04, 0x00])),
+            (10, xg([0x02, 0x01, 0x0C], &[0x40])),
+            (20, xg([0x02, 0x01, 0x2C], &[0x40])),
+            (20, xg([0x02, 0x01, 0x2E], &[0x00])),
+            (20, dt1(gs, 0x10, &[0x40, 0x01, 0x38], &[0x06])),
+            (30, xg([0x08, 0x00, 0x01], &[0x00])),
+            (30, xg([0x08, 0x00, 0x02], &[0x00])),
+            (30, xg([0x08, 0x00, 0x03], &[0x04])),
+            (40, xg([0x08, 0x00, 0x0B], &[90])),
+            (50, xg([0x08, 0x09, 0x01], &[0x7F])),
+            (50, xg([0x08, 0x09, 0x02], &[0x00])),
+            (50, xg([0x08, 0x09, 0x03], &[0x19])),
+            (60, xg([0x08, 0x00, 0x04], &[0x7F])),
+            (60, xg([0x08, 0x00, 0x30], &[0x00])),
+            (70, dt1(gs, 0x10, &[0x40, 0x00, 0x06], &[0x40])),
+        ]
+    );
+
+    // Bank selects in the channel messages are converted too.
+    let program_change = |channel, program| ChannelMessage {
+        channel,
+        kind: ChannelMessageKind::ProgramChange(program),
+    };
+    let mut data = MidiData {
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: vec![
+            // Part 1: a variation tone and an SC-88 map select.
+            (0, cc(0, 0, 0x08)),
+            (0, cc(0, 32, 0x01)),
+            (0, program_change(0, 0x04)),
+            // Part 10: SFX, then TR-808 without a bank select, then an unknown
+            // drum set.
+            (10, cc(9, 0, 0x00)),
+            (10, program_change(9, 56)),
+            (20, program_change(9, 25)),
+            (30, program_change(9, 1)),
+        ],
+        other_events: Vec::new(),
+    };
+    let report = convert_gs_to_xg(&mut data);
+    let approximated: Vec<_> = report
+        .approximated
+        .iter()
+        .map(|reported| reported.to_string())
+        .collect();
+    assert_eq!(
+        approximated,
+        &[
+            "0: Roland GS: Patch parameters, Part 1 § TONE NUMBER",
+            "30: Roland GS: Patch parameters, Part 10 § TONE NUMBER",
+        ]
+    );
+    assert_eq!(
+        data.channel_messages,
+        &[
+            (0, cc(0, 0, 0x00)),
+            (0, cc(0, 32, 0x00)),
+            (0, program_change(0, 0x04)),
+            (10, cc(9, 0, 0x7E)),
+            (10, program_change(9, 0)),
+            (20, cc(9, 0, 0x7F)),
+            (20, program_change(9, 25)),
+            (30, program_change(9, 0)),
+        ]
+    );
+}
@@ -7,9 +7,25 @@
  */
 //! User interface things, especially those shared between the web app and CLI.
 
-use crate::midi::{format_bytes, MidiData};
-use crate::sysex::parse_sysex;
+use crate::compat::{check_compatibility, Device};
+use crate::guess::guess_target;
+use crate::lint::lint_sysexes;
+use crate::midi::{
+    describe_note, format_bytes, AbsoluteTime, BarBeatConverter, Division, MidiData,
+    OctaveConvention,
+};
+use crate::nrpn::{decode_data_entries, gs_channel_default_part, parameter_number_to_gs_dt1};
+use crate::state::{gs_model, parameter_timeline, SynthState, GS_DRUM_PLAY_NOTE_NUMBER};
+use crate::sysex::roland::{fix_checksum, look_up_parameter, DeviceId, Parameter};
+use crate::sysex::text::{
+    compile_randomizer, compile_text, compile_unit_assignment, describe_canonically,
+};
+use crate::sysex::{parse_sysex, ParseFailure};
+use crate::transform::{
+    copy_parts, insert_ramp, repair_checksums, retarget_device_id, Curve, Ramp, TranslationReport,
+};
 use std::fmt::{Arguments, Debug, Result as FmtResult};
+use std::ops::RangeInclusive;
 
 // Utilities
 
@@ -128,6 +144,14 @@ pub trait Menu<T: Debug> {
         false
     }
 
+    /// Returns [true] if an item only leads to a variation of what's reachable
+    /// through other items in the menu (e.g. the same SysExes for another
+    /// device ID), so that [print_menu] can skip over its contents.
+    fn item_redundant(&self, item_idx: usize) -> bool {
+        let _ = item_idx;
+        false
+    }
+
     /// Select a menu item by its index in the list (counting from 0). See
     /// return type for more detail. Calling this method must not, by itself,
     /// alter any state or perform any action.
@@ -168,6 +192,10 @@ where
                 eprintln!(" (disabled)");
                 continue;
             }
+            if menu.item_redundant(i) {
+                eprintln!(" (…)");
+                continue;
+            }
             match menu.item_descend(i) {
                 MenuItemResult::Submenu(menu) => {
                     eprintln!();
@@ -311,6 +339,39 @@ fn test_menu_stack() {
     assert_eq!(vec, &[0xF0, 0x7E, 0x7F, 0x09, 0x01, 0xF7]);
 }
 
+/// Create an empty [MidiData] to be used as a collection of SysExes, see
+/// [add_sysex_to_collection].
+pub fn new_sysex_collection() -> MidiData {
+    MidiData {
+        // Something divisible by 10 is desirable, see add_sysex_to_collection.
+        division: Division::TicksPerQuarterNote(120),
+        channel_messages: Vec::new(),
+        other_events: Vec::new(),
+    }
+}
+
+/// Append a SysEx to a collection created by [new_sysex_collection], spacing
+/// it out from the previous one so the device has time to process it.
+pub fn add_sysex_to_collection(midi_data: &mut MidiData, sysex_bytes: Vec<u8>) {
+    // SC-55mkII and SC-7 manuals both say a GM or GS reset takes about 50ms to
+    // complete. Therefore, let's put 50ms between all SysEx messages.
+    // TODO: Use shorter delay (40ms or 20ms as appropriate) when no reset is
+    //       in the list.
+    // Assumption: All existing events have been added by this function, so they
+    //             are all in order, and there is no tempo or time signature
+    //             meta event to change from the default of 120bpm, 4/4.
+    let Division::TicksPerQuarterNote(ticks_per_quarter_note) = midi_data.division else {
+        panic!();
+    };
+    let ticks_per_quarter_note: AbsoluteTime = ticks_per_quarter_note.into();
+    let new_event_time = if let Some(&(last_event_time, _)) = midi_data.other_events.last() {
+        last_event_time + ((ticks_per_quarter_note * 120) / 60).div_ceil(1000 / 50)
+    } else {
+        0
+    };
+    midi_data.other_events.push((new_event_time, sysex_bytes));
+}
+
 // UI entry-points
 
 pub fn list_other_events(
@@ -348,17 +409,355 @@ pub fn list_other_events(
             }
             Err(err) => {
                 if with_time_and_kind {
-                    table_stream.td(format_args!("{:?}", err));
+                    table_stream.td(format_args!(
+                        "{}",
+                        if bytes.first() == Some(&0xF7) {
+                            "SysEx (continuation)"
+                        } else {
+                            "SysEx (invalid)"
+                        }
+                    ));
                 }
-                table_stream.td(format_args!("—"));
+                table_stream.td(format_args!("{}", err));
+            }
+        }
+        table_stream.end_tr();
+    }
+}
+
+/// Output a table of the parameters of a synth with the device ID `device_id`
+/// that differ from their defaults, after replaying `data` up to and including
+/// the time `until` (see [SynthState::replay]), followed by the drum instrument
+/// parameters that are known (see [SynthState::drum_instruments]).
+pub fn list_synth_state(
+    table_stream: &mut impl TableStream,
+    data: &MidiData,
+    device_id: DeviceId,
+    until: AbsoluteTime,
+) {
+    fn describe(param: &Parameter, data: Option<&[u8]>) -> String {
+        let Some(data) = data else {
+            return "(unknown)".into();
+        };
+        let mut description = format_bytes(data).to_string();
+        param.describe(data, &mut description, false).unwrap();
+        description
+    }
+
+    table_stream.th(format_args!("Model"));
+    table_stream.th(format_args!("Parameter"));
+    table_stream.th(format_args!("Value"));
+    table_stream.th(format_args!("Default"));
+    table_stream.end_tr();
+
+    let state = SynthState::replay(data, device_id, until);
+    for param_state in state.changed_parameters() {
+        table_stream.td(format_args!("{}", param_state.model_info.name));
+        table_stream.td(format_args!(
+            "{} § {}",
+            param_state.block_name, param_state.param.name
+        ));
+        table_stream.td(format_args!(
+            "{}",
+            describe(param_state.param, param_state.data.as_deref())
+        ));
+        table_stream.td(format_args!(
+            "{}",
+            describe(param_state.param, param_state.default.as_deref())
+        ));
+        table_stream.end_tr();
+    }
+
+    let gs = gs_model();
+    for drum in state.drum_instruments() {
+        table_stream.td(format_args!("{}", gs.name));
+        table_stream.td(format_args!(
+            "Drum setup parameters, MAP{} § {}, {}",
+            drum.map + 1,
+            describe_note(drum.key, OctaveConvention::MiddleC4),
+            drum.name
+        ));
+        if drum.suffix == GS_DRUM_PLAY_NOTE_NUMBER {
+            table_stream.td(format_args!(
+                "{} = {}",
+                format_bytes(&[drum.data]),
+                describe_note(drum.data, OctaveConvention::MiddleC4)
+            ));
+        } else {
+            table_stream.td(format_args!(
+                "{} = {}",
+                format_bytes(&[drum.data]),
+                drum.data
+            ));
+        }
+        table_stream.td(format_args!("(depends on the drum set)"));
+        table_stream.end_tr();
+    }
+}
+
+/// Output a table of each parameter that changes while replaying `data` for a
+/// synth with the device ID `device_id`, with its initial value and each time
+/// and value it changes to (see [parameter_timeline]). Times are given as
+/// bar:beat:tick if possible.
+pub fn list_parameter_timeline(
+    table_stream: &mut impl TableStream,
+    data: &MidiData,
+    device_id: DeviceId,
+) {
+    fn describe(param: &Parameter, data: Option<&[u8]>) -> String {
+        let Some(data) = data else {
+            return "(unknown)".into();
+        };
+        let mut description = String::new();
+        param.describe(data, &mut description, false).unwrap();
+        match description.strip_prefix(" = ") {
+            Some(value) => value.to_string(),
+            None => format!("{}{}", format_bytes(data), description),
+        }
+    }
+
+    table_stream.th(format_args!("Model"));
+    table_stream.th(format_args!("Parameter"));
+    table_stream.th(format_args!("Changes"));
+    table_stream.end_tr();
+
+    let converter = BarBeatConverter::new(data);
+    for history in parameter_timeline(data, device_id) {
+        table_stream.td(format_args!("{}", history.model_info.name));
+        table_stream.td(format_args!(
+            "{} § {}",
+            history.block_name, history.param.name
+        ));
+        let mut changes = describe(history.param, history.initial.as_deref());
+        for (i, (time, data)) in history.changes.iter().enumerate() {
+            if i != 0 {
+                changes.push(',');
+            }
+            let value = describe(history.param, data.as_deref());
+            match converter.to_bar_beat_tick(*time) {
+                Some(bar_beat_tick) => changes += &format!(" → {} at {}", value, bar_beat_tick),
+                None => changes += &format!(" → {} at {}", value, time),
             }
         }
+        table_stream.td(format_args!("{}", changes));
+        table_stream.end_tr();
+    }
+}
+
+pub fn repair_and_list_checksums(table_stream: &mut impl TableStream, data: &mut MidiData) {
+    table_stream.th(format_args!("Time"));
+    table_stream.th(format_args!("Event (raw, repaired)"));
+    table_stream.th(format_args!("Checksum"));
+    table_stream.end_tr();
+
+    for repair in repair_checksums(data) {
+        table_stream.td(format_args!("{}", repair.time));
+        table_stream.td(format_args!(
+            "{}",
+            format_bytes(&data.other_events[repair.index].1)
+        ));
+        table_stream.td(format_args!(
+            "{} => {}",
+            format_bytes(&[repair.wrong]),
+            format_bytes(&[repair.correct])
+        ));
         table_stream.end_tr();
     }
 }
 
+pub fn list_untranslated(table_stream: &mut impl TableStream, report: &TranslationReport) {
+    table_stream.th(format_args!("Time"));
+    table_stream.th(format_args!("Parameter"));
+    table_stream.th(format_args!("Problem"));
+    table_stream.end_tr();
+
+    for (reported, problem) in report
+        .untranslated
+        .iter()
+        .map(|reported| (reported, "No equivalent"))
+        .chain(
+            report
+                .approximated
+                .iter()
+                .map(|reported| (reported, "Nearest equivalent used")),
+        )
+    {
+        table_stream.td(format_args!("{}", reported.time));
+        match look_up_parameter(reported.model_info, &reported.address) {
+            (Some((block_name, _)), Some(param)) => table_stream.td(format_args!(
+                "{} {} § {}",
+                reported.model_info.name, block_name, param.name
+            )),
+            _ => table_stream.td(format_args!(
+                "{} {}",
+                reported.model_info.name,
+                format_bytes(&reported.address)
+            )),
+        }
+        table_stream.td(format_args!("{}", problem));
+        table_stream.end_tr();
+    }
+}
+
+pub fn list_data_entries(table_stream: &mut impl TableStream, data: &MidiData) {
+    table_stream.th(format_args!("Time"));
+    table_stream.th(format_args!("Channel"));
+    table_stream.th(format_args!("Parameter"));
+    table_stream.th(format_args!("Value"));
+    table_stream.th(format_args!("Equivalent GS parameter (default channel)"));
+    table_stream.end_tr();
+
+    for entry in decode_data_entries(data) {
+        table_stream.td(format_args!("{}", entry.time));
+        table_stream.td(format_args!("{}", entry.channel + 1));
+        table_stream.td(format_args!("{}", entry.number));
+        match entry.lsb {
+            Some(lsb) => table_stream.td(format_args!("{} (LSB {})", entry.msb, lsb)),
+            None => table_stream.td(format_args!("{}", entry.msb)),
+        }
+        let part_idx = gs_channel_default_part(entry.channel);
+        match parameter_number_to_gs_dt1(entry.number, entry.msb, part_idx) {
+            Some((address, dt1_data)) => {
+                let (Some((block_name, _)), Some(param)) = look_up_parameter(gs_model(), &address)
+                else {
+                    unreachable!();
+                };
+                let mut description = format!("{} § {} = ", block_name, param.name);
+                description.push_str(&format_bytes(&[dt1_data]).to_string());
+                param
+                    .describe(&[dt1_data], &mut description, false)
+                    .unwrap();
+                table_stream.td(format_args!("{}", description));
+            }
+            None => table_stream.td(format_args!("(none)")),
+        }
+        table_stream.end_tr();
+    }
+}
+
+pub fn list_lints(table_stream: &mut impl TableStream, data: &MidiData) {
+    table_stream.th(format_args!("Time"));
+    table_stream.th(format_args!("Event (raw)"));
+    table_stream.th(format_args!("Problem"));
+    table_stream.end_tr();
+
+    for lint in lint_sysexes(data) {
+        table_stream.td(format_args!("{}", lint.time));
+        table_stream.td(format_args!(
+            "{}",
+            format_bytes(&data.other_events[lint.index].1)
+        ));
+        table_stream.td(format_args!("{}", lint.problem));
+        table_stream.end_tr();
+    }
+}
+
+pub fn list_compatibility(
+    table_stream: &mut impl TableStream,
+    data: &MidiData,
+    devices: &[&'static Device],
+) {
+    table_stream.th(format_args!("Device"));
+    table_stream.th(format_args!("Time"));
+    table_stream.th(format_args!("Event (raw)"));
+    table_stream.th(format_args!("Problem"));
+    table_stream.end_tr();
+
+    for finding in check_compatibility(data, devices) {
+        table_stream.td(format_args!("{}", finding.device.name));
+        table_stream.td(format_args!("{}", finding.time));
+        match finding.incompatibility.sysex_index() {
+            Some(index) => table_stream.td(format_args!(
+                "{}",
+                format_bytes(&data.other_events[index].1)
+            )),
+            None => table_stream.td(format_args!("")),
+        }
+        table_stream.td(format_args!("{}", finding.incompatibility));
+        table_stream.end_tr();
+    }
+}
+
+/// List the guesses from [guess_target], most likely first, with the evidence
+/// for each. The guess and its score are only in the first row for it.
+pub fn list_target_guesses(table_stream: &mut impl TableStream, data: &MidiData) {
+    table_stream.th(format_args!("Written for"));
+    table_stream.th(format_args!("Score"));
+    table_stream.th(format_args!("Time"));
+    table_stream.th(format_args!("Evidence"));
+    table_stream.end_tr();
+
+    for guess in guess_target(data) {
+        for (i, evidence) in guess.evidence.iter().enumerate() {
+            if i == 0 {
+                table_stream.td(format_args!("{}", guess.target));
+                table_stream.td(format_args!("{}", guess.score));
+            } else {
+                table_stream.td(format_args!(""));
+                table_stream.td(format_args!(""));
+            }
+            table_stream.td(format_args!("{}", evidence.time));
+            if evidence.count > 1 {
+                table_stream.td(format_args!(
+                    "{} (×{})",
+                    evidence.description, evidence.count
+                ));
+            } else {
+                table_stream.td(format_args!("{}", evidence.description));
+            }
+            table_stream.end_tr();
+        }
+    }
+}
+
 #[allow(clippy::result_unit_err)]
 pub fn decode_sysex(out_string: &mut String, in_sysex: &str) -> Result<Vec<u8>, ()> {
+    let sysex_bytes = decode_hex_bytes(out_string, in_sysex)?;
+    validate_sysex(out_string, &sysex_bytes)?;
+    Ok(sysex_bytes)
+}
+
+/// Like [decode_sysex], but for one or more SysExes, e.g. one per line as
+/// written by a generator that sets a parameter on several parts. Each SysEx
+/// ends at its `F7h`, so line breaks and blank lines don't matter.
+#[allow(clippy::result_unit_err)]
+pub fn decode_sysexes(out_string: &mut String, in_sysexes: &str) -> Result<Vec<Vec<u8>>, ()> {
+    use std::fmt::Write;
+
+    let bytes = decode_hex_bytes(out_string, in_sysexes)?;
+    if bytes.is_empty() {
+        write!(out_string, "Error: No SysEx given").unwrap();
+        return Err(());
+    }
+    bytes
+        .split_inclusive(|&byte| byte == 0xF7)
+        .map(|sysex_bytes| {
+            validate_sysex(out_string, sysex_bytes)?;
+            Ok(sysex_bytes.to_vec())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn test_decode_sysexes() {
+    use crate::state::GS_RESET;
+
+    let mut out_string = String::new();
+    let gm_on = [0xF0, 0x7E, 0x7F, 0x09, 0x01, 0xF7];
+    assert_eq!(
+        decode_sysexes(
+            &mut out_string,
+            "F0 41 10 42 12 40 00 7F 00 41 F7\n\n  F0h 7Eh 7Fh\n09h 01h F7h\n"
+        ),
+        Ok(vec![GS_RESET.to_vec(), gm_on.to_vec()])
+    );
+    assert!(out_string.is_empty());
+    assert!(decode_sysexes(&mut out_string, "F0 7E 7F 09 01 F7 F0 41").is_err());
+    assert!(decode_sysexes(&mut out_string, "\n").is_err());
+}
+
+fn decode_hex_bytes(out_string: &mut String, in_sysex: &str) -> Result<Vec<u8>, ()> {
     use std::fmt::Write;
 
     let mut sysex_bytes = Vec::with_capacity(in_sysex.len() / 2);
@@ -383,29 +782,310 @@ pub fn decode_sysex(out_string: &mut String, in_sysex: &str) -> Result<Vec<u8>,
 
         sysex_bytes.push(u8::from_str_radix(hex_byte, 16).unwrap());
     }
+    Ok(sysex_bytes)
+}
 
-    if sysex_bytes.first() != Some(&0xF0) || sysex_bytes.last() != Some(&0xF7) {
-        write!(
-            out_string,
-            "Error: not a complete sysex, needs to start with F0h and end with F7h"
-        )
-        .unwrap();
-        return Err(());
+fn validate_sysex(out_string: &mut String, sysex_bytes: &[u8]) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    // The parser knows best what is and isn't a valid SysEx, and gives useful
+    // error messages. An empty SysEx (F0h F7h) is still accepted though, as it
+    // always has been.
+    match parse_sysex(sysex_bytes) {
+        Ok(_) | Err(ParseFailure::TruncatedHeader { .. }) => Ok(()),
+        Err(err) => {
+            write!(out_string, "Error: {}", err).unwrap();
+            Err(())
+        }
     }
+}
+
+/// Output a table listing the labelled byte ranges of a SysEx (see
+/// [crate::sysex::SysExAnnotator]). If the SysEx can't be parsed, the error is
+/// returned and nothing is output.
+pub fn annotate_sysex(
+    table_stream: &mut impl TableStream,
+    sysex_bytes: &[u8],
+) -> Result<(), ParseFailure> {
+    annotate_sysexes(table_stream, &[sysex_bytes])
+}
 
-    if sysex_bytes[1..sysex_bytes.len() - 1]
+/// Like [annotate_sysex], but for several SysExes in one table. If there's
+/// more than one, the first column is the number of the SysEx (counting from
+/// 1) that the byte range is in.
+pub fn annotate_sysexes(
+    table_stream: &mut impl TableStream,
+    sysexes: &[&[u8]],
+) -> Result<(), ParseFailure> {
+    let parsed = sysexes
         .iter()
-        .any(|&byte| byte > 0x7F)
-    {
-        write!(
-            out_string,
-            "Error: contains invalid data bytes, out of range (> 7Fh)"
-        )
-        .unwrap();
+        .map(|sysex_bytes| parse_sysex(sysex_bytes))
+        .collect::<Result<Vec<_>, _>>()?;
+    let numbered = sysexes.len() > 1;
+
+    if numbered {
+        table_stream.th(format_args!("SysEx"));
+    }
+    table_stream.th(format_args!("Offset"));
+    table_stream.th(format_args!("Length"));
+    table_stream.th(format_args!("Bytes"));
+    table_stream.th(format_args!("Kind"));
+    table_stream.th(format_args!("Meaning"));
+    table_stream.end_tr();
+
+    for (idx, (sysex, sysex_bytes)) in parsed.iter().zip(sysexes).enumerate() {
+        for span in sysex.byte_spans() {
+            if numbered {
+                table_stream.td(format_args!("{}", idx + 1));
+            }
+            table_stream.td(format_args!("{}", span.range.start));
+            table_stream.td(format_args!("{}", span.range.len()));
+            table_stream.td(format_args!("{}", format_bytes(&sysex_bytes[span.range])));
+            table_stream.td(format_args!("{}", span.kind.name()));
+            table_stream.td(format_args!("{}", span.label));
+            table_stream.end_tr();
+        }
+    }
+
+    Ok(())
+}
+
+/// Compile setup text (see [crate::sysex::text]) and append the resulting
+/// SysExes to a collection created by [new_sysex_collection]. If there are any
+/// errors, they are written to `out_string`, one per line, and nothing is
+/// added. Warnings are written to `out_string` the same way, but don't stop
+/// the SysExes from being added.
+#[allow(clippy::result_unit_err)]
+pub fn add_text_to_collection(
+    out_string: &mut String,
+    midi_data: &mut MidiData,
+    text: &str,
+) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    match compile_text(text) {
+        Ok((sysexes, warnings)) => {
+            for warning in warnings {
+                writeln!(out_string, "Warning: {}", warning).unwrap();
+            }
+            for sysex in sysexes {
+                add_sysex_to_collection(midi_data, sysex);
+            }
+            Ok(())
+        }
+        Err(errors) => {
+            for error in errors {
+                writeln!(out_string, "Error: {}", error).unwrap();
+            }
+            Err(())
+        }
+    }
+}
+
+/// Pick random values for the parameters chosen by a randomizer text (see
+/// [compile_randomizer]), using `seed`, and append the resulting SysExes to a
+/// collection created by [new_sysex_collection]. If there are any errors, they
+/// are written to `out_string`, one per line, and nothing is added.
+#[allow(clippy::result_unit_err)]
+pub fn add_random_to_collection(
+    out_string: &mut String,
+    midi_data: &mut MidiData,
+    text: &str,
+    seed: u64,
+) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    match compile_randomizer(text, seed) {
+        Ok(sysexes) => {
+            for sysex in sysexes {
+                add_sysex_to_collection(midi_data, sysex);
+            }
+            Ok(())
+        }
+        Err(errors) => {
+            for error in errors {
+                writeln!(out_string, "Error: {}", error).unwrap();
+            }
+            Err(())
+        }
+    }
+}
+
+/// Compile two assignments to the same parameter in setup text (see
+/// [crate::sysex::text]), e.g. `gs part 5 "CHORUS SEND LEVEL" = 0` and
+/// `gs part 5 "CHORUS SEND LEVEL" = 127`, and insert a ramp from the first
+/// value to the second into `midi_data` over `ticks` (see [insert_ramp]). The
+/// number of DT1s inserted, or the errors, are written to `out_string`.
+#[allow(clippy::result_unit_err)]
+pub fn add_ramp(
+    out_string: &mut String,
+    midi_data: &mut MidiData,
+    from_line: &str,
+    to_line: &str,
+    ticks: RangeInclusive<AbsoluteTime>,
+    curve: Curve,
+    rate: f64,
+) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    let mut compile = |line: &str| match compile_text(line) {
+        Ok((sysexes, _)) if sysexes.len() == 1 => Some(sysexes.into_iter().next().unwrap()),
+        Ok(_) => {
+            writeln!(out_string, "Error: Expected one assignment: {:?}", line).unwrap();
+            None
+        }
+        Err(errors) => {
+            for error in errors {
+                writeln!(out_string, "Error: {}", error.message).unwrap();
+            }
+            None
+        }
+    };
+    let (Some(from_sysex), Some(to_sysex)) = (compile(from_line), compile(to_line)) else {
         return Err(());
+    };
+
+    match Ramp::from_dt1s(
+        &from_sysex,
+        &to_sysex,
+        *ticks.start(),
+        *ticks.end(),
+        curve,
+        rate,
+    ) {
+        Ok(ramp) => {
+            let count = insert_ramp(midi_data, &ramp);
+            writeln!(out_string, "Inserted {} DT1(s) for the ramp.", count).unwrap();
+            Ok(())
+        }
+        Err(message) => {
+            writeln!(out_string, "Error: {}", message).unwrap();
+            Err(())
+        }
     }
+}
 
-    Ok(sysex_bytes)
+/// Change the device ID of every Roland SysEx in `midi_data` to `device_id`
+/// (see [retarget_device_id]), and write how many were changed and skipped, or
+/// the error, to `out_string`.
+#[allow(clippy::result_unit_err)]
+pub fn retarget_collection(
+    out_string: &mut String,
+    midi_data: &mut MidiData,
+    device_id: DeviceId,
+) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    match retarget_device_id(midi_data, device_id) {
+        Ok(report) => {
+            writeln!(
+                out_string,
+                "Changed the device ID of {} SysEx(es) to {:02X}h.",
+                report.changed, device_id
+            )
+            .unwrap();
+            if report.skipped != 0 {
+                writeln!(
+                    out_string,
+                    "Skipped {} SysEx(es) for models whose device ID can't be changed.",
+                    report.skipped
+                )
+                .unwrap();
+            }
+            Ok(())
+        }
+        Err(message) => {
+            writeln!(out_string, "Error: {}", message).unwrap();
+            Err(())
+        }
+    }
+}
+
+/// Copy the settings of GS parts onto other parts in `midi_data` at `time`
+/// (see [copy_parts]), for the synth with the device ID `device_id`. `moves`
+/// are pairs of part numbers to copy from and to, e.g. `[(2, 4), (4, 2)]`
+/// swaps Parts 2 and 4. The number of messages inserted, or the error, is
+/// written to `out_string`.
+#[allow(clippy::result_unit_err)]
+pub fn copy_part_settings(
+    out_string: &mut String,
+    midi_data: &mut MidiData,
+    device_id: DeviceId,
+    time: AbsoluteTime,
+    moves: &[(u8, u8)],
+) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    match copy_parts(midi_data, device_id, time, moves) {
+        Ok(count) => {
+            writeln!(
+                out_string,
+                "Inserted {} message(s) to copy the part settings.",
+                count
+            )
+            .unwrap();
+            Ok(())
+        }
+        Err(message) => {
+            writeln!(out_string, "Error: {}", message).unwrap();
+            Err(())
+        }
+    }
+}
+
+/// Compile a single assignment with a value in some unit (see
+/// [compile_unit_assignment]), e.g. `gs part 1 "PITCH FINE TUNE" = +50 cents`,
+/// and write which data byte was picked, how far off it is, and the SysEx to
+/// `out_string`.
+#[allow(clippy::result_unit_err)]
+pub fn explain_unit_assignment(out_string: &mut String, line: &str) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    match compile_unit_assignment(line) {
+        Ok((sysex, param, unit_value)) => {
+            writeln!(out_string, "{} = {}", param.name, unit_value).unwrap();
+            writeln!(out_string, "{}", format_bytes(&sysex)).unwrap();
+            Ok(())
+        }
+        Err(message) => {
+            writeln!(out_string, "Error: {}", message).unwrap();
+            Err(())
+        }
+    }
+}
+
+/// Fix the checksum of a SysEx decoded from `in_sysex` (see
+/// [crate::sysex::roland::fix_checksum]) and write what was fixed, if anything,
+/// and the resulting SysEx to `out_string`.
+#[allow(clippy::result_unit_err)]
+pub fn repair_sysex(out_string: &mut String, in_sysex: &str) -> Result<(), ()> {
+    use std::fmt::Write;
+
+    let mut sysex_bytes = decode_sysex(out_string, in_sysex)?;
+    match fix_checksum(&mut sysex_bytes) {
+        Some((wrong, correct)) => writeln!(
+            out_string,
+            "Fixed checksum: {} => {}",
+            format_bytes(&[wrong]),
+            format_bytes(&[correct])
+        ),
+        None => writeln!(out_string, "Nothing to fix."),
+    }
+    .unwrap();
+    writeln!(out_string, "{}", format_bytes(&sysex_bytes)).unwrap();
+    Ok(())
+}
+
+/// Write the SysExes in `data` as text in the canonical form (see
+/// [describe_canonically]), one per line. Meta events are skipped.
+pub fn write_sysexes_as_text(out_string: &mut String, data: &MidiData) {
+    for (_, ref bytes) in &data.other_events {
+        if bytes.first() == Some(&0xFF) {
+            continue;
+        }
+        describe_canonically(bytes, out_string).unwrap();
+        out_string.push('\n');
+    }
 }
 
 pub fn check_sysex(out_string: &mut String, sysex_bytes: &[u8]) {
@@ -416,7 +1096,7 @@ pub fn check_sysex(out_string: &mut String, sysex_bytes: &[u8]) {
             write!(out_string, "SysEx: {}", sysex).unwrap();
         }
         Err(err) => {
-            write!(out_string, "Error: {:?}", err).unwrap();
+            write!(out_string, "Error: {}", err).unwrap();
         }
     }
 }
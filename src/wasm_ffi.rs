@@ -134,12 +134,7 @@ pub unsafe extern "C" fn read_midi_and_log(
 /// which is opaque to non-Rust code and must be freed with [midi_data_free].
 #[export_name = "SoundPalette_midi_data_new"]
 pub unsafe extern "C" fn midi_data_new() -> *mut crate::midi::MidiData {
-    Box::leak(Box::new(crate::midi::MidiData {
-        // Something divisible by 10 is desirable, see midi_data_add_sysex.
-        division: crate::midi::Division::TicksPerQuarterNote(120),
-        channel_messages: Vec::new(),
-        other_events: Vec::new(),
-    }))
+    Box::leak(Box::new(crate::ui::new_sysex_collection()))
 }
 
 /// Outputs a table of "other events" from a [crate::midi::MidiData] returned
@@ -159,10 +154,182 @@ pub unsafe extern "C" fn midi_data_list_other_events(
     )
 }
 
+/// Outputs a table of the parameters that differ from their defaults after
+/// replaying a [crate::midi::MidiData] up to and including the time `until`,
+/// for a synth with the device ID `device_id`. See [crate::ui::list_synth_state]
+/// and [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_list_synth_state"]
+pub unsafe extern "C" fn midi_data_list_synth_state(
+    string: &mut String,
+    midi_data: &crate::midi::MidiData,
+    device_id: u8,
+    until: u32,
+) {
+    crate::ui::list_synth_state(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+        device_id,
+        until,
+    )
+}
+
+/// Outputs a table of each parameter that changes over a
+/// [crate::midi::MidiData], for a synth with the device ID `device_id`, with
+/// each time and value it changes to. See [crate::ui::list_parameter_timeline]
+/// and [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_list_parameter_timeline"]
+pub unsafe extern "C" fn midi_data_list_parameter_timeline(
+    string: &mut String,
+    midi_data: &crate::midi::MidiData,
+    device_id: u8,
+) {
+    crate::ui::list_parameter_timeline(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+        device_id,
+    )
+}
+
+/// Outputs a table of problems with the SysExes in a [crate::midi::MidiData].
+/// See [crate::ui::list_lints] and [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_list_lints"]
+pub unsafe extern "C" fn midi_data_list_lints(
+    string: &mut String,
+    midi_data: &crate::midi::MidiData,
+) {
+    crate::ui::list_lints(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+    )
+}
+
+/// Fixes the checksums of the SysExes in a [crate::midi::MidiData] and outputs
+/// a table of the fixes. See [crate::ui::repair_and_list_checksums] and
+/// [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_repair_checksums"]
+pub unsafe extern "C" fn midi_data_repair_checksums(
+    string: &mut String,
+    midi_data: &mut crate::midi::MidiData,
+) {
+    crate::ui::repair_and_list_checksums(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+    )
+}
+
+/// Outputs a table of the RPN and NRPN Data Entries in a
+/// [crate::midi::MidiData]. See [crate::ui::list_data_entries] and
+/// [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_list_data_entries"]
+pub unsafe extern "C" fn midi_data_list_data_entries(
+    string: &mut String,
+    midi_data: &crate::midi::MidiData,
+) {
+    crate::ui::list_data_entries(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+    )
+}
+
+/// Replaces RPNs and NRPNs in a [crate::midi::MidiData] with equivalent GS
+/// DT1s for the device ID `device_id`, returning how many were replaced. See
+/// [crate::transform::convert_nrpns_to_sysex].
+#[export_name = "SoundPalette_midi_data_convert_nrpns_to_sysex"]
+pub unsafe extern "C" fn midi_data_convert_nrpns_to_sysex(
+    midi_data: &mut crate::midi::MidiData,
+    device_id: u8,
+) -> usize {
+    crate::transform::convert_nrpns_to_sysex(midi_data, device_id)
+}
+
+/// Replaces GS DT1s in a [crate::midi::MidiData] with equivalent RPNs and
+/// NRPNs, returning how many were replaced. See
+/// [crate::transform::convert_sysex_to_nrpns].
+#[export_name = "SoundPalette_midi_data_convert_sysex_to_nrpns"]
+pub unsafe extern "C" fn midi_data_convert_sysex_to_nrpns(
+    midi_data: &mut crate::midi::MidiData,
+) -> usize {
+    crate::transform::convert_sysex_to_nrpns(midi_data)
+}
+
+/// Replaces GS DT1s in a [crate::midi::MidiData] with equivalent SC-7 DT1s,
+/// and outputs a table of the parameters that have no equivalent. See
+/// [crate::transform::translate_gs_to_sc_7] and [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_translate_gs_to_sc_7"]
+pub unsafe extern "C" fn midi_data_translate_gs_to_sc_7(
+    string: &mut String,
+    midi_data: &mut crate::midi::MidiData,
+) {
+    crate::ui::list_untranslated(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        &crate::transform::translate_gs_to_sc_7(midi_data),
+    )
+}
+
+/// Replaces SC-7 DT1s in a [crate::midi::MidiData] with equivalent GS DT1s,
+/// and outputs a table of the parameters that have no equivalent. See
+/// [crate::transform::translate_sc_7_to_gs] and [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_translate_sc_7_to_gs"]
+pub unsafe extern "C" fn midi_data_translate_sc_7_to_gs(
+    string: &mut String,
+    midi_data: &mut crate::midi::MidiData,
+) {
+    crate::ui::list_untranslated(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        &crate::transform::translate_sc_7_to_gs(midi_data),
+    )
+}
+
+/// Replaces the GS setup in a [crate::midi::MidiData] with its Yamaha XG
+/// equivalent, and outputs a table of the parameters that have no equivalent
+/// or only a near one. See [crate::transform::convert_gs_to_xg] and
+/// [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_convert_gs_to_xg"]
+pub unsafe extern "C" fn midi_data_convert_gs_to_xg(
+    string: &mut String,
+    midi_data: &mut crate::midi::MidiData,
+) {
+    crate::ui::list_untranslated(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        &crate::transform::convert_gs_to_xg(midi_data),
+    )
+}
+
+/// Outputs a table of what in a [crate::midi::MidiData] won't work on each of
+/// the devices in [crate::compat::DEVICES]. See [crate::ui::list_compatibility]
+/// and [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_list_compatibility"]
+pub unsafe extern "C" fn midi_data_list_compatibility(
+    string: &mut String,
+    midi_data: &crate::midi::MidiData,
+) {
+    let devices: Vec<_> = crate::compat::DEVICES.iter().collect();
+    crate::ui::list_compatibility(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+        &devices,
+    )
+}
+
+/// Outputs a table of guesses at what a [crate::midi::MidiData] was written
+/// for, with the evidence. See [crate::ui::list_target_guesses] and
+/// [midi_data_list_other_events].
+#[export_name = "SoundPalette_midi_data_list_target_guesses"]
+pub unsafe extern "C" fn midi_data_list_target_guesses(
+    string: &mut String,
+    midi_data: &crate::midi::MidiData,
+) {
+    crate::ui::list_target_guesses(
+        &mut crate::ui::NullTerminatedStringTableStream::new(string),
+        midi_data,
+    )
+}
+
 /// Adds a SysEx (decoded from a string consisting of `in_sysex_len` UTF-8 bytes
 /// starting at `in_sysex_bytes`) to a [crate::midi::MidiData] returned by
-/// [midi_data_new]. If the SysEx can't be decoded, an error is appended to a
-/// string allocated with [string_new] and [false] is returned.
+/// [midi_data_new]. Several SysExes can be given, e.g. one per line. If a SysEx
+/// can't be decoded, an error is appended to a string allocated with
+/// [string_new], nothing is added and [false] is returned.
 #[export_name = "SoundPalette_midi_data_add_sysex"]
 pub unsafe extern "C" fn midi_data_add_sysex(
     midi_data: &mut crate::midi::MidiData,
@@ -173,29 +340,141 @@ pub unsafe extern "C" fn midi_data_add_sysex(
     let in_sysex = slice_for_bytes(in_sysex_bytes, in_sysex_len);
     let in_sysex = std::str::from_utf8(in_sysex).unwrap();
 
-    let Ok(sysex_bytes) = crate::ui::decode_sysex(out_string, in_sysex) else {
+    let Ok(sysexes) = crate::ui::decode_sysexes(out_string, in_sysex) else {
         return false;
     };
 
-    // SC-55mkII and SC-7 manuals both say a GM or GS reset takes about 50ms to
-    // complete. Therefore, let's put 50ms between all SysEx messages.
-    // TODO: Use shorter delay (40ms or 20ms as appropriate) when no reset is
-    //       in the list.
-    // Assumption: All existing events have been added by this function, so they
-    //             are all in order, and there is no tempo or time signature
-    //             meta event to change from the default of 120bpm, 4/4.
-    let crate::midi::Division::TicksPerQuarterNote(ticks_per_quarter_note) = midi_data.division
-    else {
-        panic!();
+    for sysex_bytes in sysexes {
+        crate::ui::add_sysex_to_collection(midi_data, sysex_bytes);
+    }
+    true
+}
+
+/// Compiles setup text (see [crate::sysex::text]) consisting of `in_text_len`
+/// UTF-8 bytes starting at `in_text_bytes`, and adds the resulting SysExes to a
+/// [crate::midi::MidiData] returned by [midi_data_new]. If there are errors,
+/// they are appended to a string allocated with [string_new] and [false] is
+/// returned. Warnings are appended to the string too, but [true] is returned.
+#[export_name = "SoundPalette_midi_data_add_text"]
+pub unsafe extern "C" fn midi_data_add_text(
+    midi_data: &mut crate::midi::MidiData,
+    out_string: &mut String,
+    in_text_bytes: *const u8,
+    in_text_len: usize,
+) -> bool {
+    let in_text = slice_for_bytes(in_text_bytes, in_text_len);
+    let in_text = std::str::from_utf8(in_text).unwrap();
+
+    crate::ui::add_text_to_collection(out_string, midi_data, in_text).is_ok()
+}
+
+/// Picks random values for the parameters chosen by a randomizer text (see
+/// [crate::sysex::text::compile_randomizer]) consisting of `in_text_len` UTF-8
+/// bytes starting at `in_text_bytes`, using `seed`, and adds the resulting
+/// SysExes to a [crate::midi::MidiData] returned by [midi_data_new]. If there
+/// are errors, they are appended to a string allocated with [string_new] and
+/// [false] is returned.
+#[export_name = "SoundPalette_midi_data_add_random"]
+pub unsafe extern "C" fn midi_data_add_random(
+    midi_data: &mut crate::midi::MidiData,
+    out_string: &mut String,
+    in_text_bytes: *const u8,
+    in_text_len: usize,
+    seed: u32,
+) -> bool {
+    let in_text = slice_for_bytes(in_text_bytes, in_text_len);
+    let in_text = std::str::from_utf8(in_text).unwrap();
+
+    crate::ui::add_random_to_collection(out_string, midi_data, in_text, seed.into()).is_ok()
+}
+
+/// Compiles two lines of setup text for the same parameter (see
+/// [crate::ui::add_ramp]), each consisting of UTF-8 bytes, and inserts a ramp
+/// between their values into a [crate::midi::MidiData] from the tick `start`
+/// to the tick `end`, at no more than `rate` DT1s per second. The number of
+/// DT1s inserted, or the errors, are appended to a string allocated with
+/// [string_new]. Returns [false] if there were errors.
+#[export_name = "SoundPalette_midi_data_add_ramp"]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn midi_data_add_ramp(
+    midi_data: &mut crate::midi::MidiData,
+    out_string: &mut String,
+    in_from_bytes: *const u8,
+    in_from_len: usize,
+    in_to_bytes: *const u8,
+    in_to_len: usize,
+    start: u32,
+    end: u32,
+    exponential: bool,
+    rate: f64,
+) -> bool {
+    let in_from = slice_for_bytes(in_from_bytes, in_from_len);
+    let in_from = std::str::from_utf8(in_from).unwrap();
+    let in_to = slice_for_bytes(in_to_bytes, in_to_len);
+    let in_to = std::str::from_utf8(in_to).unwrap();
+
+    let curve = if exponential {
+        crate::transform::Curve::Exponential
+    } else {
+        crate::transform::Curve::Linear
     };
-    let ticks_per_quarter_note: crate::midi::AbsoluteTime = ticks_per_quarter_note.into();
-    let new_event_time = if let Some(&(last_event_time, _)) = midi_data.other_events.last() {
-        last_event_time + ((ticks_per_quarter_note * 120) / 60).div_ceil(1000 / 50)
+    crate::ui::add_ramp(
+        out_string,
+        midi_data,
+        in_from,
+        in_to,
+        start..=end,
+        curve,
+        rate,
+    )
+    .is_ok()
+}
+
+/// Copies the settings of the GS part numbered `from` onto the part numbered
+/// `to` in a [crate::midi::MidiData], as they are at the tick `time`, or swaps
+/// the two parts if `swap` is [true] (see [crate::ui::copy_part_settings]).
+/// The number of messages inserted, or the error, is appended to a string
+/// allocated with [string_new]. Returns [false] if there was an error.
+#[export_name = "SoundPalette_midi_data_copy_part"]
+pub unsafe extern "C" fn midi_data_copy_part(
+    midi_data: &mut crate::midi::MidiData,
+    out_string: &mut String,
+    device_id: u8,
+    time: u32,
+    from: u8,
+    to: u8,
+    swap: bool,
+) -> bool {
+    let moves: &[(u8, u8)] = if swap {
+        &[(from, to), (to, from)]
     } else {
-        0
+        &[(from, to)]
     };
-    midi_data.other_events.push((new_event_time, sysex_bytes));
-    true
+    crate::ui::copy_part_settings(out_string, midi_data, device_id, time, moves).is_ok()
+}
+
+/// Appends the SysExes from a [crate::midi::MidiData] to a string allocated
+/// with [string_new], as text that can be turned back into the same SysExes
+/// with [midi_data_add_text].
+#[export_name = "SoundPalette_midi_data_write_text"]
+pub unsafe extern "C" fn midi_data_write_text(
+    out_string: &mut String,
+    midi_data: &crate::midi::MidiData,
+) {
+    crate::ui::write_sysexes_as_text(out_string, midi_data)
+}
+
+/// Changes the device ID of every Roland SysEx in a [crate::midi::MidiData] to
+/// `device_id`. How many SysExes were changed and skipped, or the error, is
+/// appended to a string allocated with [string_new]. Returns [false] if there
+/// was an error.
+#[export_name = "SoundPalette_midi_data_retarget_device_id"]
+pub unsafe extern "C" fn midi_data_retarget_device_id(
+    midi_data: &mut crate::midi::MidiData,
+    out_string: &mut String,
+    device_id: u8,
+) -> bool {
+    crate::ui::retarget_collection(out_string, midi_data, device_id).is_ok()
 }
 
 /// Clears the "other events" from a [crate::midi::MidiData] returned by
@@ -232,7 +511,8 @@ pub unsafe extern "C" fn midi_data_free(midi: *mut crate::midi::MidiData) {
 }
 
 /// Checks an ASCII SysEx string consisting of `in_sysex_len` UTF-8 bytes
-/// starting at `in_sysex_bytes`, appending the result to a string.
+/// starting at `in_sysex_bytes`, appending the result to a string. If there
+/// are several SysExes, each is checked, and the results are one per line.
 #[export_name = "SoundPalette_check_sysex"]
 pub unsafe extern "C" fn check_sysex(
     out_string: &mut String,
@@ -242,8 +522,83 @@ pub unsafe extern "C" fn check_sysex(
     let in_sysex = slice_for_bytes(in_sysex_bytes, in_sysex_len);
     let in_sysex = std::str::from_utf8(in_sysex).unwrap();
 
-    if let Ok(ref sysex_bytes) = crate::ui::decode_sysex(out_string, in_sysex) {
-        crate::ui::check_sysex(out_string, sysex_bytes);
+    if let Ok(sysexes) = crate::ui::decode_sysexes(out_string, in_sysex) {
+        for (idx, sysex_bytes) in sysexes.iter().enumerate() {
+            if idx != 0 {
+                out_string.push('\n');
+            }
+            crate::ui::check_sysex(out_string, sysex_bytes);
+        }
+    }
+}
+
+/// Fixes the checksum of an ASCII SysEx string consisting of `in_sysex_len`
+/// UTF-8 bytes starting at `in_sysex_bytes`, appending what was fixed and the
+/// resulting SysEx (or an error) to a string allocated with [string_new].
+/// Returns [false] on error. See [crate::ui::repair_sysex].
+#[export_name = "SoundPalette_repair_sysex"]
+pub unsafe extern "C" fn repair_sysex(
+    out_string: &mut String,
+    in_sysex_bytes: *const u8,
+    in_sysex_len: usize,
+) -> bool {
+    let in_sysex = slice_for_bytes(in_sysex_bytes, in_sysex_len);
+    let in_sysex = std::str::from_utf8(in_sysex).unwrap();
+
+    crate::ui::repair_sysex(out_string, in_sysex).is_ok()
+}
+
+/// Compiles a single assignment with a value in some unit (e.g. `-1200 cents`,
+/// see [crate::sysex::text]) consisting of `in_text_len` UTF-8 bytes starting
+/// at `in_text_bytes`, and appends the nearest value, its rounding error and
+/// the SysEx (or an error) to a string allocated with [string_new]. Returns
+/// [false] on error.
+#[export_name = "SoundPalette_explain_unit_assignment"]
+pub unsafe extern "C" fn explain_unit_assignment(
+    out_string: &mut String,
+    in_text_bytes: *const u8,
+    in_text_len: usize,
+) -> bool {
+    let in_text = slice_for_bytes(in_text_bytes, in_text_len);
+    let in_text = std::str::from_utf8(in_text).unwrap();
+
+    crate::ui::explain_unit_assignment(out_string, in_text).is_ok()
+}
+
+/// Decodes an ASCII SysEx string consisting of `in_sysex_len` UTF-8 bytes
+/// starting at `in_sysex_bytes`, and appends a table of its labelled byte
+/// ranges in [crate::ui::NullTerminatedStringTableStream] format to a string
+/// allocated with [string_new]. Several SysExes are annotated in one table
+/// (see [crate::ui::annotate_sysexes]). If a SysEx can't be decoded or parsed,
+/// an error is appended instead and [false] is returned.
+#[export_name = "SoundPalette_annotate_sysex"]
+pub unsafe extern "C" fn annotate_sysex(
+    out_string: &mut String,
+    in_sysex_bytes: *const u8,
+    in_sysex_len: usize,
+) -> bool {
+    let in_sysex = slice_for_bytes(in_sysex_bytes, in_sysex_len);
+    let in_sysex = std::str::from_utf8(in_sysex).unwrap();
+
+    let Ok(sysexes) = crate::ui::decode_sysexes(out_string, in_sysex) else {
+        return false;
+    };
+    let sysexes: Vec<&[u8]> = sysexes.iter().map(Vec::as_slice).collect();
+
+    let mut table = String::new();
+    match crate::ui::annotate_sysexes(
+        &mut crate::ui::NullTerminatedStringTableStream::new(&mut table),
+        &sysexes,
+    ) {
+        Ok(()) => {
+            out_string.push_str(&table);
+            true
+        }
+        Err(err) => {
+            use std::fmt::Write;
+            write!(out_string, "Error: {}", err).unwrap();
+            false
+        }
     }
 }
 
@@ -270,7 +625,8 @@ pub unsafe extern "C" fn sysex_generator_menu_stack_list_items(
 /// Descend in a [SysExGeneratorMenuStack]'s menu by item index, pushing the
 /// result to its stack. If the result is a SysEx generator, it is immediately
 /// popped from the stack, a SysEx is generated in hexadecimal form and appended
-/// to the String, and [true] is returned. If the result is a new menu, [false]
+/// to the String (one per line, if it generates several, e.g. for several
+/// parts), and [true] is returned. If the result is a new menu, [false]
 /// is returned.
 #[export_name = "SoundPalette_sysex_generator_menu_stack_push"]
 pub unsafe extern "C" fn sysex_generator_menu_stack_push(
@@ -281,11 +637,13 @@ pub unsafe extern "C" fn sysex_generator_menu_stack_push(
     let have_command = stack.0.push(item_idx);
     if have_command {
         let sysex_generator = stack.0.pop_command();
-        let mut sysex_bytes = Vec::new();
-        sysex_generator.generate(&mut sysex_bytes);
-
         use std::fmt::Write;
-        write!(out_string, "{}", crate::midi::format_bytes(&sysex_bytes)).unwrap();
+        for (idx, sysex) in sysex_generator.generate_all().iter().enumerate() {
+            if idx != 0 {
+                writeln!(out_string).unwrap();
+            }
+            write!(out_string, "{}", crate::midi::format_bytes(sysex)).unwrap();
+        }
     }
     have_command
 }